@@ -0,0 +1,3155 @@
+//! Comandos avulsos (`open`, `export`, `sync` etc.) e o loop principal do agente.
+
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use quick_xml::{Reader as XmlReader, events::Event};
+use reqwest::{Client, ClientBuilder, NoProxy, Proxy};
+use sled::Db;
+use std::{
+	collections::{BTreeSet, HashMap},
+	error::Error,
+	fs,
+	hash::{Hash, Hasher},
+	io::{self, Write},
+	path::{Path, PathBuf},
+	process::{Command, ExitCode},
+	sync::{Arc, Mutex, atomic::Ordering},
+	time::Duration as StdDuration,
+	time::Instant,
+};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+use tokio::time;
+use tracing::{error, info, warn};
+use url::Url;
+
+use crate::{
+	BOLD, BOLD_GREEN, BOLD_RED, BOLD_YELLOW, MODO_DAEMON, RESET, SAIDA_JSON,
+	config::*,
+	emitir_evento, idioma,
+	llm::*,
+	painel::*,
+	pipeline::{etapas::*, *},
+	rastreio,
+	saidas::*,
+	sincronizacao::*,
+	socks,
+	sources::{
+		Coletor,
+		crawlers::metadados_pagina,
+		feeds::{
+			baixar_feed, com_autenticacao, descobrir_feeds_html, ler_canal, noticia_do_item_rss,
+			parece_html,
+		},
+	},
+	store::*,
+	systemd::{self, intervalo_watchdog, unidade},
+	tela::{ClienteApi, rodar_tela},
+};
+
+// =================================================================
+// COMANDOS DE LINHA DE COMANDO
+// =================================================================
+
+/// Agente de notícias: coleta as fontes, filtra e resume com um LLM local e entrega os itens
+/// relevantes. Sem comando, roda o agente (`run`).
+#[derive(Parser)]
+#[command(name = "filterflow", version)]
+pub struct Argumentos {
+	/// Arquivo de configuração [padrão: ./filterflow_config.toml, se existir, ou
+	/// ~/.config/filterflow/filterflow_config.toml]
+	#[arg(long, global = true, value_name = "ARQUIVO")]
+	pub config: Option<PathBuf>,
+
+	/// Pasta do banco sled [padrão: ./filterflow_data, se existir, ou
+	/// ~/.local/share/filterflow/filterflow_data]
+	#[arg(long, global = true, value_name = "PASTA", alias = "db-path")]
+	pub db: Option<String>,
+
+	/// Abre uma cópia temporária do banco, sem bloquear nem alterar o original (só consultas)
+	#[arg(long, global = true)]
+	pub read_only: bool,
+
+	/// Formato do stdout do agente: log legível ou eventos JSON, um por linha
+	#[arg(long, global = true, value_enum, default_value_t = FormatoSaida::Text)]
+	pub output: FormatoSaida,
+
+	/// Sem comando, o mesmo que `once`
+	#[arg(long)]
+	pub once: bool,
+
+	/// Roda o agente como serviço do systemd: log sem cores (com a prioridade de cada linha no
+	/// journal), aviso de prontidão e watchdog
+	#[arg(long)]
+	pub daemon: bool,
+
+	/// Roda o ciclo completo numa cópia descartável do banco, sem entregar nada às saídas
+	/// (só `run` e `once`)
+	#[arg(long, global = true)]
+	pub dry_run: bool,
+
+	#[command(subcommand)]
+	pub comando: Option<Comando>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum FormatoSaida {
+	Text,
+	Json,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum FormatoExportacao {
+	Markdown,
+	Json,
+}
+
+#[derive(Subcommand)]
+pub enum AcaoFeeds {
+	/// Lista os feeds configurados
+	List,
+	/// Acrescenta um feed, depois de baixá-lo e conferir que é um RSS válido
+	Add { nome: String, url: String },
+	/// Remove o feed com esse nome ou URL
+	Remove {
+		/// Nome ou URL do feed
+		feed: String,
+	},
+}
+
+#[derive(Subcommand)]
+pub enum Comando {
+	/// Roda o agente continuamente (padrão)
+	Run,
+	/// Roda um único ciclo e termina (para cron ou timers do systemd)
+	Once,
+	/// Pergunta o endpoint do LLM, o modelo, o intervalo, os feeds e os temas de interesse e
+	/// grava uma configuração inicial comentada
+	Init {
+		/// Sobrescreve o arquivo se ele já existir
+		#[arg(long)]
+		force: bool,
+	},
+	/// Pede ao agente em execução que recarregue a configuração e comece um ciclo agora
+	Poke,
+	/// Mostra uma unidade do systemd que roda o agente com `--daemon`
+	SystemdUnit {
+		/// Unidade de usuário (~/.config/systemd/user) em vez de sistema
+		#[arg(long)]
+		user: bool,
+	},
+	/// Valida o arquivo de configuração e resume o que está configurado
+	CheckConfig {
+		/// Só valida o arquivo, sem consultar o LLM nem as URLs das fontes
+		#[arg(long)]
+		offline: bool,
+	},
+	/// Contagens do banco: itens processados, arquivados, filas e métricas
+	Stats,
+	/// Roda só o filtro do LLM sobre um título e uma descrição, sem baixar nada
+	TestFilter {
+		#[arg(long)]
+		title: String,
+		#[arg(long, default_value = "")]
+		description: String,
+	},
+	/// Passa uma página ou feed pelo filtro e pelo resumo atuais e mostra as respostas do LLM
+	TestUrl {
+		/// Página (usa o título e a descrição) ou feed (usa o primeiro item)
+		link: String,
+	},
+	/// Abre no navegador o item <NUMERO> do último ciclo
+	Open { numero: u32 },
+	/// Copia título, link e resumo do item <NUMERO> do último ciclo
+	Copy { numero: u32 },
+	/// Acrescenta à configuração os feeds de um arquivo OPML
+	// `--import-opml <arquivo>`, a grafia de antes dos subcomandos, continua aceita
+	#[command(long_flag_alias = "import-opml")]
+	ImportOpml { arquivo: PathBuf },
+	/// Lista, acrescenta ou remove feeds do arquivo de configuração
+	Feeds {
+		#[command(subcommand)]
+		acao: AcaoFeeds,
+	},
+	/// Regera os resumos dos itens arquivados com o modelo e o prompt atuais
+	Resummarize {
+		/// Só os itens entregues nesse período (ex.: 30d, 12h, 90m)
+		#[arg(long, value_parser = interpretar_duracao)]
+		since: Option<Duration>,
+		/// Modelo de resumo no lugar de [geral].modelo_resumo
+		#[arg(long)]
+		model: Option<String>,
+		/// Limite de chamadas ao LLM por minuto
+		#[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(1..))]
+		por_minuto: u32,
+	},
+	/// Marca o item como já processado, sem entregá-lo
+	MarkRead {
+		/// Link ou chave do item
+		chave: String,
+	},
+	/// Corrige a decisão do filtro sobre um item
+	Reclassify {
+		/// Link ou chave do item
+		chave: String,
+		#[arg(value_parser = ["relevante", "irrelevante"])]
+		classe: String,
+	},
+	/// Remove do banco as chaves do cache de irrelevância e/ou dos itens processados, para que
+	/// voltem a ser avaliados
+	#[command(group(ArgGroup::new("arvores").required(true).multiple(true).args(["irrelevant", "processed"])))]
+	Purge {
+		/// Limpa o cache de irrelevância
+		#[arg(long)]
+		irrelevant: bool,
+		/// Limpa as chaves dos itens processados (eles podem ser entregues de novo)
+		#[arg(long)]
+		processed: bool,
+		/// Só os itens dessa fonte
+		#[arg(long, value_name = "NOME")]
+		feed: Option<String>,
+		/// Só os itens rejeitados ou processados há mais tempo que isso (ex.: 30d, 12h, 90m)
+		#[arg(long, value_parser = interpretar_duracao)]
+		older_than: Option<Duration>,
+	},
+	/// Tira do cache de irrelevância os itens rejeitados desde a data e os passa de novo pelo
+	/// pipeline, como depois de corrigir um prompt que rejeitava notícias por engano
+	Reprocess {
+		/// Só os itens dessa fonte
+		#[arg(long, value_name = "NOME")]
+		feed: Option<String>,
+		/// Data da rejeição a partir da qual reprocessar (ex.: 2026-10-01, ou 3d para os últimos
+		/// três dias)
+		#[arg(long, value_parser = interpretar_instante)]
+		since: DateTime<Utc>,
+	},
+	/// Lista as ações manuais mais recentes
+	Audit {
+		#[arg(default_value_t = 20)]
+		quantidade: usize,
+	},
+	/// Busca nos itens arquivados por título, resumo, descrição, fonte e grupo
+	Search {
+		/// Termos da busca; o item precisa conter todos
+		#[arg(required = true)]
+		termos: Vec<String>,
+		/// Número máximo de itens listados, dos mais recentes para os mais antigos
+		#[arg(long, default_value_t = 20)]
+		limit: usize,
+	},
+	/// Exporta os itens arquivados como notas Markdown, ou o banco inteiro em JSON no stdout
+	Export {
+		/// Pasta das notas (só no formato markdown)
+		pasta: Option<PathBuf>,
+		#[arg(long, value_enum, default_value_t = FormatoExportacao::Markdown)]
+		format: FormatoExportacao,
+		/// Só os itens entregues nesse período (ex.: 30d, 12h, 90m)
+		#[arg(long, value_parser = interpretar_duracao)]
+		since: Option<Duration>,
+	},
+	/// Restaura num banco vazio uma cópia de `export --format json`
+	Import {
+		arquivo: PathBuf,
+		/// Esvazia o banco de destino antes de importar
+		#[arg(long)]
+		replace: bool,
+	},
+	/// Troca com outra instância as chaves dos itens processados e irrelevantes
+	Sync {
+		/// URL do par (padrão: [sincronizacao].par)
+		#[arg(long)]
+		peer: Option<String>,
+		/// Segredo compartilhado (padrão: [sincronizacao].token)
+		#[arg(long)]
+		token: Option<String>,
+	},
+	/// Interface interativa no terminal para o agente em execução (itens, fontes e latência
+	/// do LLM), pela API do painel
+	Tui {
+		/// Endereço do painel (padrão: http://<[painel].endereco>)
+		#[arg(long)]
+		url: Option<String>,
+		/// Token da API (padrão: [painel].token)
+		#[arg(long)]
+		token: Option<String>,
+	},
+}
+
+/// Abre o link no navegador padrão do sistema.
+pub fn abrir_no_navegador(link: &str) -> io::Result<()> {
+	let mut comando = if cfg!(target_os = "windows") {
+		let mut c = Command::new("cmd");
+		c.args(["/C", "start", ""]);
+		c
+	} else if cfg!(target_os = "macos") {
+		Command::new("open")
+	} else {
+		Command::new("xdg-open")
+	};
+	comando.arg(link).spawn()?;
+	Ok(())
+}
+
+/// Localiza o item numerado informado a `open`/`copy`.
+pub fn referencia_numerada(numero: u32) -> Result<Referencia, Box<dyn Error>> {
+	let referencias = referencias_carregar()?;
+	let total = referencias.len();
+	referencias
+		.into_iter()
+		.find(|r| r.numero == numero)
+		.ok_or_else(|| {
+			format!(
+				"Item {} não encontrado no último ciclo ({} itens numerados).",
+				numero, total
+			)
+			.into()
+		})
+}
+
+/// `filterflow open <n>`: abre o item de número <n> do último ciclo.
+pub fn comando_open(numero: u32) -> Result<(), Box<dyn Error>> {
+	let referencia = referencia_numerada(numero)?;
+
+	println!(
+		"Abrindo [{}] {}{}{}\n{}",
+		referencia.numero, BOLD, referencia.titulo, RESET, referencia.link
+	);
+	abrir_no_navegador(&referencia.link)?;
+	Ok(())
+}
+
+/// Copia texto para a área de transferência do sistema.
+pub fn copiar_para_area_de_transferencia(texto: &str) -> Result<(), arboard::Error> {
+	let mut clipboard = arboard::Clipboard::new()?;
+
+	// No Linux o conteúdo pertence ao processo que o definiu: aguardamos até que um
+	// gerenciador de área de transferência o assuma (ou o prazo expire) antes de sair.
+	#[cfg(target_os = "linux")]
+	{
+		use arboard::SetExtLinux;
+		clipboard
+			.set()
+			.wait_until(Instant::now() + StdDuration::from_secs(5))
+			.text(texto)
+	}
+	#[cfg(not(target_os = "linux"))]
+	{
+		clipboard.set_text(texto)
+	}
+}
+
+/// `filterflow copy <n>`: copia título, link e resumo do item <n> do último ciclo.
+pub fn comando_copy(numero: u32) -> Result<(), Box<dyn Error>> {
+	let referencia = referencia_numerada(numero)?;
+
+	let mut texto = format!("{}\n{}", referencia.titulo, referencia.link);
+	if let Some(resumo) = &referencia.resumo {
+		texto.push_str("\n\n");
+		texto.push_str(resumo);
+	}
+
+	copiar_para_area_de_transferencia(&texto)?;
+	println!(
+		"Item [{}] copiado para a área de transferência: {}{}{}",
+		referencia.numero, BOLD, referencia.titulo, RESET
+	);
+	Ok(())
+}
+
+/// Lê os feeds de um arquivo OPML como pares (nome, url). Os nomes das pastas que contêm cada
+/// feed são preservados como prefixo do nome ("Pasta / Feed").
+pub fn parse_opml(data: &[u8]) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+	let mut reader = XmlReader::from_reader(data);
+	let mut buf = Vec::new();
+	let mut pastas: Vec<Option<String>> = Vec::new();
+	let mut feeds = Vec::new();
+
+	loop {
+		let (elemento, vazio) = match reader.read_event_into(&mut buf)? {
+			Event::Start(e) => (e, false),
+			Event::Empty(e) => (e, true),
+			Event::End(e) => {
+				if e.local_name().as_ref().eq_ignore_ascii_case(b"outline") {
+					pastas.pop();
+				}
+				buf.clear();
+				continue;
+			}
+			Event::Eof => break,
+			_ => {
+				buf.clear();
+				continue;
+			}
+		};
+
+		if !elemento
+			.local_name()
+			.as_ref()
+			.eq_ignore_ascii_case(b"outline")
+		{
+			buf.clear();
+			continue;
+		}
+
+		let mut texto = None;
+		let mut titulo = None;
+		let mut xml_url = None;
+		for attr in elemento.attributes().flatten() {
+			let valor = attr
+				.decoded_and_normalized_value(quick_xml::XmlVersion::Implicit1_0, reader.decoder())?
+				.trim()
+				.to_string();
+			match attr.key.local_name().as_ref() {
+				b"text" => texto = Some(valor),
+				b"title" => titulo = Some(valor),
+				b"xmlUrl" => xml_url = Some(valor),
+				_ => {}
+			}
+		}
+		let rotulo = titulo.or(texto).filter(|t| !t.is_empty());
+
+		match xml_url {
+			Some(url) => {
+				let caminho: Vec<&str> = pastas.iter().flatten().map(String::as_str).collect();
+				let nome_feed = rotulo.unwrap_or_else(|| url.clone());
+				let nome = if caminho.is_empty() {
+					nome_feed
+				} else {
+					format!("{} / {}", caminho.join(" / "), nome_feed)
+				};
+				feeds.push((nome, url));
+				if !vazio {
+					pastas.push(None);
+				}
+			}
+			None if !vazio => pastas.push(rotulo),
+			None => {}
+		}
+		buf.clear();
+	}
+
+	Ok(feeds)
+}
+
+/// `filterflow import-opml <arquivo>`: acrescenta ao arquivo de configuração, como `[[feeds]]`,
+/// os feeds de uma exportação OPML de outro leitor. O arquivo é editado apenas no final, para
+/// preservar comentários, e feeds com URL já configurada são ignorados.
+pub fn comando_import_opml(arquivo: &Path, caminho_config: &Path) -> Result<(), Box<dyn Error>> {
+	let feeds = parse_opml(&fs::read(arquivo)?)?;
+
+	let config_atual = fs::read_to_string(caminho_config)?;
+	let valor: toml::Table = toml::from_str(&config_atual)?;
+	let urls_existentes: Vec<&str> = valor
+		.get("feeds")
+		.and_then(|f| f.as_array())
+		.map(|feeds| {
+			feeds
+				.iter()
+				.filter_map(|f| f.get("url").and_then(|u| u.as_str()))
+				.collect()
+		})
+		.unwrap_or_default();
+
+	let mut novos = String::new();
+	let mut importados = 0;
+	for (nome, url) in &feeds {
+		if urls_existentes.contains(&url.as_str()) {
+			println!("[INFO] Já configurado, ignorado: {} ({})", nome, url);
+			continue;
+		}
+		if let Err(e) = validate_url(url) {
+			warn!("URL inválida ignorada: {} ({}): {}", nome, url, e);
+			continue;
+		}
+		novos.push_str(&format!(
+			"\n[[feeds]]\nnome = {}\nurl = {}\n",
+			toml::Value::String(nome.clone()),
+			toml::Value::String(url.clone())
+		));
+		importados += 1;
+	}
+
+	if importados == 0 {
+		println!("Nenhum feed novo encontrado em '{}'.", arquivo.display());
+		return Ok(());
+	}
+
+	let mut conteudo = config_atual;
+	if !conteudo.ends_with('\n') {
+		conteudo.push('\n');
+	}
+	conteudo.push_str(&format!(
+		"\n# Importados de '{}' em {}\n",
+		arquivo.display(),
+		Local::now().format("%d/%m/%Y %H:%M")
+	));
+	conteudo.push_str(&novos);
+	fs::write(caminho_config, conteudo)?;
+
+	println!(
+		"{}{} feeds importados{} para '{}'.",
+		BOLD_GREEN,
+		importados,
+		RESET,
+		caminho_config.display()
+	);
+	Ok(())
+}
+
+pub fn comando_feeds_list(caminho_config: &Path) -> Result<(), Box<dyn Error>> {
+	let config = carregar_config(caminho_config)?;
+	for feed in &config.feeds {
+		let grupo = feed
+			.opcoes
+			.grupo
+			.as_ref()
+			.map(|g| format!(" [grupo {}]", g))
+			.unwrap_or_default();
+		println!("{}{}{}{}\n  {}", BOLD, feed.nome, RESET, grupo, feed.url);
+	}
+	println!(
+		"\n{} feeds em '{}'.",
+		config.feeds.len(),
+		caminho_config.display()
+	);
+	Ok(())
+}
+
+/// Acrescenta um `[[feeds]]` à configuração depois de baixar o feed e conferir que ele é lido
+/// (com a autodescoberta de páginas HTML, como na coleta). Como no `feeds remove`, o arquivo é
+/// editado com `toml_edit`.
+pub async fn comando_feeds_add(
+	nome: &str,
+	url: &str,
+	caminho_config: &Path,
+) -> Result<(), Box<dyn Error>> {
+	let config = carregar_config(caminho_config)?;
+	if let Some(existente) = config.feeds.iter().find(|f| f.nome == nome || f.url == url) {
+		return Err(format!("Já existe o feed '{}' ({}).", existente.nome, existente.url).into());
+	}
+	validate_url(url).map_err(|e| format!("URL inválida '{}': {}", url, e))?;
+
+	let client = construir_cliente(&config.geral, &config.proxy, &config.tls)?;
+	let mut conteudo = baixar_feed(&client, url, None)
+		.await
+		.map_err(|e| format!("Não foi possível baixar '{}': {}", url, e))?;
+	if parece_html(&conteudo) {
+		let descobertos = descobrir_feeds_html(&String::from_utf8_lossy(&conteudo), url);
+		let descoberto = descobertos
+			.first()
+			.ok_or("A URL retornou uma página HTML sem <link rel=\"alternate\"> de feed RSS.")?;
+		println!("[INFO] Página HTML; feed anunciado: {}", descoberto);
+		conteudo = baixar_feed(&client, descoberto, None).await?;
+	}
+	let canal = ler_canal(&conteudo).map_err(|e| format!("O feed não pôde ser lido: {}", e))?;
+
+	let mut documento: toml_edit::DocumentMut = fs::read_to_string(caminho_config)?.parse()?;
+	// A lista vazia que o `feeds remove` deixa (`feeds = []`) vira uma lista de tabelas
+	let vazia = documento
+		.get("feeds")
+		.is_none_or(|f| f.as_array().is_some_and(|a| a.is_empty()));
+	if vazia {
+		documento.remove("feeds");
+		documento.insert(
+			"feeds",
+			toml_edit::Item::ArrayOfTables(toml_edit::ArrayOfTables::new()),
+		);
+	}
+	let feeds = documento["feeds"]
+		.as_array_of_tables_mut()
+		.ok_or("'feeds' não está no formato [[feeds]].")?;
+	let mut tabela = toml_edit::Table::new();
+	tabela["nome"] = toml_edit::value(nome);
+	tabela["url"] = toml_edit::value(url);
+	// Sem outro feed para acompanhar, a tabela vai para o final do arquivo
+	if vazia {
+		tabela.set_position(Some(isize::MAX));
+	}
+	feeds.push(tabela);
+
+	let novo = documento.to_string();
+	validate_config(&toml::from_str(&novo)?)?;
+	fs::write(caminho_config, novo)?;
+
+	println!(
+		"{}Feed '{}' adicionado{} ({} itens no momento).",
+		BOLD_GREEN,
+		nome,
+		RESET,
+		canal.items().len()
+	);
+	Ok(())
+}
+
+/// Remove o `[[feeds]]` com esse nome ou URL. O arquivo é editado com `toml_edit`, o que preserva
+/// os comentários e a formatação do restante; só os comentários logo acima do feed saem com ele.
+pub fn comando_feeds_remove(feed: &str, caminho_config: &Path) -> Result<(), Box<dyn Error>> {
+	let mut documento: toml_edit::DocumentMut = fs::read_to_string(caminho_config)?.parse()?;
+	let feeds = documento
+		.get_mut("feeds")
+		.and_then(|f| f.as_array_of_tables_mut())
+		.ok_or("A configuração não tem [[feeds]].")?;
+
+	let coincide = |tabela: &toml_edit::Table, campo: &str| {
+		tabela.get(campo).and_then(|v| v.as_str()) == Some(feed)
+	};
+	let encontrados: Vec<usize> = feeds
+		.iter()
+		.enumerate()
+		.filter(|(_, t)| coincide(t, "nome") || coincide(t, "url"))
+		.map(|(i, _)| i)
+		.collect();
+	match encontrados[..] {
+		[] => return Err(format!("Nenhum feed com nome ou URL '{}'.", feed).into()),
+		[indice] => {
+			feeds.remove(indice);
+		}
+		_ => {
+			return Err(format!(
+				"'{}' corresponde a {} feeds; informe a URL.",
+				feed,
+				encontrados.len()
+			)
+			.into());
+		}
+	}
+	if feeds.is_empty() {
+		documento.remove("feeds");
+	}
+
+	let novo = documento.to_string();
+	validate_config(&toml::from_str(&novo)?)?;
+	fs::write(caminho_config, novo)?;
+	println!("{}Feed '{}' removido.{}", BOLD_GREEN, feed, RESET);
+	Ok(())
+}
+
+/// `filterflow resummarize [--since 30d] [--model <modelo>] [--por-minuto N]`: regera os
+/// resumos dos itens relevantes arquivados com o modelo/prompt atual, em ritmo limitado.
+/// Registros já resumidos pelo mesmo modelo e versão de prompt são pulados.
+pub async fn comando_resummarize(
+	desde: Option<Duration>,
+	modelo: Option<String>,
+	por_minuto: u32,
+	caminho_config: &Path,
+	banco: &OpcoesBanco,
+) -> Result<(), Box<dyn Error>> {
+	let config = carregar_config(caminho_config)?;
+	let mut geral = config.geral;
+	if let Some(modelo) = modelo {
+		geral.modelo_resumo = modelo;
+	}
+	let versao = versao_prompt_resumo(&geral);
+	let client = construir_cliente(&geral, &config.proxy, &config.tls)?;
+	let geral = Arc::new(geral);
+	let cifra = CifraArquivo::carregar(&config.arquivo)?;
+
+	let db = banco.abrir_para_escrita()?;
+	let tree = db.open_tree(ARQUIVO_TREE)?;
+	let limite = desde.map(antes_de_agora);
+	let pausa = StdDuration::from_secs_f64(60.0 / por_minuto as f64);
+
+	let mut pendentes = Vec::new();
+	for entrada in tree.iter() {
+		let (chave, valor) = entrada?;
+		let registro = db_ler_arquivo(&valor, cifra.as_ref())?;
+		let atualizado = registro.modelo_resumo.as_deref() == Some(geral.modelo_resumo.as_str())
+			&& registro.versao_prompt.as_deref() == Some(versao.as_str());
+		if !atualizado && limite.is_none_or(|l| registro.entregue_em >= l) {
+			pendentes.push((chave, registro));
+		}
+	}
+	println!(
+		"{} itens a resumir com '{}' (prompt {}), {} por minuto.",
+		pendentes.len(),
+		geral.modelo_resumo,
+		versao,
+		por_minuto
+	);
+
+	let total = pendentes.len();
+	for (i, (chave, mut registro)) in pendentes.into_iter().enumerate() {
+		match call_llm_summarize(
+			&client,
+			&registro.titulo,
+			&registro.descricao,
+			Arc::clone(&geral),
+		)
+		.await
+		{
+			Ok((resumo, _)) => {
+				registro.resumo = Some(resumo);
+				registro.modelo_resumo = Some(geral.modelo_resumo.clone());
+				registro.versao_prompt = Some(versao.clone());
+				db_arquivar(
+					&db,
+					&String::from_utf8_lossy(&chave),
+					&registro,
+					cifra.as_ref(),
+				)?;
+				println!("[{}/{}] {}", i + 1, total, registro.titulo);
+			}
+			Err(e) => error!(
+				"[{}/{}] Falha ao resumir '{}': {}",
+				i + 1,
+				total,
+				registro.titulo,
+				e
+			),
+		}
+		if i + 1 < total {
+			time::sleep(pausa).await;
+		}
+	}
+	tree.flush()?;
+	Ok(())
+}
+
+/// `filterflow export <pasta> [--since 30d]`: exporta os itens relevantes arquivados como notas
+/// Markdown, no mesmo `[saida].layout` usado pelo exportador contínuo.
+pub fn comando_export(
+	pasta: Option<&Path>,
+	formato: FormatoExportacao,
+	desde: Option<Duration>,
+	caminho_config: &Path,
+	banco: &OpcoesBanco,
+) -> Result<(), Box<dyn Error>> {
+	if formato == FormatoExportacao::Json {
+		if pasta.is_some() || desde.is_some() {
+			return Err(
+				"O formato json copia o banco inteiro e não aceita pasta nem --since.".into(),
+			);
+		}
+		let copia = db_exportar(&banco.abrir()?)?;
+		let mut stdout = io::BufWriter::new(io::stdout().lock());
+		serde_json::to_writer(&mut stdout, &copia)?;
+		writeln!(stdout)?;
+		stdout.flush()?;
+		return Ok(());
+	}
+	let pasta = pasta.ok_or("Informe a pasta das notas: filterflow export <pasta>.")?;
+	let config = carregar_config(caminho_config)?;
+	let cifra = CifraArquivo::carregar(&config.arquivo)?;
+	let db = banco.abrir()?;
+	let tree = db.open_tree(ARQUIVO_TREE)?;
+	let limite = desde.map(antes_de_agora);
+
+	let mut exportados = 0;
+	for entrada in tree.iter() {
+		let (_, valor) = entrada?;
+		let registro = db_ler_arquivo(&valor, cifra.as_ref())?;
+		if limite.is_some_and(|l| registro.entregue_em < l) {
+			continue;
+		}
+		exportar_markdown(pasta, &config.saida.layout, &registro, None)?;
+		exportados += 1;
+	}
+	println!(
+		"{}{} itens exportados{} para '{}'.",
+		BOLD_GREEN,
+		exportados,
+		RESET,
+		pasta.display()
+	);
+	Ok(())
+}
+
+/// `filterflow import <arquivo> [--replace]`: grava uma cópia de `export --format json` no
+/// banco, como numa migração de máquina ou de versão do sled.
+pub fn comando_import(
+	arquivo: &Path,
+	substituir: bool,
+	banco: &OpcoesBanco,
+) -> Result<(), Box<dyn Error>> {
+	let conteudo = fs::read(arquivo)
+		.map_err(|e| format!("Não foi possível ler '{}': {}", arquivo.display(), e))?;
+	let copia: CopiaBanco = serde_json::from_slice(&conteudo)
+		.map_err(|e| format!("'{}' não é uma cópia do banco: {}", arquivo.display(), e))?;
+	let db = banco.abrir_para_escrita()?;
+	let importadas = db_importar(&db, &copia, substituir)?;
+	println!(
+		"{}{} entradas importadas{} de {} árvores (cópia de {}).",
+		BOLD_GREEN,
+		importadas,
+		RESET,
+		copia.arvores.len(),
+		copia
+			.exportado_em
+			.with_timezone(&Local)
+			.format("%d/%m/%Y %H:%M")
+	);
+	Ok(())
+}
+
+/// Opções globais do banco: `--db <pasta>` e `--read-only`. No modo somente leitura o
+/// banco (ex.: uma cópia sincronizada de backup) não é aberto no lugar, já que o sled grava ao
+/// abrir: os comandos leem um instantâneo numa pasta temporária, copiado de novo só quando o
+/// original muda. O agente e os comandos que gravam recusam rodar.
+pub struct OpcoesBanco {
+	pub caminho: String,
+	pub somente_leitura: bool,
+}
+
+impl OpcoesBanco {
+	/// Arquivo com o PID do agente que usa o banco, ao lado da pasta dele.
+	pub fn arquivo_pid(&self) -> PathBuf {
+		PathBuf::from(format!("{}.pid", self.caminho.trim_end_matches('/')))
+	}
+
+	/// Abre o banco para um comando de consulta.
+	pub fn abrir(&self) -> Result<Db, Box<dyn Error>> {
+		if !self.somente_leitura {
+			return self.abrir_para_escrita();
+		}
+		self.abrir_instantaneo()
+	}
+
+	/// Instantâneo do banco para as consultas com `--read-only`, guardado entre os comandos
+	/// em `filterflow-replica-<hash da pasta>` e copiado de novo só se algum arquivo do banco
+	/// mudou (tamanho ou data). Se outro comando estiver com o instantâneo aberto, esta consulta
+	/// usa uma cópia própria.
+	pub fn abrir_instantaneo(&self) -> Result<Db, Box<dyn Error>> {
+		let origem = Path::new(&self.caminho);
+		let mut hasher = std::collections::hash_map::DefaultHasher::new();
+		fs::canonicalize(origem)
+			.unwrap_or_else(|_| origem.to_path_buf())
+			.hash(&mut hasher);
+		let pasta =
+			std::env::temp_dir().join(format!("filterflow-replica-{:016x}", hasher.finish()));
+		let marca = pasta.with_extension("origem");
+
+		let mut assinatura = String::new();
+		if origem.exists() {
+			assinar_diretorio(origem, origem, &mut assinatura)
+				.map_err(|e| format!("Não foi possível ler '{}': {}", self.caminho, e))?;
+		}
+		if !pasta.exists() || fs::read_to_string(&marca).ok().as_ref() != Some(&assinatura) {
+			let _ = fs::remove_file(&marca);
+			let _ = fs::remove_dir_all(&pasta);
+			if origem.exists() {
+				copiar_diretorio(origem, &pasta)
+					.map_err(|e| format!("Não foi possível copiar '{}': {}", self.caminho, e))?;
+			}
+			fs::write(&marca, &assinatura)?;
+		}
+		match sled::Config::new().path(&pasta).open() {
+			Ok(db) => Ok(db),
+			Err(_) => self.abrir_copia(),
+		}
+	}
+
+	/// Abre uma cópia temporária do banco, apagada quando ela é fechada (vazia, se o banco ainda
+	/// não existe).
+	pub fn abrir_copia(&self) -> Result<Db, Box<dyn Error>> {
+		let copia = std::env::temp_dir().join(format!("filterflow-copia-{}", std::process::id()));
+		let _ = fs::remove_dir_all(&copia);
+		if Path::new(&self.caminho).exists() {
+			copiar_diretorio(Path::new(&self.caminho), &copia)
+				.map_err(|e| format!("Não foi possível copiar '{}': {}", self.caminho, e))?;
+		}
+		Ok(sled::Config::new().path(&copia).temporary(true).open()?)
+	}
+
+	/// Abre o banco para um comando que grava (o agente em execução mantém o banco bloqueado).
+	pub fn abrir_para_escrita(&self) -> Result<Db, Box<dyn Error>> {
+		if self.somente_leitura {
+			return Err("Este comando grava no banco e não pode rodar com --read-only.".into());
+		}
+		db_init_trees(&self.caminho).map_err(|e| {
+			format!(
+				"Não foi possível abrir '{}' ({}). Pare o agente antes de usar este comando.",
+				self.caminho, e
+			)
+			.into()
+		})
+	}
+}
+
+/// Acrescenta a `assinatura` uma linha por arquivo da pasta (caminho, tamanho e data da última
+/// mudança), em ordem de nome, para saber se o banco mudou desde o último instantâneo.
+fn assinar_diretorio(raiz: &Path, pasta: &Path, assinatura: &mut String) -> Result<(), io::Error> {
+	let mut entradas = fs::read_dir(pasta)?.collect::<Result<Vec<_>, _>>()?;
+	entradas.sort_by_key(|e| e.file_name());
+	for entrada in entradas {
+		let metadados = entrada.metadata()?;
+		if metadados.is_dir() {
+			assinar_diretorio(raiz, &entrada.path(), assinatura)?;
+			continue;
+		}
+		let modificado = metadados
+			.modified()?
+			.duration_since(std::time::UNIX_EPOCH)
+			.unwrap_or_default();
+		assinatura.push_str(&format!(
+			"{} {} {}\n",
+			entrada
+				.path()
+				.strip_prefix(raiz)
+				.unwrap_or(&entrada.path())
+				.display(),
+			metadados.len(),
+			modificado.as_nanos()
+		));
+	}
+	Ok(())
+}
+
+pub fn copiar_diretorio(origem: &Path, destino: &Path) -> Result<(), io::Error> {
+	fs::create_dir_all(destino)?;
+	for entrada in fs::read_dir(origem)? {
+		let entrada = entrada?;
+		let alvo = destino.join(entrada.file_name());
+		if entrada.file_type()?.is_dir() {
+			copiar_diretorio(&entrada.path(), &alvo)?;
+		} else {
+			fs::copy(entrada.path(), alvo)?;
+		}
+	}
+	Ok(())
+}
+
+/// `filterflow mark-read <link>`: marca o item como já processado, sem entregá-lo.
+pub fn comando_mark_read(chave: &str, banco: &OpcoesBanco) -> Result<(), Box<dyn Error>> {
+	let db = banco.abrir_para_escrita()?;
+	db.insert(chave.as_bytes(), b"processed")?;
+	db_auditar(
+		&db,
+		AcaoModeracao::MarcarComoLido,
+		OrigemAcao::Cli,
+		chave,
+		None,
+	)?;
+	db.flush()?;
+	println!("Item marcado como lido: {}", chave);
+	Ok(())
+}
+
+/// `filterflow reclassify <link> relevante|irrelevante`: corrige a decisão do filtro. Como
+/// irrelevante, o item sai do arquivo e entra no cache de irrelevância; como relevante, sai do
+/// cache de irrelevância para ser avaliado de novo quando reaparecer na fonte.
+pub fn comando_reclassify(
+	chave: &str,
+	classe: &str,
+	banco: &OpcoesBanco,
+) -> Result<(), Box<dyn Error>> {
+	let db = banco.abrir_para_escrita()?;
+	db_reclassificar(&db, chave, classe == "relevante")?;
+	db_auditar(
+		&db,
+		AcaoModeracao::Reclassificar,
+		OrigemAcao::Cli,
+		chave,
+		Some(classe.to_string()),
+	)?;
+	db.flush()?;
+	println!("Item reclassificado como {}: {}", classe, chave);
+	Ok(())
+}
+
+/// Seletores de `filterflow purge`.
+pub struct SelecaoLimpeza {
+	pub irrelevantes: bool,
+	pub processados: bool,
+	pub fonte: Option<String>,
+	pub mais_antigos_que: Option<Duration>,
+}
+
+/// `filterflow purge`: remove as chaves selecionadas do cache de irrelevância e/ou da árvore
+/// principal. A fonte e a data vêm do próprio registro (ou, para os processados antigos, do
+/// arquivo de relevantes); entradas sem esses dados só saem quando não há filtro por fonte nem
+/// por idade. O arquivo de relevantes é preservado. Com `simulacao`, apenas conta.
+pub fn comando_purge(
+	selecao: &SelecaoLimpeza,
+	simulacao: bool,
+	banco: &OpcoesBanco,
+) -> Result<(), Box<dyn Error>> {
+	let db = if simulacao {
+		banco.abrir()?
+	} else {
+		banco.abrir_para_escrita()?
+	};
+	let limite = selecao.mais_antigos_que.map(antes_de_agora);
+	let filtrado = selecao.fonte.is_some() || limite.is_some();
+	let corresponde = |metadados: Option<(String, DateTime<Utc>)>| match metadados {
+		Some((fonte, quando)) => {
+			selecao.fonte.as_ref().is_none_or(|f| *f == fonte) && limite.is_none_or(|l| quando < l)
+		}
+		None => !filtrado,
+	};
+
+	let arquivo = db.open_tree(ARQUIVO_TREE)?;
+	let mut arvores: Vec<(&str, sled::Tree)> = Vec::new();
+	if selecao.irrelevantes {
+		arvores.push(("irrelevantes", db.open_tree(IRRELEVANT_CACHE_TREE)?));
+	}
+	if selecao.processados {
+		arvores.push(("processados", (*db).clone()));
+	}
+
+	let mut removidas = 0;
+	for (nome, arvore) in &arvores {
+		let mut chaves = Vec::new();
+		let mut sem_metadados = 0;
+		for entrada in arvore.iter() {
+			let (chave, valor) = entrada?;
+			let metadados = if *nome == "irrelevantes" {
+				serde_json::from_slice::<RegistroIrrelevante>(&valor)
+					.ok()
+					.map(|r| (r.fonte, r.rejeitado_em))
+			} else {
+				match serde_json::from_slice::<RegistroProcessado>(&valor) {
+					Ok(r) => Some((r.fonte, r.processado_em)),
+					Err(_) => arquivo
+						.get(&chave)?
+						.and_then(|v| serde_json::from_slice::<RegistroArquivo>(&v).ok())
+						.map(|r| (r.fonte.nome, r.entregue_em)),
+				}
+			};
+			if metadados.is_none() && filtrado {
+				sem_metadados += 1;
+			}
+			if corresponde(metadados) {
+				chaves.push(chave);
+			}
+		}
+
+		if !simulacao {
+			for chave in &chaves {
+				arvore.remove(chave)?;
+			}
+		}
+		removidas += chaves.len();
+		println!(
+			"{}: {} chaves {}{}",
+			nome,
+			chaves.len(),
+			if simulacao {
+				"seriam removidas"
+			} else {
+				"removidas"
+			},
+			if sem_metadados > 0 {
+				format!(" ({} sem fonte ou data mantidas)", sem_metadados)
+			} else {
+				String::new()
+			}
+		);
+	}
+	if simulacao {
+		return Ok(());
+	}
+
+	db.flush()?;
+	let mut alvo: Vec<String> = arvores.iter().map(|(nome, _)| nome.to_string()).collect();
+	if let Some(fonte) = &selecao.fonte {
+		alvo.push(format!("fonte {}", fonte));
+	}
+	if let Some(limite) = limite {
+		alvo.push(format!(
+			"anteriores a {}",
+			limite.with_timezone(&Local).format("%d/%m/%Y %H:%M")
+		));
+	}
+	db_auditar(
+		&db,
+		AcaoModeracao::LimparCache,
+		OrigemAcao::Cli,
+		&alvo.join(", "),
+		Some(format!("{} chaves", removidas)),
+	)?;
+	Ok(())
+}
+
+/// `filterflow reprocess [--feed F] --since <data>`: tira do cache de irrelevância os itens
+/// rejeitados desde `desde` (e da fonte, se informada) e os passa de novo pelas etapas do
+/// pipeline: os aprovados agora são resumidos e entregues, e os demais voltam ao cache. Os que
+/// não chegam ao filtro do LLM (falha, pré-filtro ou já processados) mantêm o registro antigo.
+/// Com `simulacao`, tudo roda numa cópia descartável do banco e nada é entregue.
+pub async fn comando_reprocess(
+	fonte: Option<&str>,
+	desde: DateTime<Utc>,
+	simulacao: bool,
+	caminho_config: &Path,
+	banco: &OpcoesBanco,
+) -> Result<(), Box<dyn Error>> {
+	let config = carregar_config(caminho_config)?;
+	let cifra = CifraArquivo::carregar(&config.arquivo)?;
+	let db = Arc::new(if simulacao {
+		banco.abrir_copia()?
+	} else {
+		banco.abrir_para_escrita()?
+	});
+
+	// Registros antigos (só a chave, sem o item) não têm como ser reavaliados
+	let cache = db.open_tree(IRRELEVANT_CACHE_TREE)?;
+	let mut selecionados = Vec::new();
+	for entrada in cache.iter() {
+		let (chave, valor) = entrada?;
+		let Ok(mut registro) = serde_json::from_slice::<RegistroIrrelevante>(&valor) else {
+			continue;
+		};
+		if registro.rejeitado_em < desde || fonte.is_some_and(|f| f != registro.fonte) {
+			continue;
+		}
+		registro.abrir(cifra.as_ref())?;
+		selecionados.push(registro.noticia(&String::from_utf8_lossy(&chave)));
+	}
+	if selecionados.is_empty() {
+		println!("Nenhum item rejeitado corresponde à seleção.");
+		return Ok(());
+	}
+	println!(
+		"{}Reprocessando {} itens rejeitados...{}",
+		BOLD,
+		selecionados.len(),
+		RESET
+	);
+	if simulacao {
+		println!(
+			"{}[SIMULAÇÃO] O banco é uma cópia descartável; nada será entregue às saídas.{}",
+			BOLD_YELLOW, RESET
+		);
+	}
+
+	let client = construir_cliente(&config.geral, &config.proxy, &config.tls)?;
+	let fila: FilaResumo = Mutex::new(Vec::new());
+	let pipeline = Pipeline::da_config(&config.pipeline);
+	let vigente = ConfigVigente::novo(&config);
+	let contexto = ContextoEtapa {
+		client: &client,
+		db: &db,
+		config: &vigente,
+		cifra: cifra.as_ref(),
+		fila: &fila,
+		simulacao,
+		reavaliacao: true,
+	};
+	let fontes = config.fontes();
+	// Rejeitado de novo, o filtro regrava o registro no cache; aprovado, ele só sai do cache
+	// depois que a entrega o marca como processado. Nos demais casos, o registro fica como está.
+	let (mut aprovados, mut irrelevantes) = (Vec::new(), 0);
+	for noticia in &selecionados {
+		let prioridade = fontes
+			.iter()
+			.find(|f| f.nome() == noticia.fonte)
+			.map(|f| f.opcoes().prioridade)
+			.unwrap_or_default();
+		match pipeline.avaliar(&contexto, noticia, prioridade).await {
+			Ok(ResultadoFiltro::Relevante) => aprovados.push(noticia.chave.as_str()),
+			Ok(ResultadoFiltro::Irrelevante { .. }) => irrelevantes += 1,
+			Ok(_) => {}
+			Err(e) => error!("Falha na lógica central para '{}': {}", noticia.titulo, e),
+		}
+	}
+	processar_fila_resumo(&contexto, &pipeline).await;
+	let relevantes = db_descachear_processados(&db, &aprovados)?;
+	let mantidos = selecionados.len() - relevantes - irrelevantes;
+
+	println!(
+		"{}{} aprovados{}, {} rejeitados de novo, {} mantidos no cache.",
+		BOLD_GREEN, relevantes, RESET, irrelevantes, mantidos
+	);
+	if simulacao {
+		return Ok(());
+	}
+	let mut alvo = vec![format!(
+		"rejeitados desde {}",
+		desde.with_timezone(&Local).format("%d/%m/%Y %H:%M")
+	)];
+	if let Some(fonte) = fonte {
+		alvo.push(format!("fonte {}", fonte));
+	}
+	db_auditar(
+		&db,
+		AcaoModeracao::Reprocessar,
+		OrigemAcao::Cli,
+		&alvo.join(", "),
+		Some(format!(
+			"{} itens, {} aprovados",
+			selecionados.len(),
+			relevantes
+		)),
+	)?;
+	Ok(())
+}
+
+/// `filterflow audit [N]`: lista as N (padrão 20) ações manuais mais recentes.
+pub fn comando_audit(quantidade: usize, banco: &OpcoesBanco) -> Result<(), Box<dyn Error>> {
+	let db = banco.abrir()?;
+	let tree = db.open_tree(AUDITORIA_TREE)?;
+	let mut registros = Vec::new();
+	for entrada in tree.iter().rev().take(quantidade) {
+		let (_, valor) = entrada?;
+		registros.push(serde_json::from_slice::<RegistroAuditoria>(&valor)?);
+	}
+	for registro in registros.iter().rev() {
+		println!(
+			"{} [{:?}] {:?}: {}{}",
+			registro
+				.quando
+				.with_timezone(&Local)
+				.format("%d/%m/%Y %H:%M:%S"),
+			registro.origem,
+			registro.acao,
+			registro.alvo,
+			registro
+				.detalhe
+				.as_ref()
+				.map(|d| format!(" ({})", d))
+				.unwrap_or_default()
+		);
+	}
+	Ok(())
+}
+
+/// `filterflow search <termos...> [--limit N]`: percorre o arquivo de relevantes e lista os
+/// itens que contêm todos os termos no título, resumo, descrição, nome da fonte ou grupo,
+/// sem distinguir maiúsculas nem acentos. A busca é uma varredura, sem índice à parte: o
+/// arquivo pode estar cifrado, e um índice invertido em claro exporia o texto dos resumos.
+pub fn comando_search(
+	termos: &[String],
+	limite: usize,
+	caminho_config: &Path,
+	banco: &OpcoesBanco,
+) -> Result<(), Box<dyn Error>> {
+	let config = carregar_config(caminho_config)?;
+	let cifra = CifraArquivo::carregar(&config.arquivo)?;
+	let db = banco.abrir()?;
+	let tree = db.open_tree(ARQUIVO_TREE)?;
+	let termos: Vec<String> = termos.iter().map(|t| normalizar_busca(t)).collect();
+
+	let mut encontrados = Vec::new();
+	for entrada in tree.iter() {
+		let (_, valor) = entrada?;
+		let registro = db_ler_arquivo(&valor, cifra.as_ref())?;
+		let texto = normalizar_busca(&format!(
+			"{} {} {} {} {}",
+			registro.titulo,
+			registro.resumo.as_deref().unwrap_or_default(),
+			registro.descricao,
+			registro.fonte.nome,
+			registro.fonte.grupo.as_deref().unwrap_or_default()
+		));
+		if termos.iter().all(|t| texto.contains(t.as_str())) {
+			encontrados.push(registro);
+		}
+	}
+	encontrados.sort_by_key(|r| std::cmp::Reverse(r.entregue_em));
+
+	if encontrados.is_empty() {
+		println!("Nenhum item arquivado contém '{}'.", termos.join(" "));
+		return Ok(());
+	}
+	for registro in encontrados.iter().take(limite) {
+		let data = registro.publicado.unwrap_or(registro.entregue_em);
+		println!(
+			"{} {}{}{} ({})\n  {}",
+			data.with_timezone(&Local).format("%d/%m/%Y %H:%M"),
+			BOLD,
+			registro.titulo,
+			RESET,
+			registro.fonte.nome,
+			registro.link
+		);
+	}
+	if encontrados.len() > limite {
+		println!(
+			"... e mais {} itens (use --limit para ver mais).",
+			encontrados.len() - limite
+		);
+	}
+	Ok(())
+}
+
+/// Minúsculas e sem acentos, para a busca não depender da grafia.
+pub fn normalizar_busca(texto: &str) -> String {
+	texto
+		.chars()
+		.flat_map(char::to_lowercase)
+		.map(|c| match c {
+			'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+			'é' | 'è' | 'ê' | 'ë' => 'e',
+			'í' | 'ì' | 'î' | 'ï' => 'i',
+			'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+			'ú' | 'ù' | 'û' | 'ü' => 'u',
+			'ç' => 'c',
+			'ñ' => 'n',
+			c => c,
+		})
+		.collect()
+}
+
+/// `filterflow sync --peer <url> [--token T]`: troca com o par (um agente com
+/// `[sincronizacao].endereco`) as chaves dos itens já processados e irrelevantes, para que
+/// nenhuma das máquinas entregue de novo o que a outra já entregou.
+pub async fn comando_sync(
+	par: Option<String>,
+	token: Option<String>,
+	caminho_config: &Path,
+	banco: &OpcoesBanco,
+) -> Result<(), Box<dyn Error>> {
+	let config = carregar_config(caminho_config)?;
+	let par = par
+		.or(config.sincronizacao.par.clone())
+		.ok_or("Informe o par com --peer <url> ou em [sincronizacao].par.")?;
+	let token = token.or(config.sincronizacao.token.clone());
+
+	let client = construir_cliente(&config.geral, &config.proxy, &config.tls)?;
+	let db = banco.abrir_para_escrita()?;
+	let (recebidas, enviadas) = sincronizar_com_par(&client, &db, &par, token.as_deref()).await?;
+	println!(
+		"Sincronizado com {}: {} chaves novas recebidas, {} enviadas.",
+		par, recebidas, enviadas
+	);
+	Ok(())
+}
+
+/// `filterflow tui`: abre a interface do terminal ligada à API do painel. Sem `--url`, usa o
+/// `[painel].endereco` da configuração (um endereço `0.0.0.0` vira `127.0.0.1`).
+pub async fn comando_tui(
+	url: Option<String>,
+	token: Option<String>,
+	caminho_config: &Path,
+) -> Result<(), Box<dyn Error>> {
+	let painel = if url.is_none() || token.is_none() {
+		carregar_config(caminho_config)?.painel
+	} else {
+		None
+	};
+	let url = url
+		.or_else(|| {
+			painel
+				.as_ref()
+				.map(|p| format!("http://{}", p.endereco.replace("0.0.0.0", "127.0.0.1")))
+		})
+		.ok_or("Informe o painel com --url ou em [painel].endereco.")?;
+	let token = token
+		.or_else(|| painel.as_ref().and_then(|p| p.token.clone()))
+		.ok_or("A interface usa a API do painel: defina [painel].token ou use --token.")?;
+	rodar_tela(ClienteApi::novo(&url, &token)?).await
+}
+
+/// Interpreta durações como "30d", "12h" ou "90m".
+pub fn interpretar_duracao(texto: &str) -> Result<Duration, String> {
+	let erro = || {
+		format!(
+			"Duração inválida '{}': use, por exemplo, 30d, 12h ou 90m.",
+			texto
+		)
+	};
+	let unidade = texto.chars().last().ok_or_else(erro)?;
+	let numero: i64 = texto[..texto.len() - unidade.len_utf8()]
+		.parse()
+		.map_err(|_| erro())?;
+	// Negativa, a duração apontaria para o futuro (e `--older-than` pegaria tudo)
+	if numero <= 0 {
+		return Err(erro());
+	}
+	let duracao = match unidade {
+		'd' => Duration::try_days(numero),
+		'h' => Duration::try_hours(numero),
+		'm' => Duration::try_minutes(numero),
+		_ => None,
+	};
+	// A duração é sempre contada para trás a partir de agora
+	duracao
+		.filter(|d| Utc::now().checked_sub_signed(*d).is_some())
+		.ok_or_else(erro)
+}
+
+/// Instante `duracao` antes de agora (as durações de `interpretar_duracao` sempre cabem).
+pub fn antes_de_agora(duracao: Duration) -> DateTime<Utc> {
+	Utc::now()
+		.checked_sub_signed(duracao)
+		.unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+/// Interpreta um instante como data ("2026-10-01" ou "01/10/2026", à meia-noite local) ou
+/// como duração até agora ("3d", "12h").
+pub fn interpretar_instante(texto: &str) -> Result<DateTime<Utc>, String> {
+	for formato in ["%Y-%m-%d", "%d/%m/%Y"] {
+		if let Ok(data) = NaiveDate::parse_from_str(texto, formato) {
+			return data
+				.and_hms_opt(0, 0, 0)
+				.and_then(|d| d.and_local_timezone(Local).earliest())
+				.map(|d| d.with_timezone(&Utc))
+				.ok_or_else(|| format!("Data inválida '{}'.", texto));
+		}
+	}
+	interpretar_duracao(texto).map(antes_de_agora).map_err(|_| {
+		format!(
+			"Data inválida '{}': use, por exemplo, 2026-10-01, 01/10/2026 ou 3d.",
+			texto
+		)
+	})
+}
+
+// =================================================================
+// MAIN
+// =================================================================
+
+/// Cliente HTTP com o user agent, os certificados de `[tls]` e, se ativado, o proxy da
+/// configuração. Um proxy SOCKS5 é atendido por uma ponte local (ver [`crate::socks`]).
+pub fn construir_cliente(
+	geral: &GeralConfig,
+	proxy: &ProxyConfig,
+	tls: &TlsConfig,
+) -> Result<Client, Box<dyn Error>> {
+	let mut client_builder = Client::builder().user_agent(&geral.user_agent);
+	for ca in tls.cas()? {
+		client_builder = client_builder.add_root_certificate(ca);
+	}
+	if let Some(identidade) = tls.identidade()? {
+		client_builder = client_builder.identity(identidade);
+	}
+	if proxy.usar_proxy {
+		client_builder = aplicar_proxy(
+			client_builder,
+			proxy,
+			&proxy.endereco_proxy,
+			proxy.credenciais(),
+			&geral.endereco,
+		)?;
+		info!(
+			"Usando proxy em: {}",
+			endereco_sem_credenciais(&proxy.endereco_proxy)
+		);
+	}
+	Ok(client_builder.build()?)
+}
+
+/// Clientes dos feeds e sitemaps com opções HTTP próprias (proxy, cabecalhos, cookie, TLS),
+/// pelo nome da fonte. As demais usam o cliente de [`construir_cliente`].
+pub fn construir_clientes_fonte(
+	config: &Config,
+) -> Result<HashMap<String, Client>, Box<dyn Error>> {
+	let fontes = config
+		.feeds
+		.iter()
+		.map(|f| (&f.nome, &f.http))
+		.chain(config.sitemaps.iter().map(|s| (&s.nome, &s.http)));
+	let mut clientes = HashMap::new();
+	for (nome, http) in fontes.filter(|(_, http)| http.personalizada()) {
+		let mut client_builder = Client::builder().user_agent(&config.geral.user_agent);
+		// As credenciais de [proxy] não vão a um proxy próprio da fonte
+		let credenciais = match http.proxy {
+			Some(_) => None,
+			None => config.proxy.credenciais(),
+		};
+		client_builder = match http.proxy_efetivo(&config.proxy) {
+			Some(endereco) => aplicar_proxy(
+				client_builder,
+				&config.proxy,
+				endereco,
+				credenciais,
+				&config.geral.endereco,
+			)
+			.map_err(|e| format!("proxy da fonte '{}': {}", nome, e))?,
+			// Nem o proxy das variáveis de ambiente
+			None => client_builder.no_proxy(),
+		};
+		client_builder = client_builder.default_headers(http.cabecalhos_http()?);
+
+		// As CAs da fonte somam-se às de [tls]; o certificado de cliente da fonte substitui o global
+		let mut cas = config.tls.cas()?;
+		if let Some(ca) = &http.ca_arquivo {
+			cas.extend(ler_cas(ca)?);
+		}
+		for ca in cas {
+			client_builder = client_builder.add_root_certificate(ca);
+		}
+		let identidade = match ler_identidade(
+			http.certificado_cliente.as_deref(),
+			http.chave_cliente.as_deref(),
+		)? {
+			Some(identidade) => Some(identidade),
+			None => config.tls.identidade()?,
+		};
+		if let Some(identidade) = identidade {
+			client_builder = client_builder.identity(identidade);
+		}
+		if http.ignorar_certificado {
+			client_builder = client_builder.danger_accept_invalid_certs(true);
+		}
+		clientes.insert(nome.clone(), client_builder.build()?);
+	}
+	Ok(clientes)
+}
+
+/// Passa as requisições do cliente pelo proxy `endereco`, com o escopo e as exceções de
+/// `[proxy]`. Com um proxy HTTP, as `credenciais` vão em `Proxy-Authorization`; com um SOCKS5,
+/// a ponte as apresenta ao servidor, e o cliente apresenta à ponte o segredo dela. O host do
+/// LLM (`endereco_llm`) entra sempre nas exceções.
+fn aplicar_proxy(
+	client_builder: ClientBuilder,
+	proxy: &ProxyConfig,
+	endereco: &str,
+	credenciais: Option<(&str, &str)>,
+	endereco_llm: &str,
+) -> Result<ClientBuilder, Box<dyn Error>> {
+	let socks = socks::eh_socks(endereco);
+	let local = if socks {
+		socks::ponte_socks(endereco, credenciais)?
+	} else {
+		endereco.to_string()
+	};
+	let mut proxy_http = match proxy.escopo {
+		EscopoProxy::Https => Proxy::https(&local)?,
+		EscopoProxy::Todas => Proxy::all(&local)?,
+	};
+	if socks {
+		let (usuario, senha) = socks::credenciais_ponte();
+		proxy_http = proxy_http.basic_auth(usuario, senha);
+	} else if let Some((usuario, senha)) = credenciais {
+		proxy_http = proxy_http.basic_auth(usuario, senha);
+	}
+	let mut excecoes = proxy.sem_proxy.clone();
+	if let Ok(llm) = Url::parse(endereco_llm)
+		&& let Some(host) = llm.host_str()
+	{
+		excecoes.push(host.trim_matches(['[', ']']).to_string());
+	}
+	let excecoes = NoProxy::from_string(&excecoes.join(","));
+	Ok(client_builder.proxy(proxy_http.no_proxy(excecoes)))
+}
+
+/// `filterflow init [--force]`: assistente da primeira configuração. Pergunta, com valores
+/// padrão, o endpoint do LLM, o modelo, o intervalo, os feeds e os temas de interesse, e grava
+/// em `caminho_config` um arquivo comentado com eles, validado antes da gravação. Lê as respostas
+/// do stdin, então também aceita um roteiro pelo pipe.
+pub fn comando_init(caminho_config: &Path, forcar: bool) -> Result<(), Box<dyn Error>> {
+	if caminho_config.exists() && !forcar {
+		return Err(format!(
+			"'{}' já existe; use --force para sobrescrevê-lo.",
+			caminho_config.display()
+		)
+		.into());
+	}
+	let mut entrada = io::stdin().lock();
+	println!(
+		"{}Configuração inicial do FilterFlow{} (Enter aceita o valor entre colchetes)\n",
+		BOLD, RESET
+	);
+
+	let endereco = perguntar_ate(
+		&mut entrada,
+		"Endpoint do LLM (API de chat compatível com OpenAI)",
+		Some("http://localhost:1234/v1/chat/completions"),
+		|r| {
+			validate_url(r)
+				.map(|_| r.to_string())
+				.map_err(|_| "use uma URL http:// ou https://".to_string())
+		},
+	)?;
+	let modelo = perguntar(&mut entrada, "Modelo", Some("liquid/lfm2-1.2b"))?;
+	let intervalo: u64 = perguntar_ate(
+		&mut entrada,
+		"Intervalo entre as coletas, em minutos",
+		Some("30"),
+		|r| match r.parse() {
+			Ok(minutos) if minutos >= 2 => Ok(minutos),
+			_ => Err("informe um número inteiro de pelo menos 2".to_string()),
+		},
+	)?;
+
+	println!("\nFeeds RSS/Atom (deixe o nome vazio para terminar):");
+	let mut feeds = Vec::new();
+	loop {
+		let nome = perguntar(&mut entrada, "  Nome do feed", None)?;
+		if nome.is_empty() {
+			break;
+		}
+		let url = perguntar_ate(&mut entrada, "  URL", None, |r| {
+			validate_url(r)
+				.map(|_| r.to_string())
+				.map_err(|_| "use uma URL http:// ou https://".to_string())
+		})?;
+		feeds.push((nome, url));
+	}
+
+	let lista = |r: &str| -> Vec<String> {
+		r.split(',')
+			.map(str::trim)
+			.filter(|t| !t.is_empty())
+			.map(String::from)
+			.collect()
+	};
+	let relevancia = perguntar_ate(
+		&mut entrada,
+		"\nTemas de interesse, separados por vírgula",
+		None,
+		|r| match lista(r) {
+			temas if temas.is_empty() => Err("informe ao menos um tema".to_string()),
+			temas => Ok(temas),
+		},
+	)?;
+	let irrelevancia = lista(&perguntar(
+		&mut entrada,
+		"Termos a excluir, separados por vírgula (opcional)",
+		None,
+	)?);
+
+	let texto = config_inicial(
+		&endereco,
+		&modelo,
+		intervalo,
+		&feeds,
+		&relevancia,
+		&irrelevancia,
+	);
+	validate_config(&toml::from_str(&texto)?)?;
+	if let Some(pasta) = caminho_config.parent()
+		&& !pasta.as_os_str().is_empty()
+	{
+		fs::create_dir_all(pasta)?;
+	}
+	fs::write(caminho_config, texto)?;
+	println!(
+		"\n{}Configuração gravada em '{}'.{} Confira com `filterflow check-config` e inicie o agente com `filterflow`.",
+		BOLD_GREEN,
+		caminho_config.display(),
+		RESET
+	);
+	Ok(())
+}
+
+/// Faz uma pergunta do `init` e devolve a resposta sem espaços nas pontas, ou o `padrao` se
+/// ela vier vazia. No fim da entrada, a resposta é vazia.
+pub fn perguntar(
+	entrada: &mut impl io::BufRead,
+	pergunta: &str,
+	padrao: Option<&str>,
+) -> Result<String, Box<dyn Error>> {
+	perguntar_ate(entrada, pergunta, padrao, |r| Ok(r.to_string()))
+}
+
+/// Como `perguntar`, mas repete a pergunta até `interpretar` aceitar a resposta. No fim da
+/// entrada, o erro da última resposta encerra o assistente.
+pub fn perguntar_ate<T>(
+	entrada: &mut impl io::BufRead,
+	pergunta: &str,
+	padrao: Option<&str>,
+	interpretar: impl Fn(&str) -> Result<T, String>,
+) -> Result<T, Box<dyn Error>> {
+	loop {
+		let mut linha = String::new();
+		match padrao {
+			Some(padrao) => print!("{} [{}]: ", pergunta, padrao),
+			None => print!("{}: ", pergunta),
+		}
+		io::stdout().flush()?;
+		let fim = entrada.read_line(&mut linha)? == 0;
+		let resposta = match (linha.trim(), padrao) {
+			("", Some(padrao)) => padrao,
+			(resposta, _) => resposta,
+		};
+		match interpretar(resposta) {
+			Ok(valor) => return Ok(valor),
+			Err(e) if fim => return Err(format!("Resposta inválida ({}).", e).into()),
+			Err(e) => println!("{}  {}.{}", BOLD_RED, e, RESET),
+		}
+	}
+}
+
+/// Texto do `filterflow_config.toml` gravado pelo `init`: as respostas e os valores padrão do
+/// exemplo do repositório, com comentários para o que costuma ser ajustado depois.
+pub fn config_inicial(
+	endereco: &str,
+	modelo: &str,
+	intervalo: u64,
+	feeds: &[(String, String)],
+	relevancia: &[String],
+	irrelevancia: &[String],
+) -> String {
+	let texto = |s: &str| toml::Value::String(s.to_string()).to_string();
+	let lista = |termos: &[String]| {
+		if termos.is_empty() {
+			"[]".to_string()
+		} else {
+			let itens: Vec<String> = termos
+				.iter()
+				.map(|t| format!("    {},\n", texto(t)))
+				.collect();
+			format!("[\n{}]", itens.concat())
+		}
+	};
+
+	let mut config = format!(
+		r#"# =================================================================
+# CONFIGURAÇÃO DO AGENTE FILTERFLOW (gerada por `filterflow init`)
+# Todas as opções estão documentadas no README e no filterflow_config.toml do repositório.
+# =================================================================
+
+[geral]
+# Frequência de atualização em minutos (mínimo 2). Cada fonte pode sobrepor com 'intervalo_minutos'.
+intervalo_minutos = {intervalo}
+
+# Endereço da API do LLM compatível com OpenAI (LM Studio, Ollama etc.)
+endereco = {endereco}
+
+# Modelo usado na filtragem e no resumo
+modelo_resumo = {modelo}
+
+# User agent das requisições às fontes (evita bloqueios 403)
+user_agent = "Mozilla/5.0 (X11; Linux x86_64; rv:144.0) Gecko/20100101 Firefox/144.0"
+
+# Token enviado como "Authorization: Bearer" ao endpoint, se ele exigir (ex.: APIs na nuvem).
+# chave_api = "sk-..."
+
+ocultar_latencia = true
+
+max_tokens_filtro = 1
+temperatura_filtro = 0.2
+max_tokens_resumo = 270
+temperatura_resumo = 0.3
+prompt_system_filtro = "Você é um classificador de notícias conciso."
+prompt_system_resumo = "Você é um assistente conciso e imparcial."
+# Mantenha os dois {{}}: neles entram o título e a descrição da notícia
+prompt_user_resumo_template = "Resuma a seguinte notícia em no máximo 250 tokens em português. Se a notícia tiver em inglês o resumo deverá ser em inglês. NÃO adicione introduções como 'O resumo é:' ou 'A notícia é sobre:'.\n\nTítulo: {{}}\n\nCorpo/Descrição: {{}}"
+
+[proxy]
+# Ativa o proxy HTTP/HTTPS, com esquema e porta (ex.: "http://proxy.exemplo:8080")
+usar_proxy = false
+endereco_proxy = ""
+
+[filtro]
+# Temas que tornam uma notícia relevante para o LLM
+indicadores_relevancia = {relevancia}
+
+# Termos que a excluem, mesmo que cite um dos temas (também descartam sem consultar o LLM)
+indicadores_irrelevancia = {irrelevancia}
+
+# Saídas (Obsidian, e-mail, webhooks etc.), sitemaps e outras fontes são configurados
+# em seções próprias, descritas no README.
+"#,
+		intervalo = intervalo,
+		endereco = texto(endereco),
+		modelo = texto(modelo),
+		relevancia = lista(relevancia),
+		irrelevancia = lista(irrelevancia),
+	);
+
+	for (nome, url) in feeds {
+		config.push_str(&format!(
+			"\n[[feeds]]\nnome = {}\nurl = {}\n",
+			texto(nome),
+			texto(url)
+		));
+	}
+	config
+}
+
+/// Valida a configuração e mostra o que ela ativa: fontes por tipo, saídas e modelos.
+pub async fn comando_check_config(
+	caminho_config: &Path,
+	offline: bool,
+) -> Result<(), Box<dyn Error>> {
+	let config = carregar_config(caminho_config).map_err(|e| {
+		format!(
+			"Configuração inválida '{}': {}",
+			caminho_config.display(),
+			e
+		)
+	})?;
+	println!(
+		"{}Configuração válida:{} {}",
+		BOLD_GREEN,
+		RESET,
+		caminho_config.display()
+	);
+	// Só os nomes: os valores costumam ser segredos
+	let sobrepostas: Vec<String> = variaveis_ambiente().into_iter().map(|(n, _)| n).collect();
+	if !sobrepostas.is_empty() {
+		println!("Sobreposto pelo ambiente: {}", sobrepostas.join(", "));
+	}
+
+	let fontes = config.fontes();
+	let mut por_tipo: Vec<(&str, usize)> = Vec::new();
+	for fonte in &fontes {
+		match por_tipo.iter_mut().find(|(tipo, _)| *tipo == fonte.tipo()) {
+			Some((_, quantidade)) => *quantidade += 1,
+			None => por_tipo.push((fonte.tipo(), 1)),
+		}
+	}
+	println!("\n{}Fontes ({}):{}", BOLD, fontes.len(), RESET);
+	for (tipo, quantidade) in por_tipo {
+		println!("  {:<20} {}", tipo, quantidade);
+	}
+
+	let saidas: Vec<&str> = config
+		.saida
+		.ativas()
+		.iter()
+		.map(|(saida, _)| saida.nome())
+		.collect();
+	if saidas.is_empty() {
+		println!("\n{}Saídas ativas:{} nenhuma", BOLD, RESET);
+	} else {
+		println!("\n{}Saídas ativas:{} {}", BOLD, RESET, saidas.join(", "));
+	}
+	println!(
+		"{}Modelo:{} {} em {}",
+		BOLD, RESET, config.geral.modelo_resumo, config.geral.endereco
+	);
+	if let Some(revisao) = &config.revisao {
+		println!("{}Modelo revisor:{} {}", BOLD, RESET, revisao.modelo);
+	}
+
+	let mut verificacoes = vec![verificar_layout(&config.saida.layout)];
+	if !offline {
+		verificacoes.extend(diagnosticar_rede(&config).await?);
+	}
+	println!("\n{}Diagnóstico:{}", BOLD, RESET);
+	let largura = verificacoes
+		.iter()
+		.map(|v| v.item.chars().count())
+		.max()
+		.unwrap_or(0);
+	for verificacao in &verificacoes {
+		let (cor, estado) = if verificacao.ok {
+			(BOLD_GREEN, "OK")
+		} else {
+			(BOLD_RED, "FALHA")
+		};
+		println!(
+			"  {}{:<5}{} {:<largura$}  {}",
+			cor, estado, RESET, verificacao.item, verificacao.detalhe
+		);
+	}
+
+	let falhas = verificacoes.iter().filter(|v| !v.ok).count();
+	if falhas > 0 {
+		return Err(format!(
+			"{} de {} verificações falharam.",
+			falhas,
+			verificacoes.len()
+		)
+		.into());
+	}
+	Ok(())
+}
+
+/// Uma linha do diagnóstico do `check-config`.
+pub struct Verificacao {
+	pub item: String,
+	pub ok: bool,
+	pub detalhe: String,
+}
+
+/// Resolve o `[saida].layout` com um item de exemplo; sobras entre chaves são marcadores
+/// desconhecidos, que iriam parar no nome dos arquivos.
+pub fn verificar_layout(layout: &str) -> Verificacao {
+	let exemplo = RegistroArquivo {
+		titulo: "Título de exemplo".to_string(),
+		link: "https://exemplo.com.br/noticia".to_string(),
+		descricao: String::new(),
+		fonte: MetadadosFonte {
+			nome: "Fonte".to_string(),
+			grupo: Some("Grupo".to_string()),
+			dominio: Some("exemplo.com.br".to_string()),
+			favicon: None,
+		},
+		publicado: None,
+		entregue_em: Utc::now(),
+		resumo: None,
+		modelo_resumo: None,
+		versao_prompt: None,
+		cifrado: None,
+	};
+	let caminho = caminho_exportacao(layout, &exemplo).display().to_string();
+	let desconhecido = caminho.find('{').map(|inicio| {
+		let resto = &caminho[inicio..];
+		resto[..resto.find('}').map_or(resto.len(), |fim| fim + 1)].to_string()
+	});
+	Verificacao {
+		item: "[saida].layout".to_string(),
+		ok: desconhecido.is_none(),
+		detalhe: match desconhecido {
+			Some(marcador) => format!("marcador desconhecido {} em {}", marcador, caminho),
+			None => caminho,
+		},
+	}
+}
+
+/// Verificações que dependem da rede: o LLM (e o revisor) com um prompt trivial, o proxy e um
+/// HEAD em cada URL de feed, sitemap e crawler, feitos em paralelo.
+pub async fn diagnosticar_rede(config: &Config) -> Result<Vec<Verificacao>, Box<dyn Error>> {
+	let client = construir_cliente(&config.geral, &config.proxy, &config.tls)?;
+	let clientes_fonte = construir_clientes_fonte(config)?;
+	let mut verificacoes = Vec::new();
+
+	if config.proxy.usar_proxy {
+		verificacoes.push(verificar_conexao("proxy", &config.proxy.endereco_proxy).await);
+	}
+
+	let mut modelos = vec![("LLM".to_string(), config.geral.clone())];
+	if let Some(revisao) = &config.revisao {
+		let mut geral_revisor = config.geral.clone();
+		if let Some(endereco) = &revisao.endereco {
+			geral_revisor.endereco = endereco.clone();
+			geral_revisor.chave_api = None;
+		}
+		geral_revisor.modelo_resumo = revisao.modelo.clone();
+		if revisao.chave_api.is_some() {
+			geral_revisor.chave_api = revisao.chave_api.clone();
+		}
+		modelos.push(("LLM revisor".to_string(), geral_revisor));
+	}
+	for (item, geral) in modelos {
+		let inicio = Instant::now();
+		let resultado = call_llm_teste(&client, &geral).await;
+		verificacoes.push(Verificacao {
+			item: format!("{} {}", item, geral.modelo_resumo),
+			ok: resultado.is_ok(),
+			detalhe: match resultado {
+				Ok(resposta) => format!("'{}' em {} ms", resposta, inicio.elapsed().as_millis()),
+				Err(e) => format!("{}: {}", geral.endereco, e),
+			},
+		});
+	}
+
+	let urls = config
+		.feeds
+		.iter()
+		.map(|f| (format!("feed '{}'", f.nome), &f.nome, f.url.clone()))
+		.chain(
+			config
+				.sitemaps
+				.iter()
+				.map(|s| (format!("sitemap '{}'", s.nome), &s.nome, s.url.clone())),
+		)
+		.chain(config.crawlers.iter().map(|c| {
+			(
+				format!("crawler '{}'", c.nome),
+				&c.nome,
+				c.url_inicial.clone(),
+			)
+		}));
+	let mut tarefas = tokio::task::JoinSet::new();
+	for (indice, (item, nome, url)) in urls.enumerate() {
+		let client = clientes_fonte.get(nome).unwrap_or(&client).clone();
+		let autenticacao = config
+			.feeds
+			.iter()
+			.find(|f| &f.nome == nome)
+			.and_then(|f| f.autenticacao_para(&url))
+			.map(|(usuario, senha)| (usuario.to_string(), senha.map(str::to_string)));
+		tarefas.spawn(async move {
+			let autenticacao = autenticacao
+				.as_ref()
+				.map(|(usuario, senha)| (usuario.as_str(), senha.as_deref()));
+			(
+				indice,
+				verificar_url(&client, item, &url, autenticacao).await,
+			)
+		});
+	}
+	let mut resultados = tarefas.join_all().await;
+	resultados.sort_by_key(|(indice, _)| *indice);
+	verificacoes.extend(resultados.into_iter().map(|(_, v)| v));
+	Ok(verificacoes)
+}
+
+/// HEAD na URL, com o HTTP Basic do feed, se houver; servidores que não aceitam HEAD
+/// (405/501) recebem um GET.
+pub async fn verificar_url(
+	client: &Client,
+	item: String,
+	url: &str,
+	autenticacao: Option<(&str, Option<&str>)>,
+) -> Verificacao {
+	let inicio = Instant::now();
+	let mut resposta = com_autenticacao(client.head(url), autenticacao)
+		.timeout(StdDuration::from_secs(15))
+		.send()
+		.await;
+	if let Ok(r) = &resposta
+		&& matches!(r.status().as_u16(), 405 | 501)
+	{
+		resposta = com_autenticacao(client.get(url), autenticacao)
+			.timeout(StdDuration::from_secs(15))
+			.send()
+			.await;
+	}
+	match resposta {
+		Ok(r) => Verificacao {
+			item,
+			ok: r.status().is_success(),
+			detalhe: format!("HTTP {} em {} ms", r.status(), inicio.elapsed().as_millis()),
+		},
+		Err(e) => Verificacao {
+			item,
+			ok: false,
+			detalhe: format!("{}: {}", url, e),
+		},
+	}
+}
+
+/// Conexão TCP com o host da URL (o proxy no `check-config`, o LLM no `/healthz` do painel);
+/// a validade das requisições pelo proxy aparece nas URLs das fontes.
+pub async fn verificar_conexao(item: &str, endereco: &str) -> Verificacao {
+	let item = item.to_string();
+	let destino = Url::parse(endereco).ok().and_then(|u| {
+		let host = u.host_str()?.to_string();
+		Some((host, u.port_or_known_default()?))
+	});
+	let Some((host, porta)) = destino else {
+		return Verificacao {
+			item,
+			ok: false,
+			detalhe: format!("endereço inválido '{}'", endereco),
+		};
+	};
+	let conexao = time::timeout(
+		StdDuration::from_secs(5),
+		tokio::net::TcpStream::connect((host.as_str(), porta)),
+	)
+	.await;
+	match conexao {
+		Ok(Ok(_)) => Verificacao {
+			item,
+			ok: true,
+			detalhe: format!("{}:{} aceita conexões", host, porta),
+		},
+		Ok(Err(e)) => Verificacao {
+			item,
+			ok: false,
+			detalhe: format!("{}:{}: {}", host, porta, e),
+		},
+		Err(_) => Verificacao {
+			item,
+			ok: false,
+			detalhe: format!("{}:{}: sem resposta em 5 s", host, porta),
+		},
+	}
+}
+
+/// Contagens do banco: uma linha por árvore, os itens arquivados por fonte e as métricas.
+pub fn comando_stats(banco: &OpcoesBanco) -> Result<(), Box<dyn Error>> {
+	let db = banco.abrir()?;
+
+	// Na árvore principal ficam também os índices de título, que não são itens
+	let processados = db
+		.iter()
+		.keys()
+		.filter(|k| {
+			k.as_ref()
+				.is_ok_and(|k| !k.starts_with(PREFIXO_TITULO.as_bytes()))
+		})
+		.count();
+	println!("{}Banco:{} {}", BOLD, RESET, banco.caminho);
+	println!("  {:<24} {}", "processados", processados);
+	for (nome, arvore) in [
+		("irrelevantes", IRRELEVANT_CACHE_TREE),
+		("arquivados", ARQUIVO_TREE),
+		("fontes conhecidas", FONTES_CONHECIDAS_TREE),
+		("fila do e-mail", FILA_EMAIL_TREE),
+		("entregas pendentes", FILA_ENTREGAS_TREE),
+		("itens na fila", FILA_ITENS_TREE),
+		("alertas no calendário", ALERTAS_TREE),
+		("ações auditadas", AUDITORIA_TREE),
+	] {
+		println!("  {:<24} {}", nome, db.open_tree(arvore)?.len());
+	}
+
+	// O JSON do registro é lido direto: a fonte fica visível mesmo com o arquivo cifrado
+	let mut por_fonte: Vec<(String, usize)> = Vec::new();
+	for valor in db.open_tree(ARQUIVO_TREE)?.iter().values() {
+		let Ok(registro) = serde_json::from_slice::<RegistroArquivo>(&valor?) else {
+			continue;
+		};
+		match por_fonte
+			.iter_mut()
+			.find(|(nome, _)| *nome == registro.fonte.nome)
+		{
+			Some((_, quantidade)) => *quantidade += 1,
+			None => por_fonte.push((registro.fonte.nome, 1)),
+		}
+	}
+	if !por_fonte.is_empty() {
+		por_fonte.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+		println!("\n{}Arquivados por fonte:{}", BOLD, RESET);
+		for (nome, quantidade) in por_fonte {
+			println!("  {:<24} {}", nome, quantidade);
+		}
+	}
+
+	let mut por_fonte: Vec<(String, EstatisticasFonte)> = Vec::new();
+	for entrada in db.open_tree(ESTATISTICAS_TREE)?.iter() {
+		let (nome, valor) = entrada?;
+		if let Ok(estatisticas) = serde_json::from_slice(&valor) {
+			por_fonte.push((String::from_utf8_lossy(&nome).into_owned(), estatisticas));
+		}
+	}
+	if !por_fonte.is_empty() {
+		println!("\n{}Contadores por fonte (acumulados):{}", BOLD, RESET);
+		print!("{}", tabela_estatisticas(&por_fonte));
+	}
+
+	let metricas = db.open_tree(METRICAS_TREE)?;
+	if !metricas.is_empty() {
+		println!("\n{}Métricas:{}", BOLD, RESET);
+		for entrada in metricas.iter() {
+			let (nome, valor) = entrada?;
+			let valor = valor
+				.as_ref()
+				.try_into()
+				.map(u64::from_be_bytes)
+				.unwrap_or(0);
+			println!("  {:<24} {}", String::from_utf8_lossy(&nome), valor);
+		}
+	}
+	Ok(())
+}
+
+/// Código de saída de `once` quando o ciclo terminou, mas alguma fonte não pôde ser coletada.
+pub const SAIDA_FONTES_COM_FALHA: u8 = 2;
+
+/// Baixa o link e o avalia como o pipeline faria, sem consultar nem gravar o banco: pré-filtro,
+/// veredito do filtro, pontuação e resumo, com a latência e a resposta bruta de cada chamada.
+pub async fn comando_test_url(link: &str, caminho_config: &Path) -> Result<(), Box<dyn Error>> {
+	let config = carregar_config(caminho_config)?;
+	let geral = Arc::new(config.geral.clone());
+	let filtro = Arc::new(config.filtro.clone());
+	let client = construir_cliente(&geral, &config.proxy, &config.tls)?;
+	let clientes_fonte = construir_clientes_fonte(&config)?;
+	let feed = config.feeds.iter().find(|f| f.url == link);
+	let client_feed = feed
+		.and_then(|f| clientes_fonte.get(&f.nome))
+		.unwrap_or(&client);
+
+	let conteudo = baixar_feed(
+		client_feed,
+		link,
+		feed.and_then(|f| f.autenticacao_para(link)),
+	)
+	.await
+	.map_err(|e| format!("Não foi possível baixar '{}': {}", link, e))?;
+	// O feed configurado com esse endereço, se houver, decide as regras por fonte
+	let fonte = feed
+		.map(|f| f.nome.clone())
+		.or_else(|| Url::parse(link).ok()?.host_str().map(str::to_string))
+		.unwrap_or_default();
+
+	let canal = (!parece_html(&conteudo))
+		.then(|| ler_canal(&conteudo).ok())
+		.flatten();
+	let noticia = match canal {
+		Some(canal) => canal
+			.items()
+			.iter()
+			.find_map(|item| noticia_do_item_rss(&fonte, item))
+			.ok_or("O feed não tem itens com link.")?,
+		None => {
+			let (titulo, descricao) = metadados_pagina(&String::from_utf8_lossy(&conteudo));
+			ItemNoticia {
+				fonte: fonte.clone(),
+				link: link.to_string(),
+				titulo: titulo.unwrap_or_else(|| link.to_string()),
+				descricao,
+				publicado: None,
+				guid: None,
+				chave: link.to_string(),
+				grupo: None,
+			}
+		}
+	};
+
+	println!("{}{}{}", BOLD, noticia.titulo, RESET);
+	println!("  Link: {}", noticia.link);
+	println!("  Fonte: {}", noticia.fonte);
+	println!("  Descrição: {}", noticia.descricao);
+
+	let citados = contar_termos(&noticia, &filtro.indicadores_irrelevancia);
+	let ativo = config.pipeline.etapas.contains(&Etapa::PreFiltro);
+	println!(
+		"\n{}Pré-filtro{}: {}{}",
+		BOLD,
+		RESET,
+		if citados > 0 {
+			format!(
+				"descartaria ({} indicadores de irrelevância citados)",
+				citados
+			)
+		} else {
+			"aprovaria".to_string()
+		},
+		if ativo { "" } else { " [etapa inativa]" }
+	);
+
+	relatar_filtro(
+		&client,
+		&noticia.titulo,
+		&noticia.descricao,
+		Arc::clone(&filtro),
+		Arc::clone(&geral),
+	)
+	.await?;
+
+	let urgencia = contar_termos(&noticia, &filtro.termos_urgencia);
+	let importancia = pontuar_importancia(&noticia, urgencia, &filtro);
+	let minima = config.resumo.importancia_minima_para(&noticia.fonte);
+	println!(
+		"\n{}Pontuação{}: urgência {}, importância {} (mínima para resumo: {}{})",
+		BOLD,
+		RESET,
+		urgencia,
+		importancia,
+		minima,
+		if importancia < minima {
+			", seria entregue sem resumo"
+		} else {
+			""
+		}
+	);
+
+	let inicio = Instant::now();
+	let (resumo, tokens) = call_llm_summarize(&client, &noticia.titulo, &noticia.descricao, geral)
+		.await
+		.map_err(|e| format!("Falha no resumo: {}", e))?;
+	println!("\n{}Resumo{}:", BOLD, RESET);
+	println!("  Latência: {:.2?}", inicio.elapsed());
+	println!(
+		"  Tokens: {}",
+		tokens.map_or("-".to_string(), |t| t.to_string())
+	);
+	println!("  Resposta bruta: {:?}", resumo);
+	println!("\n{}", resumo);
+	Ok(())
+}
+
+/// Roda só o filtro do LLM sobre um título e uma descrição quaisquer.
+pub async fn comando_test_filter(
+	titulo: &str,
+	descricao: &str,
+	caminho_config: &Path,
+) -> Result<(), Box<dyn Error>> {
+	let config = carregar_config(caminho_config)?;
+	let geral = Arc::new(config.geral.clone());
+	let client = construir_cliente(&geral, &config.proxy, &config.tls)?;
+	relatar_filtro(
+		&client,
+		titulo,
+		descricao,
+		Arc::new(config.filtro.clone()),
+		geral,
+	)
+	.await
+}
+
+/// Chama o filtro e mostra o veredito, a latência, os tokens e a resposta bruta do modelo.
+pub async fn relatar_filtro(
+	client: &Client,
+	titulo: &str,
+	descricao: &str,
+	filtro: Arc<FiltroConfig>,
+	geral: Arc<GeralConfig>,
+) -> Result<(), Box<dyn Error>> {
+	let inicio = Instant::now();
+	let (resposta, tokens) = call_llm_filter_bruto(client, titulo, descricao, filtro, geral)
+		.await
+		.map_err(|e| format!("Falha na filtragem: {}", e))?;
+	let latencia = inicio.elapsed();
+	let veredito = match resposta.as_deref().and_then(interpretar_resposta_filtro) {
+		Some(true) => format!("{}RELEVANTE{}", BOLD_GREEN, RESET),
+		Some(false) => format!("{}IRRELEVANTE{}", BOLD_RED, RESET),
+		None => format!(
+			"{}RESPOSTA INVÁLIDA{} (tratada como irrelevante)",
+			BOLD_RED, RESET
+		),
+	};
+	println!("\n{}Filtro{}: {}", BOLD, RESET, veredito);
+	println!("  Latência: {:.2?}", latencia);
+	println!(
+		"  Tokens: {}",
+		tokens.map_or("-".to_string(), |t| t.to_string())
+	);
+	println!("  Resposta bruta: {:?}", resposta.unwrap_or_default());
+	Ok(())
+}
+
+/// Ponto de entrada do binário: roda um comando avulso ou o loop do agente.
+pub async fn executar(args: Vec<String>) -> Result<ExitCode, Box<dyn std::error::Error>> {
+	let argumentos = Argumentos::parse_from(args);
+	SAIDA_JSON.store(argumentos.output == FormatoSaida::Json, Ordering::Relaxed);
+	rastreio::iniciar();
+	let banco = OpcoesBanco {
+		caminho: argumentos.db.unwrap_or_else(caminho_banco_padrao),
+		somente_leitura: argumentos.read_only,
+	};
+	let caminho_config = argumentos.config.unwrap_or_else(caminho_config_padrao);
+	let config = caminho_config.as_path();
+
+	let padrao = if argumentos.once {
+		Comando::Once
+	} else {
+		Comando::Run
+	};
+
+	let comando = argumentos.comando.unwrap_or(padrao);
+	let simulacao = argumentos.dry_run;
+	if simulacao
+		&& !matches!(
+			comando,
+			Comando::Run | Comando::Once | Comando::Purge { .. } | Comando::Reprocess { .. }
+		) {
+		return Err("--dry-run vale apenas para os comandos run, once, purge e reprocess.".into());
+	}
+	if argumentos.daemon && !matches!(comando, Comando::Run) {
+		return Err("--daemon vale apenas para o comando run.".into());
+	}
+	MODO_DAEMON.store(argumentos.daemon, Ordering::Relaxed);
+
+	// Os comandos avulsos não iniciam o loop do agente
+	let resultado = match comando {
+		Comando::Run => return rodar_agente(config, &banco, false, simulacao).await,
+		Comando::Once => return rodar_agente(config, &banco, true, simulacao).await,
+		Comando::Init { force } => comando_init(config, force),
+		Comando::Poke => comando_poke(&banco),
+		Comando::SystemdUnit { user } => {
+			print!("{}", unidade(config, Path::new(&banco.caminho), user)?);
+			Ok(())
+		}
+		Comando::CheckConfig { offline } => comando_check_config(config, offline).await,
+		Comando::Stats => comando_stats(&banco),
+		Comando::TestFilter { title, description } => {
+			comando_test_filter(&title, &description, config).await
+		}
+		Comando::TestUrl { link } => comando_test_url(&link, config).await,
+		Comando::Open { numero } => comando_open(numero),
+		Comando::Copy { numero } => comando_copy(numero),
+		Comando::ImportOpml { arquivo } => comando_import_opml(&arquivo, config),
+		Comando::Feeds { acao } => match acao {
+			AcaoFeeds::List => comando_feeds_list(config),
+			AcaoFeeds::Add { nome, url } => comando_feeds_add(&nome, &url, config).await,
+			AcaoFeeds::Remove { feed } => comando_feeds_remove(&feed, config),
+		},
+		Comando::Resummarize {
+			since,
+			model,
+			por_minuto,
+		} => comando_resummarize(since, model, por_minuto, config, &banco).await,
+		Comando::MarkRead { chave } => comando_mark_read(&chave, &banco),
+		Comando::Reclassify { chave, classe } => comando_reclassify(&chave, &classe, &banco),
+		Comando::Purge {
+			irrelevant,
+			processed,
+			feed,
+			older_than,
+		} => {
+			let selecao = SelecaoLimpeza {
+				irrelevantes: irrelevant,
+				processados: processed,
+				fonte: feed,
+				mais_antigos_que: older_than,
+			};
+			comando_purge(&selecao, simulacao, &banco)
+		}
+		Comando::Reprocess { feed, since } => {
+			comando_reprocess(feed.as_deref(), since, simulacao, config, &banco).await
+		}
+		Comando::Audit { quantidade } => comando_audit(quantidade, &banco),
+		Comando::Search { termos, limit } => comando_search(&termos, limit, config, &banco),
+		Comando::Export {
+			pasta,
+			format,
+			since,
+		} => comando_export(pasta.as_deref(), format, since, config, &banco),
+		Comando::Import { arquivo, replace } => comando_import(&arquivo, replace, &banco),
+		Comando::Sync { peer, token } => comando_sync(peer, token, config, &banco).await,
+		Comando::Tui { url, token } => comando_tui(url, token, config).await,
+	};
+	resultado.map(|()| ExitCode::SUCCESS)
+}
+
+/// Loop do agente: recarrega a configuração, coleta as fontes vencidas, filtra, resume e
+/// entrega, e espera até a próxima fonte vencer. Com `uma_vez`, termina após o primeiro ciclo,
+/// com `SAIDA_FONTES_COM_FALHA` se alguma fonte falhou. Com `simulacao`, o ciclo roda numa
+/// cópia descartável do banco e nada sai da máquina nem é gravado fora dela.
+pub async fn rodar_agente(
+	caminho_config: &Path,
+	banco: &OpcoesBanco,
+	uma_vez: bool,
+	simulacao: bool,
+) -> Result<ExitCode, Box<dyn std::error::Error>> {
+	if banco.somente_leitura && !simulacao {
+		return Err(
+			"O agente não roda com --read-only; use os comandos de consulta (stats, audit, export)."
+				.into(),
+		);
+	}
+
+	// 1. Inicialização de âncora (Carregar a config uma vez para iniciar o DB e logar)
+	let initial_config = match carregar_config(caminho_config) {
+		Ok(c) => c,
+		Err(e) => {
+			error!(
+				fatal = true,
+				"Falha ao carregar configuração inicial '{}': {}",
+				caminho_config.display(),
+				e
+			);
+			return Err(e);
+		}
+	};
+
+	// O formato e a cópia do log em arquivo começam aqui; mudanças em [log] valem após
+	// reiniciar. O idioma do console acompanha cada recarga da configuração.
+	rastreio::definir_formato(initial_config.log.formato_log);
+	idioma::definir(initial_config.geral.idioma_interface);
+	if let Err(e) = rastreio::abrir_arquivo_log(&initial_config.log) {
+		error!(
+			"Falha ao abrir o arquivo de log '{}': {}",
+			initial_config.log.arquivo.as_deref().unwrap_or_default(),
+			e
+		);
+	}
+
+	informar!("{}", msg!("agente.titulo", BOLD, RESET));
+	informar!(
+		"{}",
+		msg!(
+			"agente.config_carregada",
+			initial_config.geral.modelo_resumo
+		)
+	);
+	informar!(
+		"{}",
+		msg!("agente.intervalo", initial_config.geral.intervalo_minutos)
+	);
+	informar!(
+		"{}",
+		msg!(
+			"agente.indicadores_relevancia",
+			format!("{:?}", initial_config.filtro.indicadores_relevancia)
+		)
+	);
+	informar!(
+		"{}",
+		msg!(
+			"agente.indicadores_irrelevancia",
+			BOLD_RED,
+			format!("{:?}", initial_config.filtro.indicadores_irrelevancia),
+			RESET
+		)
+	);
+
+	// 2. Inicializar o Banco de Dados (sled) - DEVE SER ARC FORA DO LOOP
+	let db = if simulacao {
+		banco.abrir_copia()?
+	} else {
+		db_init_trees(&banco.caminho)?
+	};
+	let db_arc = Arc::new(db); // Empacota o DB em Arc para ser Thread-Safe
+	informar!("{}", msg!("agente.banco", banco.caminho));
+	if simulacao {
+		informar!("{}", msg!("agente.simulacao", BOLD_YELLOW, RESET));
+	}
+
+	// A chave do arquivo é lida uma vez; mudanças em [arquivo] valem após reiniciar
+	let cifra_arquivo = match CifraArquivo::carregar(&initial_config.arquivo) {
+		Ok(cifra) => cifra,
+		Err(e) => {
+			error!(fatal = true, "Falha ao carregar a chave do arquivo: {}", e);
+			return Err(e);
+		}
+	};
+	if cifra_arquivo.is_some() {
+		informar!("{}", msg!("agente.arquivo_cifrado"));
+	}
+
+	// O PID permite ao `filterflow poke` encontrar o agente deste banco
+	let mut recarga = PedidoRecarga::novo();
+	if !uma_vez
+		&& !simulacao
+		&& let Err(e) = fs::write(banco.arquivo_pid(), std::process::id().to_string())
+	{
+		error!("Falha ao gravar '{}': {}", banco.arquivo_pid().display(), e);
+	}
+
+	// Atende a sincronização dos pares; mudanças no endereço valem após reiniciar
+	if let Some(endereco) = &initial_config.sincronizacao.endereco
+		&& let Some(token) = &initial_config.sincronizacao.token
+		&& !uma_vez
+		&& !simulacao
+	{
+		let listener = TcpListener::bind(endereco).await?;
+		informar!("{}", msg!("agente.sincronizacao", endereco));
+		tokio::spawn(sinc_atender(listener, Arc::clone(&db_arc), token.clone()));
+	}
+
+	// Painel web; a chave do arquivo é carregada de novo para a tarefa que o atende
+	let estado_painel: PainelCompartilhado = Arc::new(Mutex::new(EstadoPainel {
+		iniciado_em: Local::now(),
+		intervalo: StdDuration::from_secs(initial_config.geral.intervalo_minutos * 60),
+		endereco_llm: initial_config.geral.endereco.clone(),
+		..Default::default()
+	}));
+	if let Some(painel) = &initial_config.painel
+		&& !uma_vez
+		&& !simulacao
+	{
+		let listener = TcpListener::bind(&painel.endereco).await?;
+		informar!("{}", msg!("agente.painel", painel.endereco));
+		tokio::spawn(painel_atender(
+			listener,
+			ContextoPainel {
+				db: Arc::clone(&db_arc),
+				estado: Arc::clone(&estado_painel),
+				config: painel.clone(),
+				cifra: CifraArquivo::carregar(&initial_config.arquivo)?,
+				cutucada: Arc::clone(&recarga.cutucada),
+			},
+		));
+	}
+
+	// Com o systemd (Type=notify), o agente está pronto; o watchdog prova que os ciclos avançam
+	systemd::notificar("READY=1\nSTATUS=Iniciando o primeiro ciclo");
+	if let Some(intervalo) = intervalo_watchdog() {
+		tokio::spawn(systemd::vigiar(intervalo));
+	}
+
+	// Mudanças no arquivo valem para o próximo item, sem esperar o ciclo seguinte
+	let config_vigente = Arc::new(ConfigVigente::novo(&initial_config));
+	if !uma_vez {
+		tokio::spawn(vigiar_config(
+			caminho_config.to_path_buf(),
+			Arc::clone(&config_vigente),
+			Arc::clone(&recarga.alteracao),
+		));
+	}
+
+	let mut sleep_duration = StdDuration::from_secs(initial_config.geral.intervalo_minutos * 60);
+	// Em `once`, as fontes de cron também são coletadas; no agente, esperam o próximo horário
+	let mut agendador = if uma_vez {
+		Agendador::imediato()
+	} else {
+		Agendador::default()
+	};
+	let mut espera = StdDuration::ZERO;
+	let mut ultimo_email = Instant::now();
+
+	// --- Loop Principal de Atualização ---
+	loop {
+		let mut config = match carregar_config(caminho_config) {
+			Ok(c) => c,
+			Err(e) => {
+				error!(
+					"Não foi possível recarregar o config: {}. Usando a configuração anterior.",
+					e
+				);
+				recarga
+					.esperar(espera.max(StdDuration::from_secs(60)))
+					.await;
+				continue;
+			}
+		};
+		idioma::definir(config.geral.idioma_interface);
+
+		// Recalcula o tempo de sleep se necessário
+		let new_sleep_duration = StdDuration::from_secs(config.geral.intervalo_minutos * 60);
+		if new_sleep_duration != sleep_duration {
+			info!(
+				"Intervalo de atualização alterado para {} minutos.",
+				config.geral.intervalo_minutos
+			);
+			sleep_duration = new_sleep_duration;
+		}
+
+		// Perfil de agenda do dia (dia útil, fim de semana ou feriado)
+		let (nome_perfil, perfil) = config.agenda.perfil_para(Local::now().date_naive());
+		let perfil = perfil.cloned().unwrap_or_default();
+		if let Some(importancia) = perfil.importancia_minima {
+			config.resumo.importancia_minima = importancia;
+		}
+		config_vigente.trocar(&config);
+
+		// Apenas as fontes cujo intervalo venceu são coletadas nesta rodada
+		let padrao_minutos = perfil
+			.intervalo_minutos
+			.unwrap_or(config.geral.intervalo_minutos);
+		let cycle_start_time = Instant::now();
+		agendador.atualizar(padrao_minutos, config.geral.cron.as_ref(), &config.grupos);
+
+		// As fontes pausadas pela API do painel ficam de fora até serem retomadas
+		let pausadas = db_fontes_pausadas(&db_arc).unwrap_or_else(|e| {
+			error!("Falha ao ler as fontes pausadas: {}", e);
+			Default::default()
+		});
+		{
+			let mut estado = estado_painel.lock().unwrap();
+			estado.configuradas = config
+				.fontes()
+				.iter()
+				.map(|f| f.nome().to_string())
+				.collect();
+			estado.intervalo = StdDuration::from_secs(padrao_minutos * 60);
+			estado.endereco_llm = config.geral.endereco.clone();
+		}
+		let fontes_ativas: Vec<FonteConfigurada> = config
+			.fontes()
+			.into_iter()
+			.filter(|f| !pausadas.contains(f.nome()))
+			.collect();
+		let fontes_rodada = agendador.selecionar(fontes_ativas.clone(), cycle_start_time);
+
+		// 3. EMPACOTAMENTO EM ARC (Versão imutável desta iteração)
+		let geral_config_arc = Arc::new(config.geral.clone());
+		let filtro_config_arc = Arc::new(config.filtro.clone());
+		let saida_config_arc = Arc::new(config.saida.clone());
+		let fila_resumo: FilaResumo = Mutex::new(Vec::new());
+		let pipeline = Pipeline::da_config(&config.pipeline);
+
+		// 4. Inicialização Condicional do Cliente HTTP (com Proxy)
+		let clientes = construir_cliente(&geral_config_arc, &config.proxy, &config.tls)
+			.and_then(|c| Ok((c, construir_clientes_fonte(&config)?)));
+		let (client, clientes_fonte) = match clientes {
+			Ok(c) => c,
+			Err(e) => {
+				error!(
+					fatal = true,
+					"Não foi possível configurar o proxy: {}. Verifique o formato.", e
+				);
+				if uma_vez {
+					return Err(e);
+				}
+				recarga.esperar(sleep_duration).await;
+				agendador = Agendador::imediato();
+				continue;
+			}
+		};
+
+		let contexto_etapas = ContextoEtapa {
+			client: &client,
+			db: &db_arc,
+			config: &config_vigente,
+			cifra: cifra_arquivo.as_ref(),
+			fila: &fila_resumo,
+			simulacao,
+			reavaliacao: false,
+		};
+
+		// Bloco de logs do ciclo
+		informar!(
+			"\n{}=================================================={}",
+			BOLD,
+			RESET
+		);
+		informar!("{}", msg!("ciclo.iniciando", BOLD, RESET));
+		informar!(
+			"{}=================================================={}",
+			BOLD,
+			RESET
+		);
+
+		let agora = Local::now();
+		informar!("      {}", agora.format(idioma::texto("ciclo.data")));
+		let chave_perfil = match nome_perfil {
+			"feriado" => "perfil.feriado",
+			"fim de semana" => "perfil.fim_de_semana",
+			_ => "perfil.dia_util",
+		};
+		informar!("{}", msg!("ciclo.perfil", idioma::texto(chave_perfil)));
+		informar!(
+			"{}",
+			msg!("ciclo.fontes", fontes_rodada.len(), config.fontes().len())
+		);
+		if !pausadas.is_empty() {
+			informar!(
+				"{}",
+				msg!(
+					"ciclo.pausadas",
+					pausadas.iter().cloned().collect::<Vec<_>>().join(", ")
+				)
+			);
+		}
+
+		emitir_evento(
+			"cycle_start",
+			serde_json::json!({
+				"perfil": nome_perfil,
+				"fontes": fontes_rodada.len(),
+				"fontes_total": config.fontes().len(),
+			}),
+		);
+
+		// A numeração dos itens relevantes recomeça a cada ciclo
+		if !simulacao && let Err(e) = referencias_limpar() {
+			error!("Falha ao reiniciar as referências numeradas: {}", e);
+		}
+
+		// 4.1. Traz do par o que ele já processou, para não entregar de novo
+		if let Some(par) = &config.sincronizacao.par
+			&& !simulacao
+		{
+			match sincronizar_com_par(&client, &db_arc, par, config.sincronizacao.token.as_deref())
+				.await
+			{
+				Ok((recebidas, enviadas)) => info!(
+					"{}: {} chaves novas recebidas, {} enviadas.",
+					par, recebidas, enviadas
+				),
+				Err(e) => error!("Falha ao sincronizar com {}: {}", par, e),
+			}
+		}
+
+		// 5. Coleta dos itens de todas as fontes; a fila de itens perde as fontes removidas
+		let todas = config.fontes();
+		let configuradas: BTreeSet<&str> = todas.iter().map(|f| f.nome()).collect();
+		match db_fila_limpar_orfaos(&db_arc, &configuradas) {
+			Ok(0) => {}
+			Ok(n) => info!("{} itens de fontes removidas saíram da fila de itens.", n),
+			Err(e) => error!("Falha ao limpar a fila de itens: {}", e),
+		}
+		let mut lotes = coletar_fontes(
+			&client,
+			&clientes_fonte,
+			&db_arc, // Passando o Arc<Db>
+			&fontes_rodada,
+			Arc::clone(&geral_config_arc),
+			cifra_arquivo.as_ref(),
+			simulacao,
+		)
+		.await;
+		let fontes_com_falha = lotes.iter().filter(|l| l.falha_coleta).count();
+
+		// 6. Filtragem em rodízio entre as fontes (com cota de chamadas ao LLM)
+		let rejeitados = filtrar_round_robin(&contexto_etapas, &pipeline, &mut lotes).await;
+
+		// 6.1. Revisão periódica dos irrelevantes com o modelo maior; os promovidos entram na fila
+		let mut promovidos = Vec::new();
+		let revisao_vencida = |revisao: &RevisaoConfig| match db_ultima_revisao(&db_arc) {
+			Ok(ultima) => ultima.is_none_or(|u| {
+				i64::try_from(revisao.intervalo_horas)
+					.ok()
+					.and_then(chrono::Duration::try_hours)
+					.is_some_and(|intervalo| Utc::now() - u >= intervalo)
+			}),
+			Err(e) => {
+				error!("Falha ao ler a data da última revisão: {}", e);
+				false
+			}
+		};
+		if let Some(revisao) = &config.revisao
+			&& revisao_vencida(revisao)
+		{
+			if let Err(e) = db_registrar_revisao(&db_arc, Utc::now()) {
+				error!("Falha ao registrar a revisão: {}", e);
+			}
+			match revisar_irrelevantes(
+				&client,
+				&db_arc,
+				revisao,
+				Arc::clone(&filtro_config_arc),
+				&geral_config_arc,
+				&todas,
+				cifra_arquivo.as_ref(),
+			)
+			.await
+			{
+				Ok(itens) => {
+					promovidos = itens.iter().map(|i| i.noticia.chave.clone()).collect();
+					fila_resumo.lock().unwrap().extend(itens);
+				}
+				Err(e) => error!("Falha na revisão dos irrelevantes: {}", e),
+			}
+		}
+
+		// 6.2. Espelho dos rejeitados em SQLite
+		if let Some(sqlite) = &saida_config_arc.sqlite
+			&& !rejeitados.is_empty()
+			&& !simulacao
+		{
+			let agora = Utc::now();
+			let linhas: Vec<LinhaSqlite> = rejeitados
+				.iter()
+				.map(|r| LinhaSqlite::rejeitado(r, agora))
+				.collect();
+			if let Err(e) = sqlite_gravar(sqlite, &linhas) {
+				error!("Falha ao espelhar os itens rejeitados: {}", e);
+			}
+		}
+
+		// 6.3. Novas tentativas das entregas que falharam nos ciclos anteriores
+		let contexto = ContextoSaida {
+			client: &client,
+			db: &db_arc,
+			layout: &saida_config_arc.layout,
+			cifra: cifra_arquivo.as_ref(),
+		};
+		let reenvio = if simulacao {
+			Ok((0, 0))
+		} else {
+			entregas_reenviar(&saida_config_arc, &contexto).await
+		};
+		match reenvio {
+			Ok((0, 0)) => {}
+			Ok((entregues, descartadas)) => info!(
+				"{} entregas pendentes concluídas, {} descartadas.",
+				entregues, descartadas
+			),
+			Err(e) => error!("Falha ao reenviar as entregas pendentes: {}", e),
+		}
+
+		// 7. Fase de resumo e entrega (fila priorizada por urgência e recência)
+		let relevantes = fila_resumo.lock().unwrap().len();
+		processar_fila_resumo(&contexto_etapas, &pipeline).await;
+		// Os promovidos pela revisão só saem do cache de irrelevância depois de processados
+		if let Err(e) = db_descachear_processados(&db_arc, &promovidos) {
+			error!(
+				"Falha ao tirar os promovidos do cache de irrelevância: {}",
+				e
+			);
+		}
+
+		// 7.1. Resumo por e-mail dos itens acumulados desde o último envio
+		if let Some(email) = &saida_config_arc.email
+			&& !simulacao
+			&& ultimo_email.elapsed() >= StdDuration::from_secs(email.intervalo_horas * 3600)
+		{
+			match email_enviar_resumo(&db_arc, email, cifra_arquivo.as_ref()).await {
+				Ok(enviados) => {
+					ultimo_email = Instant::now();
+					if enviados > 0 {
+						info!("Resumo com {} itens enviado.", enviados);
+					}
+				}
+				Err(e) => error!(
+					"Falha ao enviar o resumo (nova tentativa no próximo ciclo): {}",
+					e
+				),
+			}
+		}
+
+		// 7.2. Amostra de QA dos itens rejeitados, para conferir falsos negativos
+		if config.qa.amostras_por_ciclo > 0 && !simulacao {
+			entregar_amostra_qa(&rejeitados, config.qa.amostras_por_ciclo, &saida_config_arc);
+		}
+
+		// 7.3. Site estático com os itens arquivados, regerado ao fim de cada ciclo
+		if let Some(site) = &saida_config_arc.site
+			&& !simulacao
+			&& let Err(e) = site_gerar(
+				&db_arc,
+				site,
+				&saida_config_arc.layout,
+				cifra_arquivo.as_ref(),
+			) {
+			error!("Falha ao gerar o site estático: {}", e);
+		}
+
+		// 7.4. Calendário dos alertas vigentes; os expirados saem a cada ciclo
+		if let Some(calendario) = &saida_config_arc.calendario
+			&& !simulacao
+			&& let Err(e) = calendario_gerar(&db_arc, calendario)
+		{
+			error!("Falha ao gerar o calendário de alertas: {}", e);
+		}
+
+		// 7.5. Contadores por fonte: resumo do ciclo e acumulado no banco
+		let estatisticas: Vec<(String, EstatisticasFonte)> = lotes
+			.iter()
+			.map(|l| (l.nome.clone(), l.estatisticas.clone()))
+			.collect();
+		if !estatisticas.is_empty() {
+			informar!(
+				"{}",
+				msg!(
+					"ciclo.resumo_fontes",
+					BOLD,
+					RESET,
+					tabela_estatisticas(&estatisticas).trim_end()
+				)
+			);
+		}
+		for (fonte, ciclo) in &estatisticas {
+			if let Err(e) = db_somar_estatisticas(&db_arc, fonte, ciclo) {
+				error!("Falha ao gravar as estatísticas de '{}': {}", fonte, e);
+			}
+		}
+
+		let cycle_duration = cycle_start_time.elapsed();
+
+		informar!(
+			"{}",
+			msg!(
+				"ciclo.concluido",
+				BOLD_GREEN,
+				format!("{:.2?}", cycle_duration),
+				RESET
+			)
+		);
+
+		// Taxa de utilização do tempo do sistema
+		let utilizacao =
+			cycle_duration.as_secs_f32() / (geral_config_arc.intervalo_minutos as f32 * 60.0);
+		informar!(
+			"{}",
+			msg!(
+				"ciclo.utilizacao",
+				BOLD_YELLOW,
+				format!("{:.2}", utilizacao * 100.0),
+				RESET
+			)
+		);
+
+		let agora_final = Local::now();
+		informar!(
+			"             {}\n",
+			agora_final.format(idioma::texto("ciclo.data"))
+		);
+
+		// 8. Lógica de Espera: até a próxima fonte vencer
+		espera = fontes_ativas
+			.iter()
+			.map(|f| agendador.espera(f))
+			.min()
+			.unwrap_or(StdDuration::from_secs(padrao_minutos * 60));
+		// No horário de silêncio, acorda quando ele termina para entregar o que ficou guardado
+		if let Some(silencio) = &config.saida.silencio {
+			let fim = silencio.janela.espera_fechamento(agora_final);
+			if !fim.is_zero() {
+				espera = espera.min(fim + StdDuration::from_secs(1));
+			}
+		}
+
+		// 8.1. Situação do ciclo para o painel web
+		{
+			let mut estado = estado_painel.lock().unwrap();
+			for lote in &lotes {
+				estado.fontes.insert(
+					lote.nome.clone(),
+					SituacaoFonte {
+						coletada_em: agora_final,
+						estatisticas: lote.estatisticas.clone(),
+						falha_coleta: lote.falha_coleta,
+					},
+				);
+			}
+			estado.ultimo_ciclo = Some(CicloPainel {
+				concluido_em: agora_final,
+				duracao: cycle_duration,
+				relevantes,
+				rejeitados: rejeitados.len(),
+				fontes_com_falha,
+				proximo_em: agora_final + Duration::from_std(espera).unwrap_or_default(),
+			});
+		}
+
+		emitir_evento(
+			"cycle_end",
+			serde_json::json!({
+				"duracao_segundos": cycle_duration.as_secs_f64(),
+				"relevantes": relevantes,
+				"rejeitados": rejeitados.len(),
+				"fontes_com_falha": fontes_com_falha,
+				"estatisticas": estatisticas
+					.iter()
+					.map(|(fonte, e)| {
+						let mut valor = serde_json::to_value(e).unwrap_or_default();
+						valor["fonte"] = fonte.clone().into();
+						valor
+					})
+					.collect::<Vec<_>>(),
+				"proximo_em_segundos": espera.as_secs(),
+			}),
+		);
+
+		if uma_vez {
+			if fontes_com_falha > 0 {
+				error!(
+					"{} de {} fontes não puderam ser coletadas.",
+					fontes_com_falha,
+					fontes_rodada.len()
+				);
+				return Ok(ExitCode::from(SAIDA_FONTES_COM_FALHA));
+			}
+			return Ok(ExitCode::SUCCESS);
+		}
+
+		systemd::notificar(&format!(
+			"STATUS=Último ciclo às {}: {} relevantes, {} fontes com falha; próximo em {:.0} min",
+			Local::now().format("%H:%M"),
+			relevantes,
+			fontes_com_falha,
+			espera.as_secs_f32() / 60.0
+		));
+		informar!(
+			"{}",
+			msg!(
+				"ciclo.aguardando",
+				BOLD_GREEN,
+				format!("{:.1}", espera.as_secs_f32() / 60.0),
+				RESET
+			)
+		);
+
+		// Um SIGHUP (`filterflow poke`) recarrega a configuração e coleta todas as fontes já
+		if recarga.esperar(espera).await {
+			informar!("{}", msg!("ciclo.recarga", BOLD_YELLOW, RESET));
+			agendador = Agendador::imediato();
+		}
+	}
+}
+
+/// SIGHUP enviado ao agente (`filterflow poke`, `systemctl reload`) ou `POST /cycle/run` na API
+/// do painel: interrompe a espera entre os ciclos. Fora do Unix, só a API a interrompe.
+pub struct PedidoRecarga {
+	#[cfg(unix)]
+	sinal: Option<tokio::signal::unix::Signal>,
+	// Um pedido feito durante o ciclo fica guardado e encerra a espera seguinte na hora
+	pub cutucada: Arc<Notify>,
+	// Mudança no arquivo de configuração (`vigiar_config`): encerra só a espera em curso
+	pub alteracao: Arc<Notify>,
+}
+
+impl PedidoRecarga {
+	pub fn novo() -> Self {
+		#[cfg(unix)]
+		{
+			use tokio::signal::unix::{SignalKind, signal};
+			let sinal = signal(SignalKind::hangup())
+				.map_err(|e| error!("Falha ao tratar o SIGHUP: {}", e))
+				.ok();
+			Self {
+				sinal,
+				cutucada: Arc::default(),
+				alteracao: Arc::default(),
+			}
+		}
+		#[cfg(not(unix))]
+		{
+			Self {
+				cutucada: Arc::default(),
+				alteracao: Arc::default(),
+			}
+		}
+	}
+
+	/// Espera `duracao` ou até um pedido; devolve `true` se um pedido interrompeu a espera. Uma
+	/// mudança na configuração também a encerra, mas devolve `false`: só as fontes novas e as
+	/// já vencidas são coletadas. Durante a espera, o watchdog do systemd segue avisado.
+	pub async fn esperar(&mut self, duracao: StdDuration) -> bool {
+		systemd::em_espera(true);
+		let interrompida = self.aguardar(duracao).await;
+		systemd::em_espera(false);
+		interrompida
+	}
+
+	async fn aguardar(&mut self, duracao: StdDuration) -> bool {
+		#[cfg(unix)]
+		if let Some(sinal) = &mut self.sinal {
+			return tokio::select! {
+				_ = time::sleep(duracao) => false,
+				_ = self.alteracao.notified() => false,
+				_ = sinal.recv() => true,
+				_ = self.cutucada.notified() => true,
+			};
+		}
+		tokio::select! {
+			_ = time::sleep(duracao) => false,
+			_ = self.alteracao.notified() => false,
+			_ = self.cutucada.notified() => true,
+		}
+	}
+}
+
+/// Vigia o arquivo de configuração pela data de modificação, a cada 2 s. Quando ele muda e
+/// continua válido, as etapas passam a usar a nova configuração no próximo item, e a espera
+/// entre os ciclos termina para que as fontes novas sejam coletadas logo. Um arquivo inválido
+/// é ignorado até a próxima mudança.
+pub async fn vigiar_config(caminho: PathBuf, vigente: Arc<ConfigVigente>, alteracao: Arc<Notify>) {
+	let modificado = |caminho: &Path| fs::metadata(caminho).and_then(|m| m.modified()).ok();
+	let mut visto = modificado(&caminho);
+	let mut relogio = time::interval(StdDuration::from_secs(2));
+	loop {
+		relogio.tick().await;
+		let atual = modificado(&caminho);
+		if atual == visto {
+			continue;
+		}
+		visto = atual;
+		let mut config = match carregar_config(&caminho) {
+			Ok(c) => c,
+			Err(e) => {
+				error!("Configuração alterada ignorada: {}", e);
+				continue;
+			}
+		};
+		// O perfil da agenda ajusta a importância mínima, como no início do ciclo
+		let (_, perfil) = config.agenda.perfil_para(Local::now().date_naive());
+		if let Some(importancia) = perfil.and_then(|p| p.importancia_minima) {
+			config.resumo.importancia_minima = importancia;
+		}
+		vigente.trocar(&config);
+		idioma::definir(config.geral.idioma_interface);
+		info!("Configuração recarregada de '{}'.", caminho.display());
+		// Só uma espera em curso termina; durante um ciclo, a mudança já vale para as etapas
+		alteracao.notify_waiters();
+	}
+}
+
+/// `filterflow poke`: pede ao agente que usa o banco de `--db` que recarregue a configuração
+/// e comece um ciclo agora, com um SIGHUP ao PID do arquivo `arquivo_pid`.
+pub fn comando_poke(banco: &OpcoesBanco) -> Result<(), Box<dyn Error>> {
+	let arquivo = banco.arquivo_pid();
+	let pid: u32 = fs::read_to_string(&arquivo)
+		.map_err(|_| {
+			format!(
+				"Nenhum agente rodando com o banco '{}' ('{}' não existe).",
+				banco.caminho,
+				arquivo.display()
+			)
+		})?
+		.trim()
+		.parse()?;
+
+	// O agente morto deixa o arquivo para trás, e o PID pode já ser de outro processo
+	#[cfg(target_os = "linux")]
+	if !fs::read(format!("/proc/{}/cmdline", pid))
+		.is_ok_and(|linha| String::from_utf8_lossy(&linha).contains("filterflow"))
+	{
+		fs::remove_file(&arquivo)?;
+		return Err(format!("O agente (PID {}) não está mais rodando.", pid).into());
+	}
+
+	let status = Command::new("kill")
+		.args(["-HUP", &pid.to_string()])
+		.status()?;
+	if !status.success() {
+		return Err(format!("Não foi possível sinalizar o agente (PID {}).", pid).into());
+	}
+	println!(
+		"{}Agente (PID {}) avisado:{} ele recarrega a configuração e inicia um ciclo agora.",
+		BOLD_GREEN, pid, RESET
+	);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn interpretar_duracao_unidades() {
+		assert_eq!(interpretar_duracao("30d"), Ok(Duration::days(30)));
+		assert_eq!(interpretar_duracao("12h"), Ok(Duration::hours(12)));
+		assert_eq!(interpretar_duracao("90m"), Ok(Duration::minutes(90)));
+		assert!(interpretar_duracao("").is_err());
+		assert!(interpretar_duracao("d").is_err());
+		assert!(interpretar_duracao("3s").is_err());
+		assert!(interpretar_duracao("5é").is_err());
+		assert!(interpretar_duracao("-5d").is_err());
+		assert!(interpretar_duracao("0h").is_err());
+		assert!(interpretar_duracao("999999999999999d").is_err());
+		assert!(interpretar_duracao("99999999999d").is_err());
+		assert!(interpretar_duracao("9223372036854775807m").is_err());
+		assert!(interpretar_instante("99999999999d").is_err());
+	}
+
+	#[test]
+	fn normalizar_busca_ignora_caixa_e_acentos() {
+		assert_eq!(normalizar_busca("Ação em SÃO Paulo"), "acao em sao paulo");
+		assert_eq!(normalizar_busca("Pingüim, Ñandú"), "pinguim, nandu");
+	}
+}
@@ -0,0 +1,343 @@
+//! Idioma das mensagens do console (`[geral].idioma_interface`): o catálogo traz, para cada
+//! chave, o texto em português e em inglês. O idioma dos resumos não depende deste: ele segue
+//! os prompts configurados.
+//!
+//! Os textos usam `{}` para os valores, preenchidos em ordem por [`preencher`] (ou pelo macro
+//! `msg!`); formatos com precisão ou alinhamento são aplicados antes, por quem chama.
+
+use std::{
+	fmt::{self, Write as _},
+	sync::atomic::{AtomicU8, Ordering},
+};
+
+use tracing::warn;
+
+use crate::config::Idioma;
+
+static ATUAL: AtomicU8 = AtomicU8::new(0);
+
+/// Troca o idioma das próximas mensagens.
+pub fn definir(idioma: Idioma) {
+	ATUAL.store(idioma as u8, Ordering::Relaxed);
+}
+
+pub fn atual() -> Idioma {
+	match ATUAL.load(Ordering::Relaxed) {
+		1 => Idioma::En,
+		_ => Idioma::PtBr,
+	}
+}
+
+// (chave, pt-BR, en)
+const CATALOGO: &[(&str, &str, &str)] = &[
+	// Início do agente
+	(
+		"agente.titulo",
+		"{}--- FilterFlow: Agente de Notícias para LLMs locais ---{}",
+		"{}--- FilterFlow: News Agent for local LLMs ---{}",
+	),
+	(
+		"agente.config_carregada",
+		"Configuração carregada. Modelo de Resumo: {}",
+		"Configuration loaded. Summary model: {}",
+	),
+	(
+		"agente.intervalo",
+		"Intervalo de Atualização: {} minutos",
+		"Update interval: {} minutes",
+	),
+	(
+		"agente.indicadores_relevancia",
+		"\nIndicadores de relevância: \n{}",
+		"\nRelevance indicators: \n{}",
+	),
+	(
+		"agente.indicadores_irrelevancia",
+		"\nIndicadores de irrelevância: \n{}{}{}",
+		"\nIrrelevance indicators: \n{}{}{}",
+	),
+	(
+		"agente.banco",
+		"\nBanco de dados iniciado em: {}",
+		"\nDatabase opened at: {}",
+	),
+	(
+		"agente.simulacao",
+		"{}[SIMULAÇÃO] O banco é uma cópia descartável; nada será entregue às saídas.{}",
+		"{}[SIMULATION] The database is a throwaway copy; nothing will be delivered to the outputs.{}",
+	),
+	(
+		"agente.arquivo_cifrado",
+		"Arquivo de itens relevantes cifrado (ChaCha20-Poly1305).",
+		"Relevant items archive is encrypted (ChaCha20-Poly1305).",
+	),
+	(
+		"agente.sincronizacao",
+		"Sincronização com pares atendida em: {}",
+		"Peer sync served at: {}",
+	),
+	(
+		"agente.painel",
+		"Painel web em: http://{}",
+		"Web dashboard at: http://{}",
+	),
+	// Ciclo
+	(
+		"ciclo.iniciando",
+		"{}        Iniciando ciclo de varredura...{}",
+		"{}        Starting scan cycle...{}",
+	),
+	// Formato do chrono
+	(
+		"ciclo.data",
+		"Data: %d/%m/%Y - Hora: %H:%M:%S",
+		"Date: %Y-%m-%d - Time: %H:%M:%S",
+	),
+	(
+		"ciclo.perfil",
+		"      Perfil da agenda: {}",
+		"      Schedule profile: {}",
+	),
+	("perfil.dia_util", "dia útil", "weekday"),
+	("perfil.fim_de_semana", "fim de semana", "weekend"),
+	("perfil.feriado", "feriado", "holiday"),
+	(
+		"ciclo.fontes",
+		"      Fontes nesta rodada: {} de {}\n",
+		"      Sources this round: {} of {}\n",
+	),
+	(
+		"ciclo.pausadas",
+		"      Fontes pausadas: {}\n",
+		"      Paused sources: {}\n",
+	),
+	(
+		"ciclo.resumo_fontes",
+		"\n{}Resumo por fonte:{}\n{}",
+		"\n{}Per-source summary:{}\n{}",
+	),
+	(
+		"ciclo.concluido",
+		"\n{} ***************** CICLO CONCLUÍDO *****************\n                  Tempo Total: {} {}",
+		"\n{} ***************** CYCLE COMPLETE *****************\n                  Total time: {} {}",
+	),
+	(
+		"ciclo.utilizacao",
+		"\n      {}Índice de utilização do sistema: {}%{}",
+		"\n      {}System utilization: {}%{}",
+	),
+	(
+		"ciclo.aguardando",
+		"\n{} [INFO] Aguardando {} minutos para a próxima checagem...{}",
+		"\n{} [INFO] Waiting {} minutes until the next check...{}",
+	),
+	(
+		"ciclo.recarga",
+		"\n{}[INFO] Pedido de recarga recebido: novo ciclo com a configuração atual.{}",
+		"\n{}[INFO] Reload requested: new cycle with the current configuration.{}",
+	),
+	// Coleta
+	(
+		"fonte.processando",
+		"--- Processando Fonte: {}{}{} ---",
+		"--- Processing source: {}{}{} ---",
+	),
+	("fonte.itens", " {} itens ✅", " {} items ✅"),
+	(
+		"fonte.urls",
+		"\n{} URLs coletadas ✅",
+		"\n{} URLs collected ✅",
+	),
+	(
+		"fonte.relevantes",
+		"\n{}*** {} NOVAS NOTÍCIAS RELEVANTES ENCONTRADAS PARA {} ***{}",
+		"\n{}*** {} NEW RELEVANT ITEMS FOUND FOR {} ***{}",
+	),
+	(
+		"crawler.visitadas",
+		"\n[INFO CRAWLER] '{}': {} páginas visitadas",
+		"\n[INFO CRAWLER] '{}': {} pages visited",
+	),
+	(
+		"feed.descoberto",
+		"\n[INFO FEED] '{}' é uma página HTML. Feeds anunciados: {}. Usando: {}",
+		"\n[INFO FEED] '{}' is an HTML page. Advertised feeds: {}. Using: {}",
+	),
+	(
+		"feed.inalterado",
+		"\n[INFO FEED] '{}' não mudou desde a última coleta (304).",
+		"\n[INFO FEED] '{}' has not changed since the last fetch (304).",
+	),
+	(
+		"sitemap.robots",
+		"\n\n[INFO SITEMAP] {} sitemaps descobertos no robots.txt de {}",
+		"\n\n[INFO SITEMAP] {} sitemaps found in the robots.txt of {}",
+	),
+	(
+		"sitemap.baixando",
+		"\n\n[INFO SITEMAP] Baixando: {}",
+		"\n\n[INFO SITEMAP] Downloading: {}",
+	),
+	(
+		"sitemap.antigas",
+		"\n[INFO SITEMAP] {} URLs ignoradas por <lastmod> anterior a {} horas.",
+		"\n[INFO SITEMAP] {} URLs skipped for a <lastmod> older than {} hours.",
+	),
+	(
+		"sitemap.fora_do_padrao",
+		"\n[INFO SITEMAP] {} URLs ignoradas pelos padrões de inclusão/exclusão.",
+		"\n[INFO SITEMAP] {} URLs skipped by the include/exclude patterns.",
+	),
+	// Tabela de estatísticas
+	("tabela.fonte", "Fonte", "Source"),
+	("tabela.coletados", "coletados", "collected"),
+	("tabela.cache", "cache", "cache"),
+	("tabela.descartados", "descartados", "discarded"),
+	("tabela.relevantes", "relevantes", "relevant"),
+	("tabela.erros", "erros", "errors"),
+	("tabela.chamadas", "chamadas", "calls"),
+	("tabela.latencia", "latência LLM", "LLM latency"),
+	// Itens
+	(
+		"item.relevante",
+		"\n\n{}[NOVA E RELEVANTE]{} {}Título: {}{}{}",
+		"\n\n{}[NEW AND RELEVANT]{} {}Title: {}{}{}",
+	),
+	("item.link", "{}Link:{} {}", "{}Link:{} {}"),
+	("item.fonte", "{}Fonte:{} {}{}{}", "{}Source:{} {}{}{}"),
+	(
+		"item.resumo",
+		"\n{}Resumo (Modelo: {}):\n{}{}\n",
+		"\n{}Summary (Model: {}):\n{}{}\n",
+	),
+	(
+		"item.resumo_omitido",
+		"{}Resumo omitido (baixa importância).{}",
+		"{}Summary skipped (low importance).{}",
+	),
+	(
+		"item.simulacao",
+		"\n{}[SIMULAÇÃO] Entregaria '{}'{} a: {}",
+		"\n{}[SIMULATION] Would deliver '{}'{} to: {}",
+	),
+	("item.nenhuma_saida", "nenhuma saída", "no output"),
+	// Amostra de QA e revisão
+	(
+		"qa.titulo",
+		"\n{}--- QA: {} de {} itens rejeitados neste ciclo ---{}",
+		"\n{}--- QA: {} of {} items rejected this cycle ---{}",
+	),
+	(
+		"qa.rejeitado",
+		"{}[REJEITADO]{} {} ({})",
+		"{}[REJECTED]{} {} ({})",
+	),
+	(
+		"revisao.titulo",
+		"\n{}--- REVISÃO: reavaliando {} de {} itens rejeitados com {} ---{}",
+		"\n{}--- REVIEW: re-checking {} of {} rejected items with {} ---{}",
+	),
+	(
+		"revisao.promovido",
+		"{}[PROMOVIDO]{} {} ({})",
+		"{}[PROMOTED]{} {} ({})",
+	),
+];
+
+/// Texto da chave no idioma atual. Uma chave fora do catálogo volta como está, para o erro
+/// aparecer na própria saída, e é avisada no log (nos builds de depuração, interrompe).
+pub fn texto(chave: &str) -> &str {
+	let idioma = atual();
+	match CATALOGO.iter().find(|(c, _, _)| *c == chave) {
+		Some((_, pt, en)) => match idioma {
+			Idioma::PtBr => pt,
+			Idioma::En => en,
+		},
+		None => {
+			if cfg!(debug_assertions) {
+				panic!("mensagem fora do catálogo: {}", chave);
+			}
+			warn!("Mensagem fora do catálogo: {}", chave);
+			chave
+		}
+	}
+}
+
+/// Texto da chave com cada `{}` trocado, em ordem, pelos valores.
+pub fn preencher(chave: &str, valores: &[&dyn fmt::Display]) -> String {
+	let modelo = texto(chave);
+	debug_assert_eq!(
+		modelo.matches("{}").count(),
+		valores.len(),
+		"número de valores da mensagem {}",
+		chave
+	);
+	let mut saida = String::new();
+	let mut valores = valores.iter();
+	let mut partes = modelo.split("{}");
+	if let Some(inicio) = partes.next() {
+		saida.push_str(inicio);
+	}
+	for parte in partes {
+		if let Some(valor) = valores.next() {
+			let _ = write!(saida, "{}", valor);
+		}
+		saida.push_str(parte);
+	}
+	saida
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use regex::Regex;
+	use std::{collections::HashSet, fs, path::Path};
+
+	#[test]
+	fn catalogo_tem_chaves_unicas_e_os_mesmos_valores_nos_dois_idiomas() {
+		let mut chaves = HashSet::new();
+		for (chave, pt, en) in CATALOGO {
+			assert!(chaves.insert(chave), "chave repetida: {}", chave);
+			assert!(!pt.is_empty() && !en.is_empty(), "texto vazio em {}", chave);
+			assert_eq!(
+				pt.matches("{}").count(),
+				en.matches("{}").count(),
+				"{}: pt e en com números diferentes de valores",
+				chave
+			);
+		}
+	}
+
+	fn fontes_rust(pasta: &Path, arquivos: &mut Vec<String>) {
+		for entrada in fs::read_dir(pasta).unwrap() {
+			let caminho = entrada.unwrap().path();
+			if caminho.is_dir() {
+				fontes_rust(&caminho, arquivos);
+			} else if caminho.extension().is_some_and(|e| e == "rs") {
+				arquivos.push(fs::read_to_string(&caminho).unwrap());
+			}
+		}
+	}
+
+	#[test]
+	fn chaves_usadas_no_codigo_estao_no_catalogo() {
+		let uso = Regex::new(r#"(?:msg!|idioma::texto|idioma::preencher)\(\s*"([^"]+)""#).unwrap();
+		let mut arquivos = Vec::new();
+		fontes_rust(
+			&Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+			&mut arquivos,
+		);
+		let mut usadas = 0;
+		for fonte in &arquivos {
+			for caps in uso.captures_iter(fonte) {
+				assert!(
+					CATALOGO.iter().any(|(c, _, _)| *c == &caps[1]),
+					"mensagem fora do catálogo: {}",
+					&caps[1]
+				);
+				usadas += 1;
+			}
+		}
+		assert!(usadas > 0);
+	}
+}
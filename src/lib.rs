@@ -0,0 +1,167 @@
+//! FilterFlow: coleta notícias de várias fontes, filtra e resume com um LLM local e entrega
+//! os itens relevantes nas saídas configuradas.
+//!
+//! O binário `filterflow` só repassa os argumentos para [`cli::executar`]; os módulos abaixo
+//! podem ser usados diretamente por outros programas:
+//!
+//! - [`config`]: estruturas do `filterflow_config.toml`, carga e validação;
+//! - [`llm`]: cliente da API compatível com OpenAI (filtro e resumo);
+//! - [`pipeline`]: coleta das fontes, deduplicação, filtragem e resumo de cada ciclo;
+//! - [`sources`]: leitura de feeds, sitemaps, GitHub, sites rastreados e pastas locais;
+//! - [`store`]: banco sled, arquivo de itens relevantes e sua cifra;
+//! - [`saidas`]: as saídas (Obsidian, Telegram, e-mail, webhook etc.) e a fila de reenvio;
+//! - [`sincronizacao`]: troca de itens vistos entre instâncias;
+//! - [`tela`]: interface interativa no terminal (`filterflow tui`);
+//! - [`idioma`]: catálogo das mensagens do console (pt-BR e en);
+//! - [`rastreio`]: destino dos eventos do `tracing` (níveis e `RUST_LOG`).
+
+use chrono::Utc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Constantes ANSI para formatação de saída no terminal
+pub const BOLD: &str = "\x1b[1m";
+pub const BOLD_GREEN: &str = "\x1b[1;32m";
+pub const RESET: &str = "\x1b[0m";
+pub const BOLD_RED: &str = "\x1b[1;31m";
+pub const BOLD_YELLOW: &str = "\x1b[1;33m";
+
+// Com `--output json` o stdout do agente só traz eventos JSON, um por linha
+pub static SAIDA_JSON: AtomicBool = AtomicBool::new(false);
+
+// `--daemon`: o log perde as cores e, com a saída no journal, ganha a prioridade de cada linha
+pub static MODO_DAEMON: AtomicBool = AtomicBool::new(false);
+// No modo daemon, o stdout parou no meio de uma linha
+static LINHA_ABERTA: AtomicBool = AtomicBool::new(false);
+
+/// `println!` do log legível do agente; não escreve nada com `--output json` nem com o log em
+/// JSON (`formato_log = "json"`).
+macro_rules! informar {
+	($($arg:tt)*) => {
+		if $crate::log_legivel() {
+			$crate::escrever_log(&format!("{}\n", format_args!($($arg)*)), false);
+		}
+	};
+}
+
+/// Escreve no stdout (ou no stderr, com `erro`) uma mensagem do log: as de `informar!` e os
+/// eventos do `tracing` (ver [`rastreio`]). No modo daemon, as cores ANSI saem e, se o systemd
+/// ligou a saída ao journal (`JOURNAL_STREAM`), cada linha começa com a prioridade syslog no
+/// formato `<N>` do sd-daemon: 7 para `[DEBUG` e `[TRACE`; 6 no stdout; no stderr, 6 para
+/// `[INFO`, 2 para `[ERRO FATAL`, 3 para os demais erros e 4 para o resto. Linhas em branco,
+/// que só separam blocos no terminal, não vão ao journal. Com `[log].arquivo`, o texto também
+/// é copiado para o arquivo.
+pub fn escrever_log(texto: &str, erro: bool) {
+	escrever_com_prioridade(texto, erro, None);
+}
+
+/// [`escrever_log`] com a prioridade do journal já decidida (as linhas JSON do log não têm o
+/// prefixo de onde ela é tirada).
+pub fn escrever_com_prioridade(texto: &str, erro: bool, prioridade: Option<u8>) {
+	rastreio::gravar_no_arquivo(texto, erro);
+	if !MODO_DAEMON.load(Ordering::Relaxed) {
+		if erro {
+			eprint!("{}", texto);
+		} else {
+			print!("{}", texto);
+		}
+		return;
+	}
+
+	let texto = sem_cores(texto);
+	if std::env::var_os("JOURNAL_STREAM").is_none() {
+		if erro {
+			eprint!("{}", texto);
+		} else {
+			print!("{}", texto);
+		}
+		return;
+	}
+
+	let inicio = texto.trim_start();
+	let prioridade = prioridade.unwrap_or(
+		if inicio.starts_with("[DEBUG") || inicio.starts_with("[TRACE") {
+			7
+		} else if !erro || inicio.starts_with("[INFO") {
+			6
+		} else if inicio.starts_with("[ERRO FATAL") {
+			2
+		} else if inicio.starts_with("[ERRO") || inicio.starts_with("Erro") {
+			3
+		} else {
+			4
+		},
+	);
+	let mut continuacao = !erro && LINHA_ABERTA.swap(!texto.ends_with('\n'), Ordering::Relaxed);
+	let mut saida = String::with_capacity(texto.len() + 8);
+	for linha in texto.split_inclusive('\n') {
+		if continuacao {
+			continuacao = false;
+		} else if linha.trim().is_empty() {
+			continue;
+		} else {
+			saida.push_str(&format!("<{}>", prioridade));
+		}
+		saida.push_str(linha);
+	}
+	if erro {
+		eprint!("{}", saida);
+	} else {
+		print!("{}", saida);
+	}
+}
+
+/// Remove as sequências de cor ANSI (`ESC [ ... m`) do texto.
+pub fn sem_cores(texto: &str) -> String {
+	let mut limpo = String::with_capacity(texto.len());
+	let mut caracteres = texto.chars();
+	while let Some(c) = caracteres.next() {
+		if c == '\x1b' {
+			for c in caracteres.by_ref() {
+				if c.is_ascii_alphabetic() {
+					break;
+				}
+			}
+		} else {
+			limpo.push(c);
+		}
+	}
+	limpo
+}
+
+/// O log legível (`informar!`) está ativo: nem `--output json` nem `formato_log = "json"`.
+pub fn log_legivel() -> bool {
+	!SAIDA_JSON.load(Ordering::Relaxed) && !rastreio::LOG_JSON.load(Ordering::Relaxed)
+}
+
+/// Escreve um evento do modo `--output json` no stdout: o objeto `dados` com `evento` e `em`.
+/// Com o log em JSON, o evento também entra no log (ver [`rastreio::registrar_evento`]).
+pub fn emitir_evento(evento: &str, mut dados: serde_json::Value) {
+	dados["evento"] = evento.into();
+	dados["em"] = Utc::now().to_rfc3339().into();
+	rastreio::registrar_evento(&dados);
+	if SAIDA_JSON.load(Ordering::Relaxed) {
+		println!("{}", dados);
+	}
+}
+
+/// Mensagem do catálogo de [`idioma`] no idioma do console, com os `{}` preenchidos em ordem.
+macro_rules! msg {
+	($chave:expr $(, $valor:expr)* $(,)?) => {
+		$crate::idioma::preencher($chave, &[$(&$valor as &dyn ::std::fmt::Display),*])
+	};
+}
+
+pub mod cli;
+pub mod config;
+pub mod idioma;
+pub mod llm;
+pub mod painel;
+pub mod pipeline;
+pub mod rastreio;
+pub mod saidas;
+pub mod sincronizacao;
+pub mod socks;
+pub mod sources;
+pub mod store;
+pub mod systemd;
+pub mod tela;
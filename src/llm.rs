@@ -0,0 +1,322 @@
+//! Cliente da API de chat compatível com OpenAI (LM Studio, Ollama etc.): filtro e resumo.
+
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use std::{error::Error, sync::Arc, time::Duration as StdDuration, time::Instant};
+use tokio::sync::Semaphore;
+use tracing::{Instrument, debug, debug_span, info, trace, warn};
+
+use crate::{
+	config::*,
+	sources::{ESPERA_MAXIMA_PAUSA, espera_retry_after, esperar_vez_do_host, pausar_host},
+	systemd,
+};
+
+// Uma chamada por vez ao LLM: as fontes são coletadas em paralelo, mas um servidor local
+// responde melhor em fila do que a pedidos simultâneos
+static VEZ_LLM: Semaphore = Semaphore::const_new(1);
+
+// Novas chamadas depois de um 429 do endpoint, e a espera quando ele não manda `Retry-After`
+const TENTATIVAS_429: u32 = 3;
+const ESPERA_PADRAO_429: StdDuration = StdDuration::from_secs(5);
+
+/// Envia a chamada ao LLM. Um 429 pausa o endpoint pelo tempo do `Retry-After` e a chamada é
+/// repetida ao fim da pausa, em vez de virar erro; uma pausa longa demais faz as chamadas
+/// seguintes falharem sem consultar o servidor até ela acabar.
+async fn enviar_ao_llm(
+	requisicao: RequestBuilder,
+	endereco: &str,
+) -> Result<Response, Box<dyn Error>> {
+	let mut tentativas = 0;
+	loop {
+		esperar_vez_do_host(endereco, StdDuration::ZERO).await?;
+		let Some(tentativa) = requisicao.try_clone() else {
+			return Ok(requisicao.send().await?);
+		};
+		let resposta = tentativa.send().await?;
+		systemd::sinal_de_vida();
+		if resposta.status() != StatusCode::TOO_MANY_REQUESTS {
+			return Ok(resposta);
+		}
+		let espera = espera_retry_after(&resposta).unwrap_or(ESPERA_PADRAO_429);
+		pausar_host(endereco, espera);
+		if tentativas >= TENTATIVAS_429 || espera > ESPERA_MAXIMA_PAUSA {
+			return Ok(resposta);
+		}
+		tentativas += 1;
+		warn!(
+			"O LLM em {} pediu uma pausa (429); nova chamada em {:.0?}.",
+			endereco, espera
+		);
+	}
+}
+
+// --- Estruturas para Comunicação com a API OpenAI/LM Studio ---
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Message {
+	pub role: String,
+	pub content: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ChatCompletionRequest {
+	pub model: String,
+	pub messages: Vec<Message>,
+	pub max_tokens: u32,
+	pub temperatura: f32,
+	pub stream: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ChatCompletionResponse {
+	pub choices: Vec<Choice>,
+	// Nem todo servidor informa o consumo
+	#[serde(default)]
+	pub usage: Option<UsoTokens>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct UsoTokens {
+	pub total_tokens: u32,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct Choice {
+	pub message: Message,
+}
+
+// =================================================================
+// FUNÇÕES DE COMUNICAÇÃO LLM (TIMEOUTS FIXOS REVERTIDOS)
+// =================================================================
+
+/// Função de resumo das notícias por llm
+pub async fn call_llm_summarize(
+	client: &Client,
+	title: &str,
+	description: &str,
+	config: Arc<GeralConfig>, // Recebe a config como Arc
+) -> Result<(String, Option<u32>), Box<dyn std::error::Error>> {
+	// 1. Injeção da variável no template
+	let prompt_content = format!(
+		"{} {} {}",
+		&config.prompt_user_resumo_template, title, description
+	);
+
+	let request_body = ChatCompletionRequest {
+		model: config.modelo_resumo.to_string(),
+		messages: vec![
+			Message {
+				role: "system".to_string(),
+				content: config.prompt_system_resumo.clone(),
+			},
+			Message {
+				role: "user".to_string(),
+				content: prompt_content,
+			},
+		],
+		max_tokens: config.max_tokens_resumo,
+		temperatura: config.temperatura_resumo,
+		stream: false,
+	};
+
+	// TIMEOUT FIXO REVERTIDO PARA 30s
+	let mut request = client
+		.post(&config.endereco)
+		.json(&request_body)
+		.timeout(StdDuration::from_secs(30));
+	if let Some(chave) = &config.chave_api {
+		request = request.bearer_auth(chave);
+	}
+	let _vez = VEZ_LLM.acquire().await?;
+	let span = debug_span!("llm", etapa = "resumo", modelo = %config.modelo_resumo);
+	let start_time = Instant::now();
+	let response = enviar_ao_llm(request, &config.endereco)
+		.instrument(span.clone())
+		.await?;
+	span.in_scope(|| debug!("Resposta em {:.2?}", start_time.elapsed()));
+
+	if !response.status().is_success() {
+		return Err(format!(
+			"Erro de Status HTTP no Resumo ({}): {}",
+			config.endereco,
+			response.status()
+		)
+		.into());
+	}
+
+	let response_json: ChatCompletionResponse = response.json().await?;
+	let tokens = response_json.usage.map(|u| u.total_tokens);
+	span.in_scope(|| trace!(?tokens, "Resumo recebido"));
+
+	if let Some(choice) = response_json.choices.into_iter().next() {
+		return Ok((choice.message.content.trim().to_string(), tokens));
+	}
+
+	Ok(("[Resposta de resumo vazia]".to_string(), tokens))
+}
+
+/// Filtro de relevância de notícias executado por llm
+pub async fn call_llm_filter(
+	client: &Client,
+	title: &str,
+	description: &str,
+	filtro_config: Arc<FiltroConfig>, // Recebe a config de filtro
+	geral_config: Arc<GeralConfig>,   // Recebe a config geral
+) -> Result<(bool, Option<u32>), Box<dyn std::error::Error>> {
+	let (resposta, tokens) =
+		call_llm_filter_bruto(client, title, description, filtro_config, geral_config).await?;
+	let Some(resposta) = resposta else {
+		return Ok((false, tokens));
+	};
+
+	let is_relevant = interpretar_resposta_filtro(&resposta).unwrap_or_else(|| {
+		warn!(
+			"LLM falhou ao retornar '1' ou '0'. Resposta: '{}'. Notícia ignorada.",
+			resposta.trim()
+		);
+		false
+	});
+	Ok((is_relevant, tokens))
+}
+
+/// Veredito do filtro: '1' relevante, '0' irrelevante; qualquer outra resposta é inválida.
+pub fn interpretar_resposta_filtro(resposta: &str) -> Option<bool> {
+	match resposta.trim() {
+		"1" => Some(true),
+		"0" => Some(false),
+		_ => None,
+	}
+}
+
+/// Chamada do filtro sem interpretar a resposta: o texto devolvido pelo modelo (None se ele não
+/// devolveu nenhuma escolha) e os tokens gastos.
+pub async fn call_llm_filter_bruto(
+	client: &Client,
+	title: &str,
+	description: &str,
+	filtro_config: Arc<FiltroConfig>,
+	geral_config: Arc<GeralConfig>,
+) -> Result<(Option<String>, Option<u32>), Box<dyn std::error::Error>> {
+	// Acesso aos termos
+	let termos1 = filtro_config.indicadores_relevancia.join(", ");
+	let termos2 = filtro_config.indicadores_irrelevancia.join(", ");
+
+	// 1. Injeção da variável no template
+	let prompt_content = format!(
+		"Avalie a relevância da notícia. Título: '{}' | Descrição: '{}'.\n\nCondições:\n1. A notícia é **principalmente** sobre um ou mais destes tópicos de INCLUSÃO: ({})\n2. A notícia **NÃO pode** estar relacionado a nenhum dos seguintes termos: ({}).\n\nSe AMBAS as condições forem satisfeitas, responda '1'. Caso contrário, responda '0'. Responda APENAS '1' ou '0'.",
+		title, description, termos1, termos2
+	);
+
+	let request_body = ChatCompletionRequest {
+		model: geral_config.modelo_resumo.to_string(),
+		messages: vec![
+			Message {
+				role: "system".to_string(),
+				content: geral_config.prompt_system_filtro.clone(),
+			},
+			Message {
+				role: "user".to_string(),
+				content: prompt_content,
+			},
+		],
+		max_tokens: geral_config.max_tokens_filtro,
+		temperatura: geral_config.temperatura_filtro,
+		stream: false,
+	};
+
+	let _vez = VEZ_LLM.acquire().await?;
+	let start_time = Instant::now();
+
+	// TIMEOUT FIXO REVERTIDO PARA 10s
+	let mut request = client
+		.post(&geral_config.endereco)
+		.json(&request_body)
+		.timeout(StdDuration::from_secs(10));
+	if let Some(chave) = &geral_config.chave_api {
+		request = request.bearer_auth(chave);
+	}
+	let span = debug_span!("llm", etapa = "filtro", modelo = %geral_config.modelo_resumo);
+	let response = enviar_ao_llm(request, &geral_config.endereco)
+		.instrument(span.clone())
+		.await?;
+
+	// Com `ocultar_latencia = false`, a latência sai no nível info; senão, só com RUST_LOG=debug
+	let duration = start_time.elapsed();
+	let bytes = response.content_length().unwrap_or(0);
+	span.in_scope(|| {
+		if geral_config.ocultar_latencia.unwrap_or(true) {
+			debug!(
+				"Tempo LLM: {:.2?} (Tamanho da Resposta: {} bytes)",
+				duration, bytes
+			);
+		} else {
+			info!(
+				"Tempo LLM: {:.2?} (Tamanho da Resposta: {} bytes)",
+				duration, bytes
+			);
+		}
+	});
+
+	if !response.status().is_success() {
+		let status = response.status();
+		let error_body = response.text().await.unwrap_or_else(|_| "N/A".to_string());
+		return Err(format!(
+			"Erro de Status HTTP na Filtragem ({}): Status: {}. Corpo: {}",
+			geral_config.endereco, status, error_body
+		)
+		.into());
+	}
+
+	let response_json: ChatCompletionResponse = response.json().await?;
+	let tokens = response_json.usage.map(|u| u.total_tokens);
+	let resposta = response_json
+		.choices
+		.into_iter()
+		.next()
+		.map(|choice| choice.message.content);
+	span.in_scope(|| trace!(?resposta, ?tokens, "Resposta do filtro"));
+
+	Ok((resposta, tokens))
+}
+
+/// Chamada mínima ao endpoint (`filterflow check-config`): confere que o servidor responde e
+/// conhece o modelo. Devolve o texto da resposta.
+pub async fn call_llm_teste(
+	client: &Client,
+	geral_config: &GeralConfig,
+) -> Result<String, Box<dyn std::error::Error>> {
+	let request_body = ChatCompletionRequest {
+		model: geral_config.modelo_resumo.to_string(),
+		messages: vec![Message {
+			role: "user".to_string(),
+			content: "Responda apenas: ok".to_string(),
+		}],
+		max_tokens: 5,
+		temperatura: 0.0,
+		stream: false,
+	};
+
+	let mut request = client
+		.post(&geral_config.endereco)
+		.json(&request_body)
+		.timeout(StdDuration::from_secs(30));
+	if let Some(chave) = &geral_config.chave_api {
+		request = request.bearer_auth(chave);
+	}
+	let _vez = VEZ_LLM.acquire().await?;
+	let response = enviar_ao_llm(request, &geral_config.endereco).await?;
+	if !response.status().is_success() {
+		return Err(format!("HTTP {}", response.status()).into());
+	}
+
+	let response_json: ChatCompletionResponse = response.json().await?;
+	response_json
+		.choices
+		.into_iter()
+		.next()
+		.map(|choice| {
+			let resposta: Vec<&str> = choice.message.content.split_whitespace().collect();
+			resposta.join(" ").chars().take(40).collect()
+		})
+		.ok_or_else(|| "resposta sem escolhas".into())
+}
@@ -0,0 +1,560 @@
+//! Painel web embutido (`[painel]`): itens relevantes recentes, situação das fontes e do último
+//! ciclo, servidos pelo próprio agente enquanto ele roda.
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::{DateTime, Local, Utc};
+use serde::Deserialize;
+use sled::{Db, IVec};
+use std::{
+	cmp::Reverse,
+	collections::{BTreeMap, BinaryHeap},
+	error::Error,
+	io,
+	sync::{Arc, Mutex},
+	time::Duration as StdDuration,
+};
+use tokio::io::{AsyncWriteExt, BufReader as AsyncBufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+use tracing::{error, info};
+
+use crate::{
+	cli::verificar_conexao, config::*, pipeline::*, saidas::*, sincronizacao::*, store::*,
+};
+
+/// Situação exibida no painel, atualizada pelo agente no início e no fim de cada ciclo.
+#[derive(Debug, Default, Clone)]
+pub struct EstadoPainel {
+	pub iniciado_em: DateTime<Local>,
+	pub ultimo_ciclo: Option<CicloPainel>,
+	// Da configuração do ciclo atual: nomes das fontes, intervalo padrão e endpoint do LLM
+	pub configuradas: Vec<String>,
+	pub intervalo: StdDuration,
+	pub endereco_llm: String,
+	// Última coleta de cada fonte; as de intervalo longo não entram em todo ciclo
+	pub fontes: BTreeMap<String, SituacaoFonte>,
+}
+
+pub type PainelCompartilhado = Arc<Mutex<EstadoPainel>>;
+
+/// Cabeçalho com que o `filterflow tui` se identifica na API, para que a auditoria separe as
+/// ações feitas por ele das feitas por outras ferramentas.
+pub const CABECALHO_CLIENTE: &str = "X-FilterFlow-Cliente";
+
+#[derive(Debug, Clone)]
+pub struct CicloPainel {
+	pub concluido_em: DateTime<Local>,
+	pub duracao: StdDuration,
+	pub relevantes: usize,
+	pub rejeitados: usize,
+	pub fontes_com_falha: usize,
+	pub proximo_em: DateTime<Local>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SituacaoFonte {
+	pub coletada_em: DateTime<Local>,
+	pub estatisticas: EstatisticasFonte,
+	pub falha_coleta: bool,
+}
+
+/// Confere o cabeçalho "Authorization: Basic": qualquer usuário, com a senha do `[painel]`.
+pub fn painel_autorizado(autorizacao: Option<&str>, senha: &str) -> bool {
+	autorizacao
+		.and_then(|a| a.strip_prefix("Basic "))
+		.and_then(|a| BASE64.decode(a.trim()).ok())
+		.and_then(|a| String::from_utf8(a).ok())
+		.is_some_and(|a| {
+			a.split_once(':')
+				.is_some_and(|(_, s)| segredo_confere(s, senha))
+		})
+}
+
+/// Só o que é preciso para ordenar e filtrar um registro arquivado (fica em claro mesmo cifrado).
+#[derive(Deserialize)]
+struct OrdemArquivo {
+	entregue_em: DateTime<Utc>,
+	fonte: FonteArquivo,
+}
+
+#[derive(Deserialize)]
+struct FonteArquivo {
+	nome: String,
+}
+
+/// Os `limite` registros arquivados mais recentes (da `fonte`, se houver), do mais novo ao mais
+/// antigo. O arquivo é percorrido uma vez guardando só os `limite` melhores, e apenas esses são
+/// decodificados por inteiro; os registros voltam ainda selados.
+pub fn arquivo_recentes(
+	db: &Db,
+	limite: usize,
+	fonte: Option<&str>,
+) -> Result<Vec<(String, RegistroArquivo)>, Box<dyn Error>> {
+	let mut melhores: BinaryHeap<Reverse<(DateTime<Utc>, IVec, IVec)>> = BinaryHeap::new();
+	if limite == 0 {
+		return Ok(Vec::new());
+	}
+	for entrada in db.open_tree(ARQUIVO_TREE)?.iter() {
+		let (chave, valor) = entrada?;
+		let Ok(ordem) = serde_json::from_slice::<OrdemArquivo>(&valor) else {
+			continue;
+		};
+		if fonte.is_some_and(|f| ordem.fonte.nome != f) {
+			continue;
+		}
+		if melhores.len() == limite {
+			if melhores
+				.peek()
+				.is_some_and(|Reverse((d, _, _))| *d >= ordem.entregue_em)
+			{
+				continue;
+			}
+			melhores.pop();
+		}
+		melhores.push(Reverse((ordem.entregue_em, chave, valor)));
+	}
+	let mut registros = Vec::with_capacity(melhores.len());
+	for Reverse((_, chave, valor)) in melhores.into_sorted_vec() {
+		if let Ok(registro) = serde_json::from_slice(&valor) {
+			registros.push((String::from_utf8_lossy(&chave).into_owned(), registro));
+		}
+	}
+	Ok(registros)
+}
+
+/// Página do painel: último ciclo, tabela das fontes e os `itens` arquivados mais recentes.
+pub fn painel_pagina(
+	db: &Db,
+	estado: &EstadoPainel,
+	itens: usize,
+	cifra: Option<&CifraArquivo>,
+) -> Result<String, Box<dyn Error>> {
+	let mut conteudo = String::from("<h1>FilterFlow</h1>\n");
+	match &estado.ultimo_ciclo {
+		Some(ciclo) => conteudo.push_str(&format!(
+			"<p>Último ciclo concluído em {} ({:.1?}): {} relevantes, {} rejeitados, \
+			 {} fontes com falha. Próximo ciclo às {}.</p>\n",
+			ciclo.concluido_em.format("%d/%m/%Y %H:%M:%S"),
+			ciclo.duracao,
+			ciclo.relevantes,
+			ciclo.rejeitados,
+			ciclo.fontes_com_falha,
+			ciclo.proximo_em.format("%H:%M")
+		)),
+		None => conteudo.push_str("<p>O primeiro ciclo ainda está em andamento.</p>\n"),
+	}
+	let pendentes = db.open_tree(FILA_ENTREGAS_TREE)?.len();
+	if pendentes > 0 {
+		conteudo.push_str(&format!(
+			"<p class=\"falha\">{} entregas aguardando nova tentativa.</p>\n",
+			pendentes
+		));
+	}
+
+	// Acumulados do banco, somados aos das fontes coletadas desde o início do agente
+	let mut acumulados: BTreeMap<String, EstatisticasFonte> = BTreeMap::new();
+	for entrada in db.open_tree(ESTATISTICAS_TREE)?.iter() {
+		let (fonte, valor) = entrada?;
+		if let Ok(total) = serde_json::from_slice(&valor) {
+			acumulados.insert(String::from_utf8_lossy(&fonte).into_owned(), total);
+		}
+	}
+	for fonte in estado.fontes.keys() {
+		acumulados.entry(fonte.clone()).or_default();
+	}
+	conteudo.push_str(
+		"<h2>Fontes</h2>\n<table><tr><th>Fonte</th><th>Última coleta</th><th>Coletados</th>\
+		 <th>Relevantes</th><th>Erros</th><th>Total de relevantes</th><th>Total de erros</th></tr>\n",
+	);
+	let pausadas = db_fontes_pausadas(db)?;
+	for (fonte, total) in &acumulados {
+		let marca = if pausadas.contains(fonte) {
+			" (pausada)"
+		} else {
+			""
+		};
+		let ultima = match estado.fontes.get(fonte) {
+			Some(s) if s.falha_coleta => format!(
+				"<td class=\"falha\">{} (falha)</td><td>{}</td><td>{}</td><td>{}</td>",
+				s.coletada_em.format("%d/%m %H:%M"),
+				s.estatisticas.coletados,
+				s.estatisticas.relevantes,
+				s.estatisticas.erros
+			),
+			Some(s) => format!(
+				"<td>{}</td><td>{}</td><td>{}</td><td>{}</td>",
+				s.coletada_em.format("%d/%m %H:%M"),
+				s.estatisticas.coletados,
+				s.estatisticas.relevantes,
+				s.estatisticas.erros
+			),
+			None => "<td>—</td><td></td><td></td><td></td>".to_string(),
+		};
+		conteudo.push_str(&format!(
+			"<tr><td>{}{}</td>{}<td>{}</td><td>{}</td></tr>\n",
+			html_escapar(fonte),
+			marca,
+			ultima,
+			total.relevantes,
+			total.erros
+		));
+	}
+	conteudo.push_str("</table>\n");
+
+	// A data de entrega fica em claro: só os itens exibidos são decifrados
+	let registros = arquivo_recentes(db, itens, None)?;
+	conteudo.push_str("<h2>Itens recentes</h2>\n");
+	if registros.is_empty() {
+		conteudo.push_str("<p>Nenhum item arquivado.</p>\n");
+	}
+	for (_, mut registro) in registros {
+		registro.abrir(cifra)?;
+		conteudo.push_str(&site_artigo(&registro, None));
+	}
+	Ok(site_pagina("FilterFlow — painel", &conteudo))
+}
+
+/// O que as conexões do painel compartilham com o agente.
+pub struct ContextoPainel {
+	pub db: Arc<Db>,
+	pub estado: PainelCompartilhado,
+	pub config: PainelConfig,
+	pub cifra: Option<CifraArquivo>,
+	// Encerra a espera do agente entre os ciclos (ver `PedidoRecarga`)
+	pub cutucada: Arc<Notify>,
+}
+
+/// Resposta da API: status, Content-Type e corpo.
+pub type RespostaPainel = (u16, &'static str, String);
+
+fn resposta_json(status: u16, valor: serde_json::Value) -> RespostaPainel {
+	(status, "application/json", valor.to_string())
+}
+
+fn resposta_erro(status: u16, mensagem: &str) -> RespostaPainel {
+	resposta_json(status, serde_json::json!({ "erro": mensagem }))
+}
+
+fn decodificar_url(texto: &str) -> String {
+	url::form_urlencoded::parse(format!("v={}", texto).as_bytes())
+		.next()
+		.map(|(_, v)| v.into_owned())
+		.unwrap_or_default()
+}
+
+/// `GET /items?limit=20&source=<fonte>`: itens arquivados mais recentes, com os resumos.
+pub fn api_itens(
+	db: &Db,
+	consulta: &[(String, String)],
+	cifra: Option<&CifraArquivo>,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+	let mut limite = 20;
+	let mut fonte = None;
+	for (nome, valor) in consulta {
+		match nome.as_str() {
+			"limit" => limite = valor.parse().map_err(|_| "limit deve ser um número.")?,
+			"source" => fonte = Some(valor.as_str()),
+			_ => return Err(format!("Parâmetro desconhecido: {}", nome).into()),
+		}
+	}
+	let registros = arquivo_recentes(db, limite, fonte)?;
+	let mut itens = Vec::with_capacity(registros.len());
+	for (chave, mut registro) in registros {
+		registro.abrir(cifra)?;
+		let mut item = serde_json::to_value(&registro)?;
+		item["chave"] = chave.into();
+		itens.push(item);
+	}
+	Ok(itens.into())
+}
+
+/// `GET /sources`: fontes configuradas com a última coleta, os totais e se estão pausadas.
+pub fn api_fontes(db: &Db, estado: &EstadoPainel) -> Result<serde_json::Value, Box<dyn Error>> {
+	let pausadas = db_fontes_pausadas(db)?;
+	let acumulados = db.open_tree(ESTATISTICAS_TREE)?;
+	let mut fontes = Vec::with_capacity(estado.configuradas.len());
+	for nome in &estado.configuradas {
+		let total: EstatisticasFonte = acumulados
+			.get(nome.as_bytes())?
+			.and_then(|v| serde_json::from_slice(&v).ok())
+			.unwrap_or_default();
+		let situacao = estado.fontes.get(nome);
+		fontes.push(serde_json::json!({
+			"nome": nome,
+			"pausada": pausadas.contains(nome),
+			"ultima_coleta": situacao.map(|s| s.coletada_em.to_rfc3339()),
+			"falha_coleta": situacao.is_some_and(|s| s.falha_coleta),
+			"ultimo_ciclo": situacao.map(|s| &s.estatisticas),
+			"total": total,
+		}));
+	}
+	Ok(fontes.into())
+}
+
+/// `GET /healthz`, sem autenticação, para orquestradores de contêineres: último ciclo, conexão
+/// com o LLM e banco. Responde 503 se o banco falha ou se o último ciclo (ou o início do agente)
+/// passou de `tolerancia_intervalos` vezes o intervalo; o intervalo é o maior entre o padrão e
+/// a espera planejada depois do ciclo, que pode ser longa nos grupos com `dias` e `janela`.
+pub async fn painel_saude(contexto: &ContextoPainel) -> RespostaPainel {
+	let (referencia, intervalo, ultimo, endereco_llm) = {
+		let estado = contexto.estado.lock().unwrap();
+		match &estado.ultimo_ciclo {
+			Some(ciclo) => (
+				ciclo.concluido_em,
+				estado.intervalo.max(
+					(ciclo.proximo_em - ciclo.concluido_em)
+						.to_std()
+						.unwrap_or_default(),
+				),
+				Some(ciclo.concluido_em.to_rfc3339()),
+				estado.endereco_llm.clone(),
+			),
+			None => (
+				estado.iniciado_em,
+				estado.intervalo,
+				None,
+				estado.endereco_llm.clone(),
+			),
+		}
+	};
+	let limite = intervalo * contexto.config.tolerancia_intervalos;
+	let atraso = (Local::now() - referencia).to_std().unwrap_or_default();
+	let llm = verificar_conexao("llm", &endereco_llm).await;
+	let banco = contexto.db.size_on_disk();
+
+	let situacao = if banco.is_err() {
+		"erro"
+	} else if atraso > limite {
+		"atrasado"
+	} else {
+		"ok"
+	};
+	resposta_json(
+		if situacao == "ok" { 200 } else { 503 },
+		serde_json::json!({
+			"status": situacao,
+			"ultimo_ciclo": ultimo,
+			"segundos_desde_o_ciclo": atraso.as_secs(),
+			"limite_segundos": limite.as_secs(),
+			"llm": { "ok": llm.ok, "detalhe": llm.detalhe },
+			"banco": match banco {
+				Ok(tamanho) => serde_json::json!({ "ok": true, "bytes": tamanho }),
+				Err(e) => serde_json::json!({ "ok": false, "detalhe": e.to_string() }),
+			},
+		}),
+	)
+}
+
+/// Rotas do painel: a página (`/`, com a senha) e a API de controle (com o token). Lê o banco
+/// de forma síncrona; as conexões a chamam com `spawn_blocking`.
+pub fn painel_responder(
+	contexto: &ContextoPainel,
+	metodo: &str,
+	alvo: &str,
+	autorizacao: Option<&str>,
+	origem: OrigemAcao,
+) -> RespostaPainel {
+	let (caminho, consulta) = alvo.split_once('?').unwrap_or((alvo, ""));
+	let consulta: Vec<(String, String)> = url::form_urlencoded::parse(consulta.as_bytes())
+		.into_owned()
+		.collect();
+	let segmentos: Vec<String> = caminho
+		.trim_matches('/')
+		.split('/')
+		.map(decodificar_url)
+		.collect();
+	let segmentos: Vec<&str> = segmentos.iter().map(String::as_str).collect();
+
+	if matches!(segmentos.as_slice(), [""] | ["index.html"]) {
+		if contexto
+			.config
+			.senha
+			.as_ref()
+			.is_some_and(|s| !painel_autorizado(autorizacao, s))
+		{
+			return (401, "text/plain", "Senha necessária.".to_string());
+		}
+		if metodo != "GET" {
+			return (405, "text/plain", "Método não suportado.".to_string());
+		}
+		// Uma cópia do estado, para não prender o agente enquanto o arquivo é percorrido
+		let estado = contexto.estado.lock().unwrap().clone();
+		return match painel_pagina(
+			&contexto.db,
+			&estado,
+			contexto.config.itens,
+			contexto.cifra.as_ref(),
+		) {
+			Ok(pagina) => (200, "text/html", pagina),
+			Err(e) => {
+				error!("Falha ao montar a página: {}", e);
+				(500, "text/plain", "Falha ao montar a página.".to_string())
+			}
+		};
+	}
+
+	let Some(token) = &contexto.config.token else {
+		return resposta_erro(404, "API desativada: defina [painel].token.");
+	};
+	if !autorizacao
+		.and_then(|a| a.strip_prefix("Bearer "))
+		.is_some_and(|t| segredo_confere(t, token))
+	{
+		return resposta_erro(401, "Token inválido.");
+	}
+	let resultado = match (metodo, segmentos.as_slice()) {
+		("GET", ["items"]) => match api_itens(&contexto.db, &consulta, contexto.cifra.as_ref()) {
+			Ok(itens) => Ok(resposta_json(200, itens)),
+			Err(e) => return resposta_erro(400, &e.to_string()),
+		},
+		("GET", ["sources"]) => {
+			let estado = contexto.estado.lock().unwrap().clone();
+			api_fontes(&contexto.db, &estado).map(|f| resposta_json(200, f))
+		}
+		("POST", ["cycle", "run"]) => {
+			contexto.cutucada.notify_one();
+			info!("Novo ciclo pedido pela API.");
+			Ok(resposta_json(202, serde_json::json!({ "ciclo": "pedido" })))
+		}
+		("POST", ["sources", nome, acao @ ("pause" | "resume")]) => {
+			let pausar = *acao == "pause";
+			if !contexto
+				.estado
+				.lock()
+				.unwrap()
+				.configuradas
+				.iter()
+				.any(|f| f == nome)
+			{
+				return resposta_erro(404, &format!("Fonte não configurada: {}", nome));
+			}
+			db_pausar_fonte(&contexto.db, nome, pausar)
+				.map_err(Into::into)
+				.and_then(|mudou| {
+					if mudou {
+						let acao = if pausar {
+							AcaoModeracao::PausarFonte
+						} else {
+							AcaoModeracao::RetomarFonte
+						};
+						db_auditar(&contexto.db, acao, origem, nome, None)?;
+					}
+					Ok(resposta_json(
+						200,
+						serde_json::json!({ "fonte": nome, "pausada": pausar }),
+					))
+				})
+		}
+		("POST", ["items", "reclassify"]) => {
+			let parametro = |nome: &str| {
+				consulta
+					.iter()
+					.find(|(n, _)| n == nome)
+					.map(|(_, v)| v.as_str())
+			};
+			let (Some(chave), Some(classe @ ("relevante" | "irrelevante"))) =
+				(parametro("key"), parametro("class"))
+			else {
+				return resposta_erro(400, "Informe key e class (relevante ou irrelevante).");
+			};
+			db_reclassificar(&contexto.db, chave, classe == "relevante")
+				.map_err(Into::into)
+				.and_then(|()| {
+					db_auditar(
+						&contexto.db,
+						AcaoModeracao::Reclassificar,
+						origem,
+						chave,
+						Some(classe.to_string()),
+					)?;
+					Ok(resposta_json(
+						200,
+						serde_json::json!({ "chave": chave, "classe": classe }),
+					))
+				})
+		}
+		(
+			_,
+			["items"]
+			| ["items", "reclassify"]
+			| ["sources"]
+			| ["cycle", "run"]
+			| ["sources", _, "pause" | "resume"],
+		) => {
+			return resposta_erro(405, "Método não suportado.");
+		}
+		_ => return resposta_erro(404, "Rota não encontrada."),
+	};
+	resultado.unwrap_or_else(|e: Box<dyn Error>| {
+		error!("Falha ao atender a API: {}", e);
+		resposta_erro(500, &e.to_string())
+	})
+}
+
+pub async fn painel_conexao(stream: TcpStream, contexto: Arc<ContextoPainel>) -> io::Result<()> {
+	let mut conexao = AsyncBufReader::new(stream);
+	// As rotas não leem corpo; um corpo curto é aceito e descartado
+	let (status, tipo, resposta) = match ler_requisicao(&mut conexao, 64 * 1024).await {
+		Err(status) => (status, "text/plain", motivo_http(status).to_string()),
+		Ok(requisicao) if requisicao.metodo == "GET" && requisicao.caminho == "/healthz" => {
+			painel_saude(&contexto).await
+		}
+		Ok(requisicao) => {
+			let contexto = Arc::clone(&contexto);
+			let origem = match requisicao.cliente.as_deref() {
+				Some("tui") => OrigemAcao::Tui,
+				_ => OrigemAcao::Api,
+			};
+			tokio::task::spawn_blocking(move || {
+				painel_responder(
+					&contexto,
+					&requisicao.metodo,
+					&requisicao.caminho,
+					requisicao.autorizacao.as_deref(),
+					origem,
+				)
+			})
+			.await
+			.map_err(io::Error::other)?
+		}
+	};
+	let motivo = match status {
+		202 => "Accepted",
+		405 => "Method Not Allowed",
+		503 => "Service Unavailable",
+		_ => motivo_http(status),
+	};
+	let mut cabecalhos = format!(
+		"HTTP/1.1 {} {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n",
+		status,
+		motivo,
+		tipo,
+		resposta.len()
+	);
+	if status == 401 && tipo == "text/plain" {
+		cabecalhos.push_str("WWW-Authenticate: Basic realm=\"FilterFlow\", charset=\"UTF-8\"\r\n");
+	}
+	cabecalhos.push_str("\r\n");
+	let stream = conexao.get_mut();
+	stream.write_all(cabecalhos.as_bytes()).await?;
+	stream.write_all(resposta.as_bytes()).await?;
+	stream.shutdown().await
+}
+
+/// Atende o painel em `[painel].endereco` enquanto o agente roda.
+pub async fn painel_atender(listener: TcpListener, contexto: ContextoPainel) {
+	let contexto = Arc::new(contexto);
+	loop {
+		match listener.accept().await {
+			Ok((stream, _)) => {
+				let contexto = Arc::clone(&contexto);
+				tokio::spawn(async move {
+					if let Err(e) = painel_conexao(stream, contexto).await {
+						error!("Falha ao atender a conexão: {}", e);
+					}
+				});
+			}
+			Err(e) => error!("Falha ao aceitar conexão: {}", e),
+		}
+	}
+}
@@ -0,0 +1,675 @@
+//! Etapas do processamento de cada item, montadas a partir de `[pipeline].etapas`.
+//!
+//! A fase de filtragem (deduplicação, pré-filtro, filtro do LLM e enriquecimento) roda no
+//! rodízio entre as fontes e decide se o item entra na fila de resumo. A fase de entrega
+//! (resumo, persistência e emissão) roda sobre essa fila, já priorizada. Dentro de cada fase as
+//! etapas seguem a ordem da configuração; quem embute a biblioteca pode montar um [`Pipeline`]
+//! com etapas próprias.
+//!
+//! As etapas `script_filtro` e `script_entrega` chamam um programa externo por item (em
+//! qualquer linguagem), com o item em JSON no stdin. A resposta no stdout, também em JSON, pode
+//! rejeitar o item (`{"decisao": "rejeitar"}`) e trocar o `titulo`, a `descricao` ou o `resumo`;
+//! sem resposta, o item segue como está.
+
+use chrono::Utc;
+use reqwest::Client;
+use serde::Deserialize;
+use sled::Db;
+use std::{
+	error::Error,
+	pin::Pin,
+	process::Stdio,
+	sync::{Arc, RwLock},
+	time::Duration as StdDuration,
+};
+use tracing::{debug, error};
+
+use super::{
+	FilaResumo, ItemNoticia, ItemPendente, ResultadoFiltro, contar_termos, db_titulo_semelhante,
+	pontuar_importancia,
+};
+use crate::{BOLD_YELLOW, RESET, config::*, emitir_evento, llm::*, saidas::*, store::*};
+
+/// O que as etapas compartilham durante o ciclo.
+pub struct ContextoEtapa<'a> {
+	pub client: &'a Client,
+	pub db: &'a Arc<Db>,
+	pub config: &'a ConfigVigente,
+	pub cifra: Option<&'a CifraArquivo>,
+	pub fila: &'a FilaResumo,
+	// `--dry-run`: o banco é uma cópia descartável e nada é entregue às saídas
+	pub simulacao: bool,
+	// `filterflow reprocess`: os itens vêm do cache de irrelevância, que a deduplicação então
+	// não consulta; o registro só sai do cache depois de gravado o novo veredito
+	pub reavaliacao: bool,
+}
+
+impl ContextoEtapa<'_> {
+	pub fn filtro(&self) -> Arc<FiltroConfig> {
+		Arc::clone(&self.config.0.read().unwrap().filtro)
+	}
+
+	pub fn geral(&self) -> Arc<GeralConfig> {
+		Arc::clone(&self.config.0.read().unwrap().geral)
+	}
+
+	pub fn resumo(&self) -> Arc<ResumoConfig> {
+		Arc::clone(&self.config.0.read().unwrap().resumo)
+	}
+
+	pub fn saida(&self) -> Arc<SaidaConfig> {
+		Arc::clone(&self.config.0.read().unwrap().saida)
+	}
+}
+
+/// Partes da configuração que as etapas leem a cada item. Quando o arquivo muda, a vigia da
+/// configuração troca o conjunto inteiro, e o item seguinte já usa os novos prompts e termos.
+pub struct ConfigVigente(RwLock<ConfigEtapas>);
+
+struct ConfigEtapas {
+	filtro: Arc<FiltroConfig>,
+	geral: Arc<GeralConfig>,
+	resumo: Arc<ResumoConfig>,
+	saida: Arc<SaidaConfig>,
+}
+
+impl ConfigEtapas {
+	fn de(config: &Config) -> Self {
+		ConfigEtapas {
+			filtro: Arc::new(config.filtro.clone()),
+			geral: Arc::new(config.geral.clone()),
+			resumo: Arc::new(config.resumo.clone()),
+			saida: Arc::new(config.saida.clone()),
+		}
+	}
+}
+
+impl ConfigVigente {
+	pub fn novo(config: &Config) -> Self {
+		ConfigVigente(RwLock::new(ConfigEtapas::de(config)))
+	}
+
+	pub fn trocar(&self, config: &Config) {
+		*self.0.write().unwrap() = ConfigEtapas::de(config);
+	}
+}
+
+pub type Avaliacao<'a> =
+	Pin<Box<dyn Future<Output = Result<Option<ResultadoFiltro>, Box<dyn Error>>> + 'a>>;
+
+pub type Execucao<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+/// Etapa da fase de filtragem. `Some` encerra a avaliação do item com esse resultado; se todas
+/// as etapas devolverem `None`, o item entra na fila de resumo.
+pub trait EtapaFiltro {
+	fn avaliar<'a>(
+		&'a self,
+		contexto: &'a ContextoEtapa<'a>,
+		item: &'a mut ItemPendente,
+	) -> Avaliacao<'a>;
+}
+
+/// Etapa da fase de entrega. Cada etapa registra as próprias falhas, que não impedem as
+/// seguintes.
+pub trait EtapaEntrega {
+	fn executar<'a>(
+		&'a self,
+		contexto: &'a ContextoEtapa<'a>,
+		item: &'a mut ItemEmEntrega,
+	) -> Execucao<'a>;
+}
+
+/// Item relevante durante a fase de entrega.
+pub struct ItemEmEntrega {
+	pub pendente: ItemPendente,
+	// Número do item no ciclo (`filterflow open <n>`)
+	pub numero: Option<u32>,
+	pub resumo: Option<String>,
+	pub tokens_resumo: Option<u32>,
+	// Resumo dispensado pela importância (ou sem a etapa de resumo), e não por falha do LLM
+	pub resumo_omitido: bool,
+	// Rejeitado por uma etapa (o script de entrega): as seguintes não rodam
+	pub descartado: bool,
+	registro: Option<RegistroArquivo>,
+}
+
+impl ItemEmEntrega {
+	/// Registro do item como foi entregue. Montado na primeira etapa que o usa, com o resumo
+	/// obtido até então.
+	pub fn registro(&mut self, geral: &GeralConfig) -> &RegistroArquivo {
+		let (noticia, resumo) = (&self.pendente.noticia, &self.resumo);
+		self.registro.get_or_insert_with(|| RegistroArquivo {
+			titulo: noticia.titulo.clone(),
+			link: noticia.link.clone(),
+			descricao: noticia.descricao.clone(),
+			fonte: MetadadosFonte::de(noticia),
+			publicado: noticia.publicado,
+			entregue_em: Utc::now(),
+			modelo_resumo: resumo.as_ref().map(|_| geral.modelo_resumo.clone()),
+			versao_prompt: resumo.as_ref().map(|_| versao_prompt_resumo(geral)),
+			resumo: resumo.clone(),
+			cifrado: None,
+		})
+	}
+}
+
+/// Etapas ativas de cada fase, na ordem em que rodam.
+pub struct Pipeline {
+	pub filtragem: Vec<Box<dyn EtapaFiltro>>,
+	pub entrega: Vec<Box<dyn EtapaEntrega>>,
+}
+
+impl Pipeline {
+	pub fn da_config(config: &PipelineConfig) -> Self {
+		let mut pipeline = Pipeline {
+			filtragem: Vec::new(),
+			entrega: Vec::new(),
+		};
+		for etapa in &config.etapas {
+			match etapa {
+				Etapa::Deduplicacao => pipeline.filtragem.push(Box::new(Deduplicar)),
+				Etapa::PreFiltro => pipeline.filtragem.push(Box::new(PreFiltrar)),
+				Etapa::FiltroLlm => pipeline.filtragem.push(Box::new(FiltrarComLlm)),
+				Etapa::Enriquecimento => pipeline.filtragem.push(Box::new(Enriquecer)),
+				Etapa::Resumo => pipeline.entrega.push(Box::new(Resumir)),
+				Etapa::Persistencia => pipeline.entrega.push(Box::new(Persistir)),
+				Etapa::Emissao => pipeline.entrega.push(Box::new(Emitir)),
+				Etapa::ScriptFiltro => pipeline.filtragem.push(Box::new(ScriptFiltro(
+					Script::novo(&config.script_filtro, config.tempo_limite_script_segundos),
+				))),
+				Etapa::ScriptEntrega => pipeline.entrega.push(Box::new(ScriptEntrega(
+					Script::novo(&config.script_entrega, config.tempo_limite_script_segundos),
+				))),
+			}
+		}
+		pipeline
+	}
+
+	/// Fase de filtragem de um item. Os aprovados por todas as etapas entram na fila de resumo.
+	pub async fn avaliar(
+		&self,
+		contexto: &ContextoEtapa<'_>,
+		noticia: &ItemNoticia,
+		prioridade: i32,
+	) -> Result<ResultadoFiltro, Box<dyn Error>> {
+		let mut item = ItemPendente {
+			noticia: noticia.clone(),
+			prioridade,
+			urgencia: 0,
+			importancia: 0,
+			tokens_filtro: None,
+		};
+		for etapa in &self.filtragem {
+			if let Some(resultado) = etapa.avaliar(contexto, &mut item).await? {
+				return Ok(resultado);
+			}
+		}
+		contexto.fila.lock().unwrap().push(item);
+		Ok(ResultadoFiltro::Relevante)
+	}
+
+	/// Fase de entrega de um item da fila de resumo.
+	pub async fn entregar(&self, contexto: &ContextoEtapa<'_>, pendente: ItemPendente) {
+		// A simulação não numera os itens, para não sobrescrever as referências do último ciclo
+		let numero = if contexto.simulacao {
+			None
+		} else {
+			match referencias_adicionar(&pendente.noticia) {
+				Ok(n) => Some(n),
+				Err(e) => {
+					error!("Falha ao registrar referência numerada: {}", e);
+					None
+				}
+			}
+		};
+		let mut item = ItemEmEntrega {
+			pendente,
+			numero,
+			resumo: None,
+			tokens_resumo: None,
+			resumo_omitido: true,
+			descartado: false,
+			registro: None,
+		};
+		for etapa in &self.entrega {
+			etapa.executar(contexto, &mut item).await;
+			if item.descartado {
+				break;
+			}
+		}
+		if let Err(e) = db_desenfileirar(contexto.db, &item.pendente.noticia) {
+			error!("Falha ao tirar o item da fila de itens: {}", e);
+		}
+	}
+}
+
+/// Descarta o que já está em cache, já foi processado, tem título semelhante a um item
+/// processado ou já está na fila deste ciclo (o mesmo link em mais de uma fonte).
+pub struct Deduplicar;
+
+impl EtapaFiltro for Deduplicar {
+	fn avaliar<'a>(
+		&'a self,
+		contexto: &'a ContextoEtapa<'a>,
+		item: &'a mut ItemPendente,
+	) -> Avaliacao<'a> {
+		Box::pin(async move {
+			let (db, noticia) = (contexto.db, &item.noticia);
+			let chave = noticia.chave.as_str();
+
+			match db_is_irrelevant(db, chave) {
+				Ok(true) if !contexto.reavaliacao => return Ok(Some(ResultadoFiltro::JaVisto)),
+				Ok(true) => {}
+				Err(e) => {
+					error!("Falha ao verificar o cache de irrelevância: {}", e);
+					return Err(e.into());
+				}
+				Ok(false) => {}
+			}
+
+			if db.contains_key(chave.as_bytes())? || db_titulo_semelhante(db, chave)? {
+				return Ok(Some(ResultadoFiltro::JaVisto));
+			}
+
+			if contexto
+				.fila
+				.lock()
+				.unwrap()
+				.iter()
+				.any(|p| p.noticia.link == noticia.link || p.noticia.chave == chave)
+			{
+				return Ok(Some(ResultadoFiltro::JaVisto));
+			}
+			Ok(None)
+		})
+	}
+}
+
+/// Descarta, sem consultar o LLM, os itens que citam algum dos `indicadores_irrelevancia`.
+pub struct PreFiltrar;
+
+impl EtapaFiltro for PreFiltrar {
+	fn avaliar<'a>(
+		&'a self,
+		contexto: &'a ContextoEtapa<'a>,
+		item: &'a mut ItemPendente,
+	) -> Avaliacao<'a> {
+		Box::pin(async move {
+			if contar_termos(&item.noticia, &contexto.filtro().indicadores_irrelevancia) > 0 {
+				return Ok(Some(ResultadoFiltro::Descartado));
+			}
+			Ok(None)
+		})
+	}
+}
+
+/// Filtragem semântica pelo LLM. Os irrelevantes vão para o cache de irrelevância.
+pub struct FiltrarComLlm;
+
+impl EtapaFiltro for FiltrarComLlm {
+	fn avaliar<'a>(
+		&'a self,
+		contexto: &'a ContextoEtapa<'a>,
+		item: &'a mut ItemPendente,
+	) -> Avaliacao<'a> {
+		Box::pin(async move {
+			let noticia = &item.noticia;
+			let geral = contexto.geral();
+			let (relevante, tokens) = match call_llm_filter(
+				contexto.client,
+				&noticia.titulo,
+				&noticia.descricao,
+				contexto.filtro(),
+				Arc::clone(&geral),
+			)
+			.await
+			{
+				Ok(resultado) => resultado,
+				Err(e) => {
+					error!(
+						"Falha na filtragem da notícia: {}. Verifique se o LLM está rodando em {}",
+						e, geral.endereco
+					);
+					return Ok(Some(ResultadoFiltro::FalhaLlm));
+				}
+			};
+
+			debug!(link = %noticia.link, relevante, "Veredito do filtro: '{}'", noticia.titulo);
+			if !relevante {
+				if let Err(e) = db_cache_noticia_irrelevante(contexto.db, noticia, contexto.cifra) {
+					error!("Falha ao salvar no cache de irrelevância: {}", e);
+				}
+				return Ok(Some(ResultadoFiltro::Irrelevante { tokens }));
+			}
+			item.tokens_filtro = tokens;
+			Ok(None)
+		})
+	}
+}
+
+/// Pontua a urgência (ordem da fila de resumo) e a importância (se o item é resumido).
+pub struct Enriquecer;
+
+impl EtapaFiltro for Enriquecer {
+	fn avaliar<'a>(
+		&'a self,
+		contexto: &'a ContextoEtapa<'a>,
+		item: &'a mut ItemPendente,
+	) -> Avaliacao<'a> {
+		Box::pin(async move {
+			let filtro = contexto.filtro();
+			item.urgencia = contar_termos(&item.noticia, &filtro.termos_urgencia);
+			item.importancia = pontuar_importancia(&item.noticia, item.urgencia, &filtro);
+			Ok(None)
+		})
+	}
+}
+
+/// Resume os itens com a importância mínima da fonte; os demais seguem só com título e link.
+pub struct Resumir;
+
+impl EtapaEntrega for Resumir {
+	fn executar<'a>(
+		&'a self,
+		contexto: &'a ContextoEtapa<'a>,
+		item: &'a mut ItemEmEntrega,
+	) -> Execucao<'a> {
+		Box::pin(async move {
+			let noticia = &item.pendente.noticia;
+			if item.pendente.importancia < contexto.resumo().importancia_minima_para(&noticia.fonte)
+			{
+				return;
+			}
+			item.resumo_omitido = false;
+			match call_llm_summarize(
+				contexto.client,
+				&noticia.titulo,
+				&noticia.descricao,
+				contexto.geral(),
+			)
+			.await
+			{
+				Ok((resumo, tokens)) => {
+					if let Some(n) = item.numero
+						&& let Err(e) = referencias_definir_resumo(n, &resumo)
+					{
+						error!("Falha ao registrar o resumo do item [{}]: {}", n, e);
+					}
+					item.resumo = Some(resumo);
+					item.tokens_resumo = tokens;
+				}
+				Err(e) => error!("Falha ao resumir notícia: {}", e),
+			}
+		})
+	}
+}
+
+/// Marca o item como processado e o guarda no arquivo de itens relevantes.
+pub struct Persistir;
+
+impl EtapaEntrega for Persistir {
+	fn executar<'a>(
+		&'a self,
+		contexto: &'a ContextoEtapa<'a>,
+		item: &'a mut ItemEmEntrega,
+	) -> Execucao<'a> {
+		Box::pin(async move {
+			let chave = item.pendente.noticia.chave.clone();
+			if let Err(e) = db_marcar_processado(contexto.db, &item.pendente.noticia) {
+				error!("Falha ao salvar na Árvore Principal: {}", e);
+			}
+			let registro = item.registro(&contexto.geral());
+			if let Err(e) = db_arquivar(contexto.db, &chave, registro, contexto.cifra) {
+				error!("Falha ao arquivar item relevante: {}", e);
+			}
+		})
+	}
+}
+
+/// Emite o evento `item_relevant` e entrega o item às saídas ativas (em simulação, só lista as
+/// saídas que o receberiam).
+pub struct Emitir;
+
+impl EtapaEntrega for Emitir {
+	fn executar<'a>(
+		&'a self,
+		contexto: &'a ContextoEtapa<'a>,
+		item: &'a mut ItemEmEntrega,
+	) -> Execucao<'a> {
+		Box::pin(async move {
+			// Monta o registro, se nenhuma etapa anterior o fez
+			item.registro(&contexto.geral());
+			let Some(registro) = &item.registro else {
+				return;
+			};
+			let pendente = &item.pendente;
+
+			let mut evento = registro_json(registro);
+			evento["chave"] = pendente.noticia.chave.clone().into();
+			evento["prioridade"] = pendente.prioridade.into();
+			evento["tokens_filtro"] = pendente.tokens_filtro.into();
+			evento["tokens_resumo"] = item.tokens_resumo.into();
+			emitir_evento("item_relevant", evento);
+
+			let entregue = ItemEntregue {
+				chave: &pendente.noticia.chave,
+				registro,
+				numero: item.numero,
+				prioridade: pendente.prioridade,
+				importancia: pendente.importancia,
+				resumo_omitido: item.resumo_omitido,
+				tokens_filtro: pendente.tokens_filtro,
+				tokens_resumo: item.tokens_resumo,
+			};
+			if contexto.simulacao {
+				let saidas: Vec<&str> = contexto
+					.saida()
+					.ativas()
+					.into_iter()
+					.filter(|(_, rota)| rota.aceita(&entregue))
+					.map(|(saida, _)| saida.nome())
+					.collect();
+				informar!(
+					"{}",
+					msg!(
+						"item.simulacao",
+						BOLD_YELLOW,
+						registro.titulo,
+						RESET,
+						if saidas.is_empty() {
+							msg!("item.nenhuma_saida")
+						} else {
+							saidas.join(", ")
+						}
+					)
+				);
+				if let Some(resumo) = &registro.resumo {
+					informar!("{}", resumo);
+				}
+				return;
+			}
+			let config_saida = contexto.saida();
+			let saida = ContextoSaida {
+				client: contexto.client,
+				db: contexto.db,
+				layout: &config_saida.layout,
+				cifra: contexto.cifra,
+			};
+			despachar(&config_saida, &saida, &entregue).await;
+		})
+	}
+}
+
+/// Programa externo de uma etapa de script.
+pub struct Script {
+	pub comando: Vec<String>,
+	pub tempo_limite: StdDuration,
+}
+
+/// Decisão de um script sobre o item.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DecisaoScript {
+	#[default]
+	Seguir,
+	Rejeitar,
+}
+
+/// Resposta de um script: a decisão e os campos que ele troca.
+#[derive(Debug, Deserialize, Default)]
+pub struct RespostaScript {
+	#[serde(default)]
+	pub decisao: DecisaoScript,
+	pub titulo: Option<String>,
+	pub descricao: Option<String>,
+	pub resumo: Option<String>,
+}
+
+impl Script {
+	pub fn novo(comando: &[String], tempo_limite_segundos: u64) -> Self {
+		Script {
+			comando: comando.to_vec(),
+			tempo_limite: StdDuration::from_secs(tempo_limite_segundos),
+		}
+	}
+
+	/// Roda o programa com o documento no stdin. Um stdout vazio é `seguir`; o stderr vai ao
+	/// log em `debug`, para as mensagens de depuração do próprio script.
+	pub async fn executar(
+		&self,
+		documento: &serde_json::Value,
+	) -> Result<RespostaScript, Box<dyn Error>> {
+		let saida = executar_programa(
+			&self.comando,
+			&[],
+			documento.to_string().as_bytes(),
+			self.tempo_limite,
+			Stdio::piped(),
+			"script",
+		)
+		.await?;
+
+		let erros = String::from_utf8_lossy(&saida.stderr);
+		if !erros.trim().is_empty() {
+			debug!(script = %self.comando[0], "{}", erros.trim_end());
+		}
+		if !saida.status.success() {
+			return Err(format!("o script terminou com {}", saida.status).into());
+		}
+		let texto = String::from_utf8_lossy(&saida.stdout);
+		if texto.trim().is_empty() {
+			return Ok(RespostaScript::default());
+		}
+		serde_json::from_str(texto.trim())
+			.map_err(|e| format!("resposta do script não é um JSON válido: {}", e).into())
+	}
+}
+
+/// Documento JSON do item enviado aos scripts.
+pub fn documento_script(pendente: &ItemPendente) -> serde_json::Value {
+	let noticia = &pendente.noticia;
+	serde_json::json!({
+		"chave": noticia.chave,
+		"titulo": noticia.titulo,
+		"link": noticia.link,
+		"descricao": noticia.descricao,
+		"fonte": noticia.fonte,
+		"grupo": noticia.grupo,
+		"publicado": noticia.publicado,
+		"prioridade": pendente.prioridade,
+		"urgencia": pendente.urgencia,
+		"importancia": pendente.importancia,
+	})
+}
+
+/// `script_filtro`: o script pode rejeitar o item (contado como descartado, sem chamada ao
+/// LLM) ou trocar o título e a descrição vistos pelas etapas seguintes. Se o script falha, o
+/// item segue.
+pub struct ScriptFiltro(pub Script);
+
+impl EtapaFiltro for ScriptFiltro {
+	fn avaliar<'a>(
+		&'a self,
+		_: &'a ContextoEtapa<'a>,
+		item: &'a mut ItemPendente,
+	) -> Avaliacao<'a> {
+		Box::pin(async move {
+			let resposta = match self.0.executar(&documento_script(item)).await {
+				Ok(resposta) => resposta,
+				Err(e) => {
+					error!(link = %item.noticia.link, "Falha no script de filtro: {}", e);
+					return Ok(None);
+				}
+			};
+			if let Some(titulo) = resposta.titulo {
+				item.noticia.titulo = titulo;
+			}
+			if let Some(descricao) = resposta.descricao {
+				item.noticia.descricao = descricao;
+			}
+			if resposta.decisao == DecisaoScript::Rejeitar {
+				debug!(link = %item.noticia.link, "Rejeitado pelo script de filtro");
+				return Ok(Some(ResultadoFiltro::Descartado));
+			}
+			Ok(None)
+		})
+	}
+}
+
+/// `script_entrega`: recebe também o `resumo` e pode trocá-lo (junto com o título e a
+/// descrição) ou rejeitar o item, que então não é persistido nem entregue e vai para o cache
+/// de irrelevância. Deve vir antes de `persistencia` e `emissao`.
+pub struct ScriptEntrega(pub Script);
+
+impl EtapaEntrega for ScriptEntrega {
+	fn executar<'a>(
+		&'a self,
+		contexto: &'a ContextoEtapa<'a>,
+		item: &'a mut ItemEmEntrega,
+	) -> Execucao<'a> {
+		Box::pin(async move {
+			let mut documento = documento_script(&item.pendente);
+			documento["resumo"] = item.resumo.clone().into();
+			let resposta = match self.0.executar(&documento).await {
+				Ok(resposta) => resposta,
+				Err(e) => {
+					error!(link = %item.pendente.noticia.link, "Falha no script de entrega: {}", e);
+					return;
+				}
+			};
+			let noticia = &mut item.pendente.noticia;
+			if resposta.titulo.is_some()
+				|| resposta.descricao.is_some()
+				|| resposta.resumo.is_some()
+			{
+				// O registro é remontado com os campos novos na próxima etapa que o usar
+				item.registro = None;
+			}
+			if let Some(titulo) = resposta.titulo {
+				noticia.titulo = titulo;
+			}
+			if let Some(descricao) = resposta.descricao {
+				noticia.descricao = descricao;
+			}
+			if let Some(resumo) = resposta.resumo {
+				item.resumo = Some(resumo);
+			}
+			if resposta.decisao == DecisaoScript::Rejeitar {
+				item.descartado = true;
+				if let Err(e) = db_cache_noticia_irrelevante(contexto.db, noticia, contexto.cifra) {
+					error!("Falha ao salvar no cache de irrelevância: {}", e);
+				}
+				emitir_evento(
+					"item_skipped",
+					serde_json::json!({
+						"chave": noticia.chave,
+						"link": noticia.link,
+						"titulo": noticia.titulo,
+						"fonte": noticia.fonte,
+						"motivo": "script",
+					}),
+				);
+			}
+		})
+	}
+}
@@ -0,0 +1,895 @@
+//! Ciclo de processamento: coleta das fontes, deduplicação, filtragem e resumo dos itens.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Utc};
+use futures_util::{StreamExt, stream};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Client;
+use rss::Item;
+use serde::{Deserialize, Serialize};
+use sled::Db;
+use std::{
+	collections::{BTreeSet, HashMap, HashSet},
+	error::Error,
+	sync::{Arc, Mutex},
+	time::Duration as StdDuration,
+	time::Instant,
+};
+use tracing::{Instrument, debug, error, info, info_span, warn};
+use url::Url;
+
+use crate::{
+	BOLD, BOLD_GREEN, RESET, config::*, emitir_evento, idioma, sources::*, store::*, systemd,
+};
+use etapas::{ContextoEtapa, Pipeline};
+
+pub mod etapas;
+
+pub static FIM_REGEX_LAZY: Lazy<Regex> =
+	Lazy::new(|| Regex::new(r"(?s)Fim<\/th>.*?<td>(.*?)<\/td>").unwrap());
+
+/// Item extraído de uma fonte (RSS, Sitemap, GitHub) pronto para a filtragem.
+#[derive(Debug, Clone)]
+pub struct ItemNoticia {
+	// Nome da fonte configurada (feed, sitemap, repositório)
+	pub fonte: String,
+	pub link: String,
+	pub titulo: String,
+	pub descricao: String,
+	// Data de publicação/modificação informada pela fonte, quando existir
+	pub publicado: Option<DateTime<Utc>>,
+	pub guid: Option<String>,
+	// Chave de deduplicação no banco (ver `Deduplicacao`)
+	pub chave: String,
+	// Grupo da fonte em [grupos], se houver
+	pub grupo: Option<String>,
+}
+
+/// Item aprovado pelo filtro, aguardando a fase de resumo.
+#[derive(Debug)]
+pub struct ItemPendente {
+	pub noticia: ItemNoticia,
+	pub prioridade: i32,
+	pub urgencia: u32,
+	pub importancia: u32,
+	// Tokens gastos na filtragem, se o servidor informar
+	pub tokens_filtro: Option<u32>,
+}
+
+/// Item rejeitado pelo filtro no ciclo (para a amostra de QA e o espelho em SQLite).
+#[derive(Debug)]
+pub struct ItemRejeitado {
+	pub noticia: ItemNoticia,
+	pub tokens_filtro: Option<u32>,
+}
+
+/// Resultado da filtragem de um item.
+#[derive(Debug, PartialEq)]
+pub enum ResultadoFiltro {
+	// Já processado/em cache: nenhuma chamada ao LLM foi feita
+	JaVisto,
+	Relevante,
+	Irrelevante { tokens: Option<u32> },
+	// A chamada ao LLM falhou; o item não entra no cache e é reavaliado no próximo ciclo
+	FalhaLlm,
+	// Rejeitado pelo pré-filtro, sem chamada ao LLM
+	Descartado,
+}
+
+/// Contadores de uma fonte: os do ciclo, somados em `ESTATISTICAS_TREE` ao fim de cada um.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct EstatisticasFonte {
+	pub coletados: u64,
+	// Já vistos (cache de irrelevância, processados ou repetidos no ciclo)
+	pub em_cache: u64,
+	// Rejeitados pelo pré-filtro ou pelo LLM
+	pub descartados: u64,
+	pub relevantes: u64,
+	// Falhas de coleta e de chamada ao LLM
+	pub erros: u64,
+	pub chamadas_llm: u64,
+	pub latencia_llm_ms: u64,
+}
+
+impl EstatisticasFonte {
+	pub fn somar(&mut self, outra: &EstatisticasFonte) {
+		self.coletados += outra.coletados;
+		self.em_cache += outra.em_cache;
+		self.descartados += outra.descartados;
+		self.relevantes += outra.relevantes;
+		self.erros += outra.erros;
+		self.chamadas_llm += outra.chamadas_llm;
+		self.latencia_llm_ms += outra.latencia_llm_ms;
+	}
+
+	pub fn latencia_media_ms(&self) -> u64 {
+		self.latencia_llm_ms
+			.checked_div(self.chamadas_llm)
+			.unwrap_or(0)
+	}
+}
+
+/// Tabela de contadores por fonte, para o fim do ciclo e o `filterflow stats`.
+pub fn tabela_estatisticas(linhas: &[(String, EstatisticasFonte)]) -> String {
+	let largura = linhas
+		.iter()
+		.map(|(nome, _)| nome.chars().count())
+		.max()
+		.unwrap_or(0)
+		.max(5);
+	let mut tabela = format!(
+		"  {:<largura$} {:>9} {:>7} {:>11} {:>10} {:>6} {:>10} {:>12}\n",
+		idioma::texto("tabela.fonte"),
+		idioma::texto("tabela.coletados"),
+		idioma::texto("tabela.cache"),
+		idioma::texto("tabela.descartados"),
+		idioma::texto("tabela.relevantes"),
+		idioma::texto("tabela.erros"),
+		idioma::texto("tabela.chamadas"),
+		idioma::texto("tabela.latencia")
+	);
+	for (nome, e) in linhas {
+		tabela.push_str(&format!(
+			"  {:<largura$} {:>9} {:>7} {:>11} {:>10} {:>6} {:>10} {:>9} ms\n",
+			nome,
+			e.coletados,
+			e.em_cache,
+			e.descartados,
+			e.relevantes,
+			e.erros,
+			e.chamadas_llm,
+			e.latencia_media_ms()
+		));
+	}
+	tabela
+}
+
+/// Itens coletados de uma fonte no ciclo, consumidos em rodízio na fase de filtragem.
+pub struct LoteFonte {
+	pub nome: String,
+	pub itens: std::collections::VecDeque<ItemNoticia>,
+	pub cota: Option<u32>,
+	pub prioridade: i32,
+	// A coleta falhou: o lote fica vazio, só para a contagem
+	pub falha_coleta: bool,
+	pub estatisticas: EstatisticasFonte,
+}
+
+impl LoteFonte {
+	pub fn new(fonte: &impl Fonte, itens: Vec<ItemNoticia>, cota_padrao: Option<u32>) -> Self {
+		Self {
+			nome: fonte.nome().to_string(),
+			estatisticas: EstatisticasFonte {
+				coletados: itens.len() as u64,
+				..Default::default()
+			},
+			itens: itens.into(),
+			cota: fonte.opcoes().max_chamadas_llm.or(cota_padrao),
+			prioridade: fonte.opcoes().prioridade,
+			falha_coleta: false,
+		}
+	}
+
+	pub fn cota_esgotada(&self) -> bool {
+		self.cota
+			.is_some_and(|c| self.estatisticas.chamadas_llm >= u64::from(c))
+	}
+}
+
+/// Fila de resumo do ciclo, preenchida pela fase de filtragem de todas as fontes.
+pub type FilaResumo = Mutex<Vec<ItemPendente>>;
+
+/// Verifica se o item de alerta do INMET expirou, usando o campo 'Fim' da tabela na descrição.
+#[allow(unused)]
+pub fn is_inmet_alert_expired(item: &Item) -> bool {
+	let title = item.title().unwrap_or("[Sem Título]");
+	let description = item.description().unwrap_or("");
+
+	// ----------------------------------------------------
+	// 1. Tentar extrair a data de FIM da DESCRIÇÃO
+	// ----------------------------------------------------
+	if let Some(caps) = FIM_REGEX_LAZY.captures(description)
+		&& let Some(date_time_match) = caps.get(1)
+	{
+		let date_str_raw = date_time_match.as_str(); // Ex: "2025-10-28 10:00:00.0"
+
+		let date_str_iso_prep = date_str_raw.trim().replace(' ', "T");
+		let final_date_str = date_str_iso_prep.trim_end_matches(".0").to_string();
+
+		match DateTime::parse_from_rfc3339(&format!("{}Z", final_date_str)) {
+			Ok(expiration_dt) => {
+				let now = Utc::now();
+				let is_expired = expiration_dt.with_timezone(&Utc) < now;
+				return is_expired;
+			}
+			Err(e) => {
+				warn!(
+					"Falha ao analisar data '{}' da Descrição. Erro: {}",
+					final_date_str, e
+				);
+				// Continua para o fallback pubDate se o parse falhar
+			}
+		}
+	}
+
+	// ----------------------------------------------------
+	// 2. FALLBACK: Tentar data de publicação (<pubDate>)
+	// ----------------------------------------------------
+	// Log de fallback MANTIDO para diagnosticar falha na FIM_REGEX_LAZY.
+	debug!(
+		"Aviso '{}' sem 'Fim' na Descrição ou Erro de Parse. Usando <pubDate> como fallback.",
+		title
+	);
+
+	if let Some(pub_date_str) = item.pub_date() {
+		// pubDate usa o formato RFC2822 (Ex: Sun, 26 Oct 2025 07:00:00 -0300)
+		match DateTime::parse_from_rfc2822(pub_date_str) {
+			Ok(pub_dt) => {
+				let now = Utc::now();
+				// Assumir um alerta não pode ter mais de 72 horas (3 dias)
+				let max_valid_duration = Duration::hours(72);
+
+				let is_too_old = (now - pub_dt.with_timezone(&Utc)) > max_valid_duration;
+
+				return is_too_old;
+			}
+			Err(_) => {
+				warn!(
+					"Falha ao analisar <pubDate> '{}' para '{}'. Tratado como VÁLIDO.",
+					pub_date_str, title
+				);
+			}
+		}
+	}
+
+	// 3. Se tudo falhar ou estiver dentro do prazo de 72h, tratar como VÁLIDO
+	false
+}
+
+// =================================================================
+// FUNÇÕES DE PROCESSAMENTO CENTRAL
+// =================================================================
+
+/// Conta quantos dos termos aparecem no título/descrição do item (case-insensitive).
+pub fn contar_termos(noticia: &ItemNoticia, termos: &[String]) -> u32 {
+	let texto = format!("{} {}", noticia.titulo, noticia.descricao).to_lowercase();
+	termos
+		.iter()
+		.filter(|t| texto.contains(&t.to_lowercase()))
+		.count() as u32
+}
+
+/// Importância de um item: sua urgência somada aos indicadores de relevância citados no texto.
+pub fn pontuar_importancia(noticia: &ItemNoticia, urgencia: u32, filtro: &FiltroConfig) -> u32 {
+	urgencia + contar_termos(noticia, &filtro.indicadores_relevancia)
+}
+
+/// Fase 1: filtra os itens de todas as fontes em rodízio, uma chamada ao LLM por fonte a cada
+/// volta, respeitando a cota de cada uma. Assim um sitemap gigante não monopoliza o LLM e os
+/// feeds menores são atendidos logo. Itens já em cache não consomem cota. As fontes de maior
+/// prioridade formam uma fila rápida: sua faixa é esgotada antes de passar à seguinte. Com
+/// `max_itens_llm_por_ciclo`, o ciclo para de chamar o LLM ao gastar o orçamento e o restante
+/// fica na fila de itens para o próximo ciclo.
+pub async fn filtrar_round_robin(
+	contexto: &ContextoEtapa<'_>,
+	pipeline: &Pipeline,
+	lotes: &mut [LoteFonte],
+) -> Vec<ItemRejeitado> {
+	let mut rejeitados = Vec::new();
+	let orcamento = contexto.geral().max_itens_llm_por_ciclo;
+	let mut restante = orcamento.map(u64::from);
+	lotes.sort_by_key(|l| std::cmp::Reverse(l.prioridade));
+	for faixa in lotes.chunk_by_mut(|a, b| a.prioridade == b.prioridade) {
+		rejeitados.extend(filtrar_faixa(contexto, pipeline, faixa, &mut restante).await);
+	}
+
+	if let Some(orcamento) = orcamento
+		&& restante == Some(0)
+	{
+		let sobra: usize = lotes.iter().map(|l| l.itens.len()).sum();
+		if sobra > 0 {
+			info!(
+				"Orçamento de {} chamadas ao LLM do ciclo esgotado: {} itens ficam para o próximo ciclo.",
+				orcamento, sobra
+			);
+		}
+	}
+
+	for lote in lotes.iter() {
+		if lote.estatisticas.relevantes > 0 {
+			informar!(
+				"{}",
+				msg!(
+					"fonte.relevantes",
+					BOLD_GREEN,
+					lote.estatisticas.relevantes,
+					lote.nome,
+					RESET
+				)
+			);
+		}
+		if lote.cota_esgotada() && !lote.itens.is_empty() {
+			info!(
+				"Cota de {} chamadas ao LLM esgotada para '{}': {} itens ficam para o próximo ciclo.",
+				lote.estatisticas.chamadas_llm,
+				lote.nome,
+				lote.itens.len()
+			);
+		}
+	}
+
+	rejeitados
+}
+
+/// Rodízio entre as fontes de uma mesma faixa de prioridade, descontando cada chamada ao LLM
+/// do orçamento `restante` do ciclo, se houver. Devolve os itens rejeitados pelo LLM, para a
+/// amostragem de QA.
+pub async fn filtrar_faixa(
+	contexto: &ContextoEtapa<'_>,
+	pipeline: &Pipeline,
+	lotes: &mut [LoteFonte],
+	restante: &mut Option<u64>,
+) -> Vec<ItemRejeitado> {
+	let mut rejeitados = Vec::new();
+	loop {
+		let mut progresso = false;
+
+		for lote in lotes.iter_mut() {
+			if *restante == Some(0) {
+				return rejeitados;
+			}
+			if lote.cota_esgotada() {
+				continue;
+			}
+
+			// Avança até a próxima chamada efetiva ao LLM desta fonte
+			while let Some(noticia) = lote.itens.pop_front() {
+				let inicio = Instant::now();
+				let resultado = pipeline.avaliar(contexto, &noticia, lote.prioridade).await;
+				// Decidido o item, ele sai da fila persistente; os relevantes saem na entrega
+				if matches!(
+					resultado,
+					Ok(ResultadoFiltro::JaVisto
+						| ResultadoFiltro::Descartado
+						| ResultadoFiltro::Irrelevante { .. })
+				) && let Err(e) = db_desenfileirar(contexto.db, &noticia)
+				{
+					error!(
+						"Falha ao tirar '{}' da fila de itens: {}",
+						noticia.titulo, e
+					);
+				}
+				let estatisticas = &mut lote.estatisticas;
+				match resultado {
+					Ok(ResultadoFiltro::JaVisto) => {
+						estatisticas.em_cache += 1;
+						continue;
+					}
+					Ok(ResultadoFiltro::Descartado) => {
+						estatisticas.descartados += 1;
+						emitir_evento(
+							"item_skipped",
+							serde_json::json!({
+								"chave": noticia.chave,
+								"link": noticia.link,
+								"titulo": noticia.titulo,
+								"fonte": noticia.fonte,
+								"motivo": "pre_filtro",
+							}),
+						);
+						continue;
+					}
+					Ok(resultado) => {
+						estatisticas.chamadas_llm += 1;
+						if let Some(restante) = restante {
+							*restante = restante.saturating_sub(1);
+						}
+						estatisticas.latencia_llm_ms += inicio.elapsed().as_millis() as u64;
+						match resultado {
+							ResultadoFiltro::Relevante => estatisticas.relevantes += 1,
+							ResultadoFiltro::FalhaLlm => estatisticas.erros += 1,
+							ResultadoFiltro::Irrelevante { tokens } => {
+								estatisticas.descartados += 1;
+								emitir_evento(
+									"item_skipped",
+									serde_json::json!({
+										"chave": noticia.chave,
+										"link": noticia.link,
+										"titulo": noticia.titulo,
+										"fonte": noticia.fonte,
+										"motivo": "irrelevante",
+									}),
+								);
+								rejeitados.push(ItemRejeitado {
+									noticia,
+									tokens_filtro: tokens,
+								})
+							}
+							_ => {}
+						}
+						progresso = true;
+						break;
+					}
+					Err(e) => {
+						estatisticas.erros += 1;
+						error!("Falha na lógica central para '{}': {}", noticia.titulo, e);
+						continue;
+					}
+				}
+			}
+		}
+
+		if !progresso {
+			break;
+		}
+	}
+
+	rejeitados
+}
+
+/// Fase 2: resume e entrega os itens relevantes do ciclo. Com o modelo de resumo lento, a fila
+/// é atendida pela prioridade da fonte, por urgência e, em seguida, pelos mais recentes, em vez
+/// da ordem de chegada.
+pub async fn processar_fila_resumo(contexto: &ContextoEtapa<'_>, pipeline: &Pipeline) {
+	let mut pendentes = std::mem::take(&mut *contexto.fila.lock().unwrap());
+	pendentes.sort_by(|a, b| {
+		b.prioridade
+			.cmp(&a.prioridade)
+			.then(b.urgencia.cmp(&a.urgencia))
+			.then(b.noticia.publicado.cmp(&a.noticia.publicado))
+	});
+
+	for pendente in pendentes {
+		pipeline.entregar(contexto, pendente).await;
+	}
+}
+
+// =================================================================
+// DEDUPLICAÇÃO
+// =================================================================
+
+pub const TITULO_SIMILARIDADE_MINIMA: f64 = 0.8;
+pub const PREFIXO_TITULO: &str = "titulo:";
+
+/// Parâmetros de consulta que só servem para rastreamento e não identificam o conteúdo.
+pub fn eh_parametro_rastreio(nome: &str) -> bool {
+	nome.starts_with("utm_") || matches!(nome, "fbclid" | "gclid" | "mc_cid" | "mc_eid" | "ref")
+}
+
+pub fn url_canonica(link: &str) -> String {
+	let Ok(mut url) = Url::parse(link) else {
+		return link.to_string();
+	};
+	url.set_fragment(None);
+
+	let mut pares: Vec<(String, String)> = url
+		.query_pairs()
+		.filter(|(nome, _)| !eh_parametro_rastreio(nome))
+		.map(|(nome, valor)| (nome.into_owned(), valor.into_owned()))
+		.collect();
+	pares.sort();
+	if pares.is_empty() {
+		url.set_query(None);
+	} else {
+		url.query_pairs_mut().clear().extend_pairs(&pares);
+	}
+
+	if let Some(host) = url
+		.host_str()
+		.and_then(|h| h.strip_prefix("www."))
+		.map(str::to_string)
+	{
+		let _ = url.set_host(Some(&host));
+	}
+	let caminho = url.path().trim_end_matches('/').to_string();
+	if !caminho.is_empty() {
+		url.set_path(&caminho);
+	}
+	url.to_string()
+}
+
+/// Palavras do texto em minúsculas, sem pontuação, para comparações tolerantes.
+pub fn palavras_normalizadas(texto: &str) -> Vec<String> {
+	texto
+		.to_lowercase()
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|p| !p.is_empty())
+		.map(str::to_string)
+		.collect()
+}
+
+/// FNV-1a de 64 bits: estável entre versões, ao contrário do `DefaultHasher`.
+pub fn hash_fnv1a(texto: &str) -> u64 {
+	texto.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+		(hash ^ byte as u64).wrapping_mul(0x100000001b3)
+	})
+}
+
+pub fn chave_deduplicacao(noticia: &ItemNoticia, estrategia: Deduplicacao) -> String {
+	match estrategia {
+		Deduplicacao::Link => noticia.link.clone(),
+		Deduplicacao::Guid => match &noticia.guid {
+			Some(guid) if !guid.is_empty() => format!("guid:{}:{}", noticia.fonte, guid),
+			_ => noticia.link.clone(),
+		},
+		Deduplicacao::UrlCanonica => url_canonica(&noticia.link),
+		Deduplicacao::HashConteudo => {
+			let conteudo =
+				palavras_normalizadas(&format!("{} {}", noticia.titulo, noticia.descricao));
+			format!("hash:{:016x}", hash_fnv1a(&conteudo.join(" ")))
+		}
+		Deduplicacao::TituloFuzzy => {
+			let palavras: BTreeSet<String> =
+				palavras_normalizadas(&noticia.titulo).into_iter().collect();
+			let palavras: Vec<String> = palavras.into_iter().collect();
+			format!(
+				"{}{}\u{1f}{}",
+				PREFIXO_TITULO,
+				noticia.fonte,
+				palavras.join(" ")
+			)
+		}
+	}
+}
+
+/// Similaridade de Jaccard entre dois conjuntos de palavras.
+pub fn similaridade_palavras(a: &BTreeSet<&str>, b: &BTreeSet<&str>) -> f64 {
+	let uniao = a.union(b).count();
+	if uniao == 0 {
+		return 1.0;
+	}
+	a.intersection(b).count() as f64 / uniao as f64
+}
+
+/// Para chaves `por_titulo_fuzzy`, procura na mesma fonte um título já visto (relevante ou
+/// irrelevante) com palavras suficientemente parecidas.
+pub fn db_titulo_semelhante(db: &Db, chave: &str) -> Result<bool, Box<dyn Error>> {
+	let Some((prefixo, palavras)) = chave.split_once('\u{1f}') else {
+		return Ok(false);
+	};
+	if !prefixo.starts_with(PREFIXO_TITULO) {
+		return Ok(false);
+	}
+	let prefixo = format!("{}\u{1f}", prefixo);
+	let atual: BTreeSet<&str> = palavras.split(' ').collect();
+
+	let irrelevantes = db.open_tree(IRRELEVANT_CACHE_TREE)?;
+	for entrada in db
+		.scan_prefix(&prefixo)
+		.chain(irrelevantes.scan_prefix(&prefixo))
+	{
+		let (chave_vista, _) = entrada?;
+		let chave_vista = String::from_utf8_lossy(&chave_vista);
+		let vistas: BTreeSet<&str> = chave_vista[prefixo.len()..].split(' ').collect();
+		if similaridade_palavras(&atual, &vistas) >= TITULO_SIMILARIDADE_MINIMA {
+			return Ok(true);
+		}
+	}
+	Ok(false)
+}
+
+// =================================================================
+// COLETA DAS FONTES
+// =================================================================
+
+/// Agenda a coleta de cada fonte segundo o seu intervalo, o do seu grupo ou o global.
+#[derive(Default)]
+pub struct Agendador {
+	pub ultima_coleta: HashMap<String, Instant>,
+	// Quando cada fonte com cron apareceu sem coleta anterior: ela espera o próximo horário
+	// do cron a partir daí, em vez de ser coletada no ato
+	pub vista_em: HashMap<String, DateTime<Local>>,
+	// Na próxima seleção, coleta todas as fontes já, inclusive as de cron nunca coletadas
+	// (`once` e pedidos de recarga)
+	pub coletar_ja: bool,
+	pub padrao_minutos: u64,
+	// [geral].cron, que substitui o intervalo padrão
+	pub cron_padrao: Option<ExpressaoCron>,
+	pub grupos: HashMap<String, GrupoConfig>,
+}
+
+impl Agendador {
+	/// Agendador que coleta todas as fontes na próxima seleção.
+	pub fn imediato() -> Agendador {
+		Agendador {
+			coletar_ja: true,
+			..Default::default()
+		}
+	}
+
+	/// Atualiza o intervalo padrão, o cron global e os grupos a cada recarga da configuração.
+	pub fn atualizar(
+		&mut self,
+		padrao_minutos: u64,
+		cron_padrao: Option<&ExpressaoCron>,
+		grupos: &HashMap<String, GrupoConfig>,
+	) {
+		self.padrao_minutos = padrao_minutos;
+		self.cron_padrao = cron_padrao.cloned();
+		self.grupos = grupos.clone();
+	}
+
+	pub fn grupo(&self, fonte: &impl Fonte) -> Option<&GrupoConfig> {
+		fonte
+			.opcoes()
+			.grupo
+			.as_ref()
+			.and_then(|g| self.grupos.get(g))
+	}
+
+	pub fn intervalo(&self, fonte: &impl Fonte) -> StdDuration {
+		let minutos = fonte
+			.opcoes()
+			.intervalo_minutos
+			.or_else(|| self.grupo(fonte).and_then(|g| g.intervalo_minutos))
+			.unwrap_or(self.padrao_minutos);
+		StdDuration::from_secs(minutos * 60)
+	}
+
+	/// Cron da fonte. Como o intervalo, o da fonte prevalece sobre o do grupo, e o do grupo
+	/// sobre o global; um intervalo definido num nível encobre o cron dos níveis abaixo.
+	pub fn cron<'a>(&'a self, fonte: &'a impl Fonte) -> Option<&'a ExpressaoCron> {
+		let opcoes = fonte.opcoes();
+		if opcoes.cron.is_some() || opcoes.intervalo_minutos.is_some() {
+			return opcoes.cron.as_ref();
+		}
+		if let Some(grupo) = self.grupo(fonte)
+			&& (grupo.cron.is_some() || grupo.intervalo_minutos.is_some())
+		{
+			return grupo.cron.as_ref();
+		}
+		self.cron_padrao.as_ref()
+	}
+
+	/// Tempo restante até a fonte vencer (zero se já venceu ou nunca foi coletada). Com
+	/// cron, a fonte vence no primeiro horário dele depois da última coleta ou, se nunca foi
+	/// coletada, depois de vista pela primeira vez.
+	/// Fora da janela de horário ou dos dias do grupo, espera a janela abrir.
+	pub fn espera(&self, fonte: &impl Fonte) -> StdDuration {
+		let agora = Local::now();
+		let restante = match (self.ultima_coleta.get(fonte.nome()), self.cron(fonte)) {
+			(Some(ultima), Some(cron)) => {
+				let coletada = agora - Duration::from_std(ultima.elapsed()).unwrap_or_default();
+				cron.proxima(coletada)
+					.map(|proxima| (proxima - agora).to_std().unwrap_or_default())
+					.unwrap_or_else(|| espera_proximo_dia(agora))
+			}
+			(Some(ultima), None) => self.intervalo(fonte).saturating_sub(ultima.elapsed()),
+			(None, Some(cron)) if !self.coletar_ja => {
+				let vista = self.vista_em.get(fonte.nome()).copied().unwrap_or(agora);
+				cron.proxima(vista)
+					.map(|proxima| (proxima - agora).to_std().unwrap_or_default())
+					.unwrap_or_else(|| espera_proximo_dia(agora))
+			}
+			(None, _) => StdDuration::ZERO,
+		};
+		let grupo = self.grupo(fonte);
+		if let Some(grupo) = grupo
+			&& !grupo.dias.is_empty()
+			&& !grupo.dias.iter().any(|d| d.weekday() == agora.weekday())
+		{
+			return restante.max(espera_proximo_dia(agora));
+		}
+		let janela = fonte
+			.opcoes()
+			.janela
+			.or_else(|| grupo.and_then(|g| g.janela));
+		match janela {
+			Some(janela) => restante.max(janela.espera_abertura(agora)),
+			None => restante,
+		}
+	}
+
+	pub fn vencida(&self, fonte: &impl Fonte) -> bool {
+		self.espera(fonte).is_zero()
+	}
+
+	pub fn registrar(&mut self, fonte: &impl Fonte, quando: Instant) {
+		self.ultima_coleta.insert(fonte.nome().to_string(), quando);
+	}
+
+	/// Seleciona as fontes vencidas, registrando-as como coletadas em `quando`.
+	pub fn selecionar<'a>(
+		&mut self,
+		fontes: Vec<FonteConfigurada<'a>>,
+		quando: Instant,
+	) -> Vec<FonteConfigurada<'a>> {
+		let agora = Local::now();
+		for fonte in &fontes {
+			if !self.ultima_coleta.contains_key(fonte.nome()) && self.cron(fonte).is_some() {
+				self.vista_em
+					.entry(fonte.nome().to_string())
+					.or_insert(agora);
+			}
+		}
+		let vencidas: Vec<FonteConfigurada> =
+			fontes.into_iter().filter(|f| self.vencida(f)).collect();
+		for fonte in &vencidas {
+			self.registrar(fonte, quando);
+		}
+		self.coletar_ja = false;
+		vencidas
+	}
+}
+
+/// Tempo até a meia-noite local.
+pub fn espera_proximo_dia(agora: DateTime<Local>) -> StdDuration {
+	let Some(amanha) = agora.date_naive().succ_opt() else {
+		return StdDuration::ZERO;
+	};
+	(amanha.and_time(NaiveTime::MIN) - agora.naive_local())
+		.to_std()
+		.unwrap_or_default()
+}
+
+/// Aplica a política `primeira_execucao` ao lote recém-coletado de uma fonte.
+pub fn aplicar_primeira_execucao(db: &Db, lote: &mut LoteFonte, modo: PrimeiraExecucao) {
+	let vista_em = match db_fonte_vista_em(db, &lote.nome) {
+		Ok(v) => v,
+		Err(e) => {
+			error!("Falha ao consultar fontes conhecidas: {}", e);
+			return;
+		}
+	};
+
+	match (vista_em, modo) {
+		// Modo "ignorar" continua valendo nos ciclos seguintes para itens antigos
+		(Some(desde), PrimeiraExecucao::Ignorar) => {
+			lote.itens
+				.retain(|n| n.publicado.is_none_or(|publicado| publicado >= desde));
+		}
+		(Some(_), _) => {}
+		(None, modo) => {
+			let agora = Utc::now();
+			match modo {
+				PrimeiraExecucao::Processar => {}
+				PrimeiraExecucao::Ignorar => {
+					info!(
+						"Primeira coleta de '{}': {} itens existentes ignorados.",
+						lote.nome,
+						lote.itens.len()
+					);
+					// Gravados como processados: os itens sem data não passariam pelo filtro de
+					// `desde` e voltariam no ciclo seguinte
+					for noticia in lote.itens.drain(..) {
+						if let Err(e) = db_marcar_processado(db, &noticia) {
+							error!("Falha ao marcar item ignorado como visto: {}", e);
+						}
+					}
+				}
+				PrimeiraExecucao::MarcarComoVisto => {
+					for noticia in lote.itens.drain(..) {
+						if let Err(e) = db_marcar_processado(db, &noticia) {
+							error!("Falha ao marcar item como visto: {}", e);
+						}
+					}
+					info!(
+						"Primeira coleta de '{}': itens existentes marcados como vistos.",
+						lote.nome
+					);
+				}
+			}
+			if let Err(e) = db_registrar_fonte(db, &lote.nome, agora) {
+				error!("Falha ao registrar fonte conhecida: {}", e);
+			}
+		}
+	}
+}
+
+/// Grava o lote recém-coletado na fila persistente de itens e acrescenta a ele os que ficaram
+/// pendentes de ciclos anteriores (LLM fora do ar, cota esgotada, agente interrompido), mesmo
+/// que a fonte não os traga mais. Assim a fila é drenada com a coleta, e não perdida. Devolve
+/// se o lote foi gravado na fila.
+pub fn retomar_fila(db: &Db, lote: &mut LoteFonte, cifra: Option<&CifraArquivo>) -> bool {
+	let itens: Vec<ItemNoticia> = lote.itens.iter().cloned().collect();
+	if let Err(e) = db_enfileirar(db, &itens, cifra) {
+		error!("Falha ao gravar a fila de itens de '{}': {}", lote.nome, e);
+		return false;
+	}
+	let pendentes = match db_fila_da_fonte(db, &lote.nome, cifra) {
+		Ok(pendentes) => pendentes,
+		Err(e) => {
+			error!("Falha ao ler a fila de itens de '{}': {}", lote.nome, e);
+			return true;
+		}
+	};
+	let coletadas: HashSet<&str> = itens.iter().map(|n| n.chave.as_str()).collect();
+	let retomados: Vec<ItemNoticia> = pendentes
+		.into_iter()
+		.filter(|n| !coletadas.contains(n.chave.as_str()))
+		.collect();
+	if !retomados.is_empty() {
+		info!(
+			"'{}': {} itens pendentes retomados da fila.",
+			lote.nome,
+			retomados.len()
+		);
+		lote.itens.extend(retomados);
+	}
+	true
+}
+
+/// Baixa e extrai os itens de todas as fontes configuradas, um lote por fonte.
+pub async fn coletar_fontes(
+	client: &Client,
+	clientes_fonte: &HashMap<String, Client>,
+	db: &Arc<sled::Db>,
+	fontes: &[FonteConfigurada<'_>],
+	geral_config: Arc<GeralConfig>,
+	cifra: Option<&CifraArquivo>,
+	simulacao: bool,
+) -> Vec<LoteFonte> {
+	let cota_padrao = geral_config.max_chamadas_llm_por_fonte;
+	let contexto = ContextoColeta {
+		client,
+		clientes_fonte,
+		db,
+		simulacao,
+		repeticao: Repeticao::de(&geral_config),
+		arquivos_lidos: Default::default(),
+	};
+	let mut lotes = Vec::new();
+
+	// As fontes de maior prioridade começam primeiro; até `fontes_simultaneas` são baixadas ao
+	// mesmo tempo, e cada uma é anunciada ao terminar
+	let mut fontes = fontes.to_vec();
+	fontes.sort_by_key(|f| std::cmp::Reverse(f.opcoes().prioridade));
+	let coletas = fontes.iter().enumerate().map(|(ordem, fonte)| {
+		let span = info_span!("fonte", nome = fonte.nome());
+		let contexto = &contexto;
+		async move {
+			let resultado = fonte.coletar(contexto).instrument(span.clone()).await;
+			(ordem, resultado, span)
+		}
+	});
+	let mut em_curso =
+		stream::iter(coletas).buffer_unordered(geral_config.fontes_simultaneas.max(1));
+
+	let mut concluidas = Vec::new();
+	while let Some((ordem, resultado, span)) = em_curso.next().await {
+		systemd::sinal_de_vida();
+		let fonte = &fontes[ordem];
+		let mut itens = match resultado {
+			Ok(itens) => {
+				informar!(
+					"{}{}",
+					msg!("fonte.processando", BOLD, fonte.nome(), RESET),
+					fonte.resumo_coleta(itens.len())
+				);
+				span.in_scope(|| debug!(itens = itens.len(), "Coleta concluída"));
+				itens
+			}
+			Err(e) => {
+				error!(
+					"Falha ao coletar {} '{}': {}",
+					fonte.tipo(),
+					fonte.nome(),
+					e
+				);
+				let mut lote = LoteFonte::new(fonte, Vec::new(), cota_padrao);
+				lote.falha_coleta = true;
+				lote.estatisticas.erros = 1;
+				retomar_fila(db, &mut lote, cifra);
+				concluidas.push((ordem, lote));
+				continue;
+			}
+		};
+
+		let estrategia = fonte.opcoes().deduplicacao;
+		for item in &mut itens {
+			item.chave = chave_deduplicacao(item, estrategia);
+			item.grupo = fonte.opcoes().grupo.clone();
+		}
+		let mut lote = LoteFonte::new(fonte, itens, cota_padrao);
+		aplicar_primeira_execucao(db, &mut lote, fonte.opcoes().primeira_execucao);
+		if retomar_fila(db, &mut lote, cifra) {
+			fonte.coleta_gravada(&contexto);
+		}
+		concluidas.push((ordem, lote));
+	}
+
+	// Os lotes seguem a ordem das fontes, e não a de chegada
+	concluidas.sort_by_key(|(ordem, _)| *ordem);
+	lotes.extend(concluidas.into_iter().map(|(_, lote)| lote));
+	lotes
+}
@@ -0,0 +1,591 @@
+//! Destino dos eventos do `tracing`: filtro por nível e módulo (`RUST_LOG`) e escrita pelo
+//! mesmo caminho do log legível (`escrever_log`), com o modo daemon e o journal.
+//!
+//! Os resumos coloridos do console (blocos de ciclo, tabelas) continuam em `informar!`; os
+//! erros, avisos e detalhes de depuração são eventos, com os spans ativos (fonte, chamada ao
+//! LLM) no início da linha: `[ERRO pipeline] fonte{nome=G1}: Falha ao ...`.
+//!
+//! Com `[log].arquivo`, tudo o que vai ao terminal é copiado, sem cores e com data e hora em
+//! cada linha, para um arquivo com rotação por tamanho.
+//!
+//! Com `formato_log = "json"`, o log legível sai de cena e cada evento vira um objeto JSON por
+//! linha (data, nível, módulo, spans e campos), junto com as decisões sobre cada item, para
+//! coletores como Loki e ELK.
+
+use chrono::{Local, Utc};
+use std::{
+	cell::RefCell,
+	collections::HashMap,
+	fmt::{self, Write as _},
+	fs::{self, File, OpenOptions},
+	io::{self, Write as _},
+	path::PathBuf,
+	sync::{
+		Mutex,
+		atomic::{AtomicBool, AtomicU64, Ordering},
+	},
+};
+use tracing::{
+	Event, Level, Metadata, Subscriber,
+	field::{Field, Visit},
+	level_filters::LevelFilter,
+	span::{Attributes, Id, Record},
+};
+
+use crate::{
+	BOLD_RED, BOLD_YELLOW, RESET,
+	config::{FormatoLog, LogConfig},
+	escrever_com_prioridade, escrever_log, sem_cores,
+};
+
+// `formato_log = "json"`: um objeto JSON por linha no lugar do log legível
+pub static LOG_JSON: AtomicBool = AtomicBool::new(false);
+
+/// Níveis por alvo, no formato do `RUST_LOG`: `warn,filterflow::llm=debug`. Uma diretiva sem
+/// `=` é o nível padrão ou, se não for um nível, um alvo com todos os níveis; vale a diretiva
+/// de alvo mais longo que for prefixo do módulo do evento.
+#[derive(Debug, Clone)]
+pub struct FiltroLog {
+	pub padrao: LevelFilter,
+	pub diretivas: Vec<(String, LevelFilter)>,
+}
+
+impl FiltroLog {
+	pub fn interpretar(texto: &str) -> Self {
+		let mut filtro = FiltroLog {
+			padrao: LevelFilter::INFO,
+			diretivas: Vec::new(),
+		};
+		for diretiva in texto.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+			match diretiva.split_once('=') {
+				Some((alvo, nivel)) => match nivel.trim().parse() {
+					Ok(nivel) => filtro.diretivas.push((alvo.trim().to_string(), nivel)),
+					Err(_) => eprintln!("RUST_LOG: nível inválido em '{}', ignorado.", diretiva),
+				},
+				None => match diretiva.parse() {
+					Ok(nivel) => filtro.padrao = nivel,
+					Err(_) => filtro
+						.diretivas
+						.push((diretiva.to_string(), LevelFilter::TRACE)),
+				},
+			}
+		}
+		// O alvo mais longo primeiro, para a primeira correspondência ser a mais específica
+		filtro
+			.diretivas
+			.sort_by_key(|(alvo, _)| std::cmp::Reverse(alvo.len()));
+		filtro
+	}
+
+	pub fn nivel_para(&self, alvo: &str) -> LevelFilter {
+		self.diretivas
+			.iter()
+			.find(|(prefixo, _)| {
+				alvo == prefixo
+					|| alvo
+						.strip_prefix(prefixo.as_str())
+						.is_some_and(|resto| resto.starts_with("::"))
+			})
+			.map(|(_, nivel)| *nivel)
+			.unwrap_or(self.padrao)
+	}
+
+	pub fn nivel_maximo(&self) -> LevelFilter {
+		self.diretivas
+			.iter()
+			.map(|(_, nivel)| *nivel)
+			.fold(self.padrao, LevelFilter::max)
+	}
+}
+
+/// Um campo de evento ou span: o nome, o valor como aparece na linha de texto e o mesmo valor
+/// no JSON (números e booleanos mantêm o tipo).
+struct Campo {
+	nome: &'static str,
+	texto: String,
+	valor: serde_json::Value,
+}
+
+/// Campos de um evento ou span: `message` à parte e os demais na ordem em que vieram.
+#[derive(Default)]
+struct Campos {
+	mensagem: String,
+	resto: Vec<Campo>,
+	fatal: bool,
+}
+
+impl Campos {
+	fn registrar(&mut self, field: &Field, texto: String, valor: serde_json::Value) {
+		self.resto.push(Campo {
+			nome: field.name(),
+			texto,
+			valor,
+		});
+	}
+}
+
+/// Os campos como `nome=valor`, separados por espaço.
+fn campos_em_texto(campos: &[Campo]) -> String {
+	let mut texto = String::new();
+	for campo in campos {
+		if !texto.is_empty() {
+			texto.push(' ');
+		}
+		let _ = write!(texto, "{}={}", campo.nome, campo.texto);
+	}
+	texto
+}
+
+impl Visit for Campos {
+	fn record_str(&mut self, field: &Field, value: &str) {
+		if field.name() == "message" {
+			self.mensagem.push_str(value);
+		} else {
+			self.registrar(field, value.to_string(), value.into());
+		}
+	}
+
+	fn record_bool(&mut self, field: &Field, value: bool) {
+		if field.name() == "fatal" {
+			self.fatal = value;
+		} else {
+			self.registrar(field, value.to_string(), value.into());
+		}
+	}
+
+	fn record_i64(&mut self, field: &Field, value: i64) {
+		self.registrar(field, value.to_string(), value.into());
+	}
+
+	fn record_u64(&mut self, field: &Field, value: u64) {
+		self.registrar(field, value.to_string(), value.into());
+	}
+
+	fn record_f64(&mut self, field: &Field, value: f64) {
+		self.registrar(field, value.to_string(), value.into());
+	}
+
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		if field.name() == "message" {
+			let _ = write!(self.mensagem, "{:?}", value);
+		} else {
+			let texto = format!("{:?}", value);
+			self.registrar(field, texto.clone(), texto.into());
+		}
+	}
+}
+
+struct DadosSpan {
+	nome: &'static str,
+	campos: Vec<Campo>,
+	referencias: usize,
+}
+
+thread_local! {
+	// Spans em que a thread entrou, do mais externo ao mais interno
+	static PILHA_SPANS: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Assinante global do `tracing`, instalado por [`iniciar`].
+pub struct AssinanteLog {
+	filtro: FiltroLog,
+	spans: Mutex<HashMap<u64, DadosSpan>>,
+	proximo_id: AtomicU64,
+}
+
+impl AssinanteLog {
+	pub fn novo(filtro: FiltroLog) -> Self {
+		AssinanteLog {
+			filtro,
+			spans: Mutex::new(HashMap::new()),
+			proximo_id: AtomicU64::new(1),
+		}
+	}
+
+	/// Linha do evento: nível e módulo (sem o prefixo `filterflow::`), spans ativos, mensagem
+	/// e campos. Os níveis seguem os prefixos já usados no log (`[ERRO`, `[INFO`), que o
+	/// `escrever_log` traduz em prioridades do journal.
+	fn formatar(&self, metadados: &Metadata<'_>, campos: &Campos) -> String {
+		let nivel = match *metadados.level() {
+			Level::ERROR if campos.fatal => "ERRO FATAL",
+			Level::ERROR => "ERRO",
+			Level::WARN => "AVISO",
+			Level::INFO => "INFO",
+			Level::DEBUG => "DEBUG",
+			Level::TRACE => "TRACE",
+		};
+		let alvo = metadados.target();
+		let alvo = alvo.strip_prefix("filterflow::").unwrap_or(alvo);
+		let cor = match *metadados.level() {
+			Level::ERROR => BOLD_RED,
+			Level::WARN => BOLD_YELLOW,
+			_ => "",
+		};
+		let mut linha = format!(
+			"{}[{} {}]{} ",
+			cor,
+			nivel,
+			alvo,
+			if cor.is_empty() { "" } else { RESET }
+		);
+
+		let spans = self.spans.lock().unwrap();
+		PILHA_SPANS.with(|pilha| {
+			for id in pilha.borrow().iter() {
+				if let Some(span) = spans.get(id) {
+					if span.campos.is_empty() {
+						let _ = write!(linha, "{}: ", span.nome);
+					} else {
+						let _ = write!(
+							linha,
+							"{}{{{}}}: ",
+							span.nome,
+							campos_em_texto(&span.campos)
+						);
+					}
+				}
+			}
+		});
+		linha.push_str(&campos.mensagem);
+		if !campos.resto.is_empty() {
+			let _ = write!(linha, " ({})", campos_em_texto(&campos.resto));
+		}
+		linha.push('\n');
+		linha
+	}
+
+	/// O evento como objeto JSON: `em`, `nivel`, `alvo`, os campos dos spans ativos (o campo
+	/// `nome` de um span vira a chave com o nome dele, como `"fonte": "G1"`; os demais ganham o
+	/// prefixo do span, como `llm_modelo`), a `mensagem` e os campos do evento.
+	fn formatar_json(&self, metadados: &Metadata<'_>, campos: Campos) -> serde_json::Value {
+		let mut objeto = serde_json::Map::new();
+		objeto.insert("em".into(), Utc::now().to_rfc3339().into());
+		objeto.insert(
+			"nivel".into(),
+			metadados.level().as_str().to_lowercase().into(),
+		);
+		objeto.insert("alvo".into(), metadados.target().into());
+		if campos.fatal {
+			objeto.insert("fatal".into(), true.into());
+		}
+
+		let spans = self.spans.lock().unwrap();
+		PILHA_SPANS.with(|pilha| {
+			for id in pilha.borrow().iter() {
+				if let Some(span) = spans.get(id) {
+					for campo in &span.campos {
+						let chave = if campo.nome == "nome" {
+							span.nome.to_string()
+						} else {
+							format!("{}_{}", span.nome, campo.nome)
+						};
+						objeto.insert(chave, campo.valor.clone());
+					}
+				}
+			}
+		});
+		objeto.insert("mensagem".into(), campos.mensagem.into());
+		for campo in campos.resto {
+			objeto.insert(campo.nome.into(), campo.valor);
+		}
+		objeto.into()
+	}
+}
+
+/// Prioridade syslog de uma linha JSON no journal, pelo nível do evento.
+fn prioridade_json(nivel: &Level, fatal: bool) -> u8 {
+	match *nivel {
+		Level::ERROR if fatal => 2,
+		Level::ERROR => 3,
+		Level::WARN => 4,
+		Level::INFO => 6,
+		Level::DEBUG | Level::TRACE => 7,
+	}
+}
+
+impl Subscriber for AssinanteLog {
+	fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+		*metadata.level() <= self.filtro.nivel_para(metadata.target())
+	}
+
+	fn max_level_hint(&self) -> Option<LevelFilter> {
+		Some(self.filtro.nivel_maximo())
+	}
+
+	fn new_span(&self, span: &Attributes<'_>) -> Id {
+		let mut campos = Campos::default();
+		span.record(&mut campos);
+		let id = self.proximo_id.fetch_add(1, Ordering::Relaxed);
+		self.spans.lock().unwrap().insert(
+			id,
+			DadosSpan {
+				nome: span.metadata().name(),
+				campos: campos.resto,
+				referencias: 1,
+			},
+		);
+		Id::from_u64(id)
+	}
+
+	fn record(&self, span: &Id, values: &Record<'_>) {
+		if let Some(dados) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+			let mut campos = Campos {
+				resto: std::mem::take(&mut dados.campos),
+				..Default::default()
+			};
+			values.record(&mut campos);
+			dados.campos = campos.resto;
+		}
+	}
+
+	fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+	fn event(&self, event: &Event<'_>) {
+		let mut campos = Campos::default();
+		event.record(&mut campos);
+		// Os eventos vão todos para o stderr: o stdout fica com o log legível ou só com os
+		// eventos de `--output json`
+		if LOG_JSON.load(Ordering::Relaxed) {
+			let metadados = event.metadata();
+			let prioridade = prioridade_json(metadados.level(), campos.fatal);
+			let objeto = self.formatar_json(metadados, campos);
+			escrever_com_prioridade(&format!("{}\n", objeto), true, Some(prioridade));
+		} else {
+			escrever_log(&self.formatar(event.metadata(), &campos), true);
+		}
+	}
+
+	fn enter(&self, span: &Id) {
+		PILHA_SPANS.with(|pilha| pilha.borrow_mut().push(span.into_u64()));
+	}
+
+	fn exit(&self, span: &Id) {
+		PILHA_SPANS.with(|pilha| {
+			let mut pilha = pilha.borrow_mut();
+			if let Some(posicao) = pilha.iter().rposition(|id| *id == span.into_u64()) {
+				pilha.remove(posicao);
+			}
+		});
+	}
+
+	fn clone_span(&self, id: &Id) -> Id {
+		if let Some(dados) = self.spans.lock().unwrap().get_mut(&id.into_u64()) {
+			dados.referencias += 1;
+		}
+		id.clone()
+	}
+
+	fn try_close(&self, id: Id) -> bool {
+		let mut spans = self.spans.lock().unwrap();
+		let Some(dados) = spans.get_mut(&id.into_u64()) else {
+			return false;
+		};
+		dados.referencias -= 1;
+		if dados.referencias > 0 {
+			return false;
+		}
+		spans.remove(&id.into_u64());
+		true
+	}
+}
+
+/// Instala o assinante com o filtro do `RUST_LOG` (padrão: `info`). Chamadas repetidas, ou
+/// com outro assinante já instalado por quem usa a biblioteca, não fazem nada.
+pub fn iniciar() {
+	let filtro = FiltroLog::interpretar(&std::env::var("RUST_LOG").unwrap_or_default());
+	let _ = tracing::subscriber::set_global_default(AssinanteLog::novo(filtro));
+}
+
+/// Aplica `[log].formato_log`. Vale a partir do próximo evento.
+pub fn definir_formato(formato: FormatoLog) {
+	LOG_JSON.store(formato == FormatoLog::Json, Ordering::Relaxed);
+}
+
+/// Linha do log JSON para um evento de `--output json` (ver [`crate::emitir_evento`]), se o
+/// formato JSON estiver ativo e o `RUST_LOG` deixar passar `info` de `filterflow::eventos`. Nos
+/// itens, `fonte` é o nome da fonte e `decisao` diz o que aconteceu: `relevante`,
+/// `pre_filtro`, `irrelevante` ou `script`.
+pub fn registrar_evento(dados: &serde_json::Value) {
+	if !LOG_JSON.load(Ordering::Relaxed)
+		|| !tracing::enabled!(target: "filterflow::eventos", Level::INFO)
+	{
+		return;
+	}
+	let mut objeto = serde_json::Map::new();
+	objeto.insert("em".into(), dados["em"].clone());
+	objeto.insert("nivel".into(), "info".into());
+	objeto.insert("alvo".into(), "filterflow::eventos".into());
+	let mut dados = dados.clone();
+	if let Some(dados) = dados.as_object_mut() {
+		dados.remove("em");
+		if let Some(nome) = dados.get("fonte").and_then(|f| f.get("nome")).cloned() {
+			dados.insert("fonte".into(), nome);
+		}
+		let decisao = match dados.get("evento").and_then(|e| e.as_str()) {
+			Some("item_relevant") => Some("relevante".into()),
+			Some("item_skipped") => dados.remove("motivo"),
+			_ => None,
+		};
+		if let Some(decisao) = decisao {
+			dados.insert("decisao".into(), decisao);
+		}
+		objeto.append(dados);
+	}
+	escrever_com_prioridade(
+		&format!("{}\n", serde_json::Value::from(objeto)),
+		true,
+		Some(6),
+	);
+}
+
+/// Arquivo de `[log]` aberto pelo agente.
+pub struct ArquivoLog {
+	caminho: PathBuf,
+	arquivo: File,
+	tamanho: u64,
+	maximo: u64,
+	rotacoes: u32,
+	// A última escrita parou no meio de uma linha
+	linha_aberta: bool,
+}
+
+static ARQUIVO_LOG: Mutex<Option<ArquivoLog>> = Mutex::new(None);
+
+impl ArquivoLog {
+	pub fn abrir(config: &LogConfig, caminho: &str) -> io::Result<Self> {
+		let caminho = PathBuf::from(caminho);
+		if let Some(pasta) = caminho.parent().filter(|p| !p.as_os_str().is_empty()) {
+			fs::create_dir_all(pasta)?;
+		}
+		let arquivo = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&caminho)?;
+		Ok(ArquivoLog {
+			tamanho: arquivo.metadata()?.len(),
+			caminho,
+			arquivo,
+			maximo: config.tamanho_maximo_mb * 1024 * 1024,
+			rotacoes: config.rotacoes,
+			linha_aberta: false,
+		})
+	}
+
+	fn rotacionado(&self, n: u32) -> PathBuf {
+		let mut nome = self.caminho.clone().into_os_string();
+		nome.push(format!(".{}", n));
+		PathBuf::from(nome)
+	}
+
+	/// Desloca `<arquivo>.N` para `.N+1` (o mais antigo sai), o atual para `.1` e recomeça.
+	fn girar(&mut self) -> io::Result<()> {
+		if self.rotacoes == 0 {
+			fs::remove_file(&self.caminho)?;
+		} else {
+			let _ = fs::remove_file(self.rotacionado(self.rotacoes));
+			for n in (1..self.rotacoes).rev() {
+				let origem = self.rotacionado(n);
+				if origem.exists() {
+					fs::rename(origem, self.rotacionado(n + 1))?;
+				}
+			}
+			fs::rename(&self.caminho, self.rotacionado(1))?;
+		}
+		self.arquivo = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&self.caminho)?;
+		self.tamanho = 0;
+		Ok(())
+	}
+
+	/// Grava o texto linha a linha com a data e a hora (as linhas JSON já trazem `em`); linhas
+	/// em branco ficam de fora. Um erro (stderr) no meio de uma linha do stdout vai para a linha
+	/// seguinte. A rotação só acontece entre linhas completas.
+	pub fn gravar(&mut self, texto: &str, erro: bool) -> io::Result<()> {
+		let texto = sem_cores(texto);
+		let com_data = !LOG_JSON.load(Ordering::Relaxed);
+		let mut saida = String::with_capacity(texto.len() + 32);
+		if erro && self.linha_aberta {
+			saida.push('\n');
+			self.linha_aberta = false;
+		}
+		for linha in texto.split_inclusive('\n') {
+			if self.linha_aberta {
+				self.linha_aberta = false;
+			} else if linha.trim().is_empty() {
+				continue;
+			} else if com_data {
+				saida.push_str(&Local::now().format("%Y-%m-%d %H:%M:%S ").to_string());
+			}
+			saida.push_str(linha);
+		}
+		self.linha_aberta = !texto.is_empty() && !texto.ends_with('\n');
+		if saida.is_empty() {
+			return Ok(());
+		}
+		if self.tamanho > 0 && self.tamanho + saida.len() as u64 > self.maximo && !self.linha_aberta
+		{
+			self.girar()?;
+		}
+		self.arquivo.write_all(saida.as_bytes())?;
+		self.tamanho += saida.len() as u64;
+		Ok(())
+	}
+}
+
+/// Passa a copiar o log para `[log].arquivo`; sem ele, não faz nada. Mudanças valem após
+/// reiniciar o agente.
+pub fn abrir_arquivo_log(config: &LogConfig) -> io::Result<()> {
+	let Some(caminho) = &config.arquivo else {
+		return Ok(());
+	};
+	*ARQUIVO_LOG.lock().unwrap() = Some(ArquivoLog::abrir(config, caminho)?);
+	Ok(())
+}
+
+/// Cópia de uma escrita do log no arquivo, se houver. Se a escrita falha (disco cheio,
+/// permissão), o aviso vai ao stderr e o arquivo é abandonado até o agente reiniciar.
+pub fn gravar_no_arquivo(texto: &str, erro: bool) {
+	let mut arquivo = ARQUIVO_LOG.lock().unwrap();
+	if let Some(log) = arquivo.as_mut()
+		&& let Err(e) = log.gravar(texto, erro)
+	{
+		eprintln!(
+			"[ERRO] Falha ao gravar o log em '{}': {}. O log segue só no terminal.",
+			log.caminho.display(),
+			e
+		);
+		*arquivo = None;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn filtro_log_diretivas_por_alvo() {
+		let filtro = FiltroLog::interpretar("warn, filterflow::llm=debug,filterflow=error");
+		assert_eq!(filtro.padrao, LevelFilter::WARN);
+		assert_eq!(filtro.nivel_para("filterflow::llm"), LevelFilter::DEBUG);
+		assert_eq!(
+			filtro.nivel_para("filterflow::llm::cache"),
+			LevelFilter::DEBUG
+		);
+		assert_eq!(filtro.nivel_para("filterflow::llmx"), LevelFilter::ERROR);
+		assert_eq!(filtro.nivel_para("reqwest"), LevelFilter::WARN);
+		assert_eq!(filtro.nivel_maximo(), LevelFilter::DEBUG);
+	}
+
+	#[test]
+	fn filtro_log_alvo_sem_nivel_e_nivel_invalido() {
+		let filtro = FiltroLog::interpretar("filterflow::pipeline,filterflow::llm=alto");
+		assert_eq!(filtro.padrao, LevelFilter::INFO);
+		assert_eq!(
+			filtro.nivel_para("filterflow::pipeline"),
+			LevelFilter::TRACE
+		);
+		assert_eq!(filtro.nivel_para("filterflow::llm"), LevelFilter::INFO);
+	}
+}
@@ -0,0 +1,2989 @@
+//! Saídas dos itens relevantes e a fila de reenvio das entregas que falharam.
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::{
+	DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc,
+};
+use once_cell::sync::Lazy;
+use rand::seq::IndexedRandom;
+use regex::Regex;
+use reqwest::Client;
+use ring::hmac;
+use rss::{Channel, Guid, Item};
+use serde::{Deserialize, Serialize};
+use sled::Db;
+use std::{
+	collections::{BTreeMap, VecDeque},
+	error::Error,
+	fs, io,
+	io::Write,
+	path::{Path, PathBuf},
+	pin::Pin,
+	process::{Output, Stdio},
+	sync::{Arc, Mutex},
+	time::Duration as StdDuration,
+	time::Instant,
+};
+use tokio::io::{
+	AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+	BufReader as AsyncBufReader,
+};
+use tokio::net::TcpStream;
+use tokio::process::Command;
+use tokio::time;
+use tokio_native_tls::{TlsConnector, native_tls};
+use tracing::{error, info, warn};
+use url::Url;
+
+use crate::{
+	BOLD, BOLD_GREEN, BOLD_RED, BOLD_YELLOW, RESET, config::*, llm::*, pipeline::*, store::*,
+};
+
+// Campos "Início" e "Fim" na descrição já sem HTML (ex.: "Início 2025-10-28 10:00:00.0")
+pub static JANELA_ALERTA_REGEX_LAZY: Lazy<Regex> = Lazy::new(|| {
+	Regex::new(r"(?i)\b(In[íi]cio|Fim)\s*:?\s*(\d{4}-\d{2}-\d{2}[ T]\d{2}:\d{2})").unwrap()
+});
+
+// Instantes das notificações mostradas na janela atual e quantas foram omitidas pelo limite
+pub static NOTIFICACOES_DESKTOP: Lazy<Mutex<(VecDeque<Instant>, usize)>> =
+	Lazy::new(|| Mutex::new((VecDeque::new(), 0)));
+
+// Token OAuth do Wallabag e o instante em que expira, reaproveitado entre os itens
+pub static TOKEN_WALLABAG: Lazy<Mutex<Option<(String, Instant)>>> = Lazy::new(|| Mutex::new(None));
+
+// Token de sessão do Shiori, descartado quando o servidor o recusa
+pub static TOKEN_SHIORI: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+// =================================================================
+// DESPACHO PARA AS SAÍDAS
+// =================================================================
+
+/// Item relevante já resumido e pronto para as saídas.
+pub struct ItemEntregue<'a> {
+	pub chave: &'a str,
+	pub registro: &'a RegistroArquivo,
+	// Número do item no ciclo (ver `filterflow open`)
+	pub numero: Option<u32>,
+	pub prioridade: i32,
+	pub importancia: u32,
+	// O resumo foi dispensado pela importância mínima (e não por falha do LLM)
+	pub resumo_omitido: bool,
+	pub tokens_filtro: Option<u32>,
+	pub tokens_resumo: Option<u32>,
+}
+
+/// O que as saídas compartilham durante a entrega.
+pub struct ContextoSaida<'a> {
+	pub client: &'a Client,
+	pub db: &'a Db,
+	pub layout: &'a str,
+	pub cifra: Option<&'a CifraArquivo>,
+}
+
+pub type EnvioSaida<'a> = Pin<Box<dyn Future<Output = Result<(), Box<dyn Error>>> + 'a>>;
+
+/// Uma saída configurada em `[saida]`. Todas as saídas ativas recebem cada item relevante; a
+/// falha de uma é registrada e não impede as demais.
+pub trait Saida {
+	/// Rótulo das mensagens de erro, ex.: "TELEGRAM".
+	fn nome(&self) -> &'static str;
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a>;
+}
+
+impl SaidaConfig {
+	/// Saídas ativas com suas rotas, na ordem em que recebem os itens.
+	pub fn ativas(&self) -> Vec<(&dyn Saida, &RotaSaida)> {
+		let terminal = Some(&self.terminal).filter(|t| t.ativo);
+		let desktop = self.desktop.as_ref().filter(|d| d.ativo);
+		[
+			terminal.map(|s| (s as &dyn Saida, &s.rota)),
+			self.obsidian.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.digest.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.telegram.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.matrix.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.xmpp.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.slack.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.ntfy.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.gotify.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			desktop.map(|s| (s as &dyn Saida, &s.rota)),
+			self.markdown.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.sqlite.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.calendario.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.jsonl.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.rss.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.webhook.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.comando.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.mqtt.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.wallabag.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.readeck.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.linkding.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.shiori.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+			self.email.as_ref().map(|s| (s as &dyn Saida, &s.rota)),
+		]
+		.into_iter()
+		.flatten()
+		.collect()
+	}
+}
+
+/// Entrega o item a cada saída cuja rota o aceita, em sequência; os erros de uma não
+/// interrompem as demais, e a entrega que falhou entra na fila de novas tentativas.
+pub async fn despachar(
+	config: &SaidaConfig,
+	contexto: &ContextoSaida<'_>,
+	item: &ItemEntregue<'_>,
+) {
+	let agora = Local::now();
+	for (saida, rota) in config.ativas() {
+		if !rota.aceita(item) {
+			continue;
+		}
+		// No horário de silêncio, a entrega vai para a fila, vencendo quando a janela fecha
+		if let Some(silencio) = &config.silencio
+			&& silencio.segura(saida.nome(), item.prioridade, agora)
+		{
+			let fim = Utc::now()
+				+ Duration::from_std(silencio.janela.espera_fechamento(agora)).unwrap_or_default();
+			let pendente = EntregaPendente::adiada(saida.nome(), item, fim);
+			if let Err(e) = entregas_gravar(contexto, None, pendente) {
+				error!("Falha ao guardar a entrega para o fim do silêncio: {}", e);
+			}
+			continue;
+		}
+		if let Err(e) = saida.entregar(contexto, item).await {
+			error!(
+				saida = saida.nome(),
+				"Falha ao entregar item (nova tentativa em {} min): {}",
+				config.espera_tentativa_minutos,
+				e
+			);
+			let pendente = EntregaPendente::de(saida.nome(), item, config, &e.to_string());
+			if let Err(e) = entregas_gravar(contexto, None, pendente) {
+				error!("Falha ao enfileirar a entrega: {}", e);
+			}
+		}
+	}
+}
+
+/// Entrega que falhou numa saída, guardada para novas tentativas nos próximos ciclos.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntregaPendente {
+	// `Saida::nome` da saída que falhou
+	pub saida: String,
+	pub chave: String,
+	// Cifrado como no arquivo, se [arquivo].criptografar
+	pub registro: RegistroArquivo,
+	pub numero: Option<u32>,
+	pub prioridade: i32,
+	pub importancia: u32,
+	pub resumo_omitido: bool,
+	pub tokens_filtro: Option<u32>,
+	pub tokens_resumo: Option<u32>,
+	pub tentativas: u32,
+	pub proxima_em: DateTime<Utc>,
+	pub ultimo_erro: String,
+}
+
+impl EntregaPendente {
+	pub fn de(saida: &str, item: &ItemEntregue, config: &SaidaConfig, erro: &str) -> Self {
+		EntregaPendente {
+			saida: saida.to_string(),
+			chave: item.chave.to_string(),
+			registro: item.registro.clone(),
+			numero: item.numero,
+			prioridade: item.prioridade,
+			importancia: item.importancia,
+			resumo_omitido: item.resumo_omitido,
+			tokens_filtro: item.tokens_filtro,
+			tokens_resumo: item.tokens_resumo,
+			tentativas: 1,
+			proxima_em: Utc::now() + espera_tentativa(config, 1),
+			ultimo_erro: erro.to_string(),
+		}
+	}
+
+	/// Entrega guardada pelo horário de silêncio até `proxima_em`, sem contar como tentativa.
+	pub fn adiada(saida: &str, item: &ItemEntregue, proxima_em: DateTime<Utc>) -> Self {
+		EntregaPendente {
+			saida: saida.to_string(),
+			chave: item.chave.to_string(),
+			registro: item.registro.clone(),
+			numero: item.numero,
+			prioridade: item.prioridade,
+			importancia: item.importancia,
+			resumo_omitido: item.resumo_omitido,
+			tokens_filtro: item.tokens_filtro,
+			tokens_resumo: item.tokens_resumo,
+			tentativas: 0,
+			proxima_em,
+			ultimo_erro: String::new(),
+		}
+	}
+}
+
+/// Espera antes da próxima tentativa: dobra a cada falha, até 24 h.
+pub fn espera_tentativa(config: &SaidaConfig, tentativas: u32) -> Duration {
+	let minutos = config
+		.espera_tentativa_minutos
+		.max(1)
+		.saturating_mul(1 << tentativas.saturating_sub(1).min(16));
+	Duration::minutes(minutos.min(24 * 60) as i64)
+}
+
+/// Grava a entrega pendente (nova, ou de volta no mesmo `id` após outra falha).
+pub fn entregas_gravar(
+	contexto: &ContextoSaida,
+	id: Option<&[u8]>,
+	mut pendente: EntregaPendente,
+) -> Result<(), Box<dyn Error>> {
+	if let Some(cifra) = contexto.cifra
+		&& pendente.registro.cifrado.is_none()
+	{
+		pendente.registro.selar(cifra)?;
+	}
+	let tree = contexto.db.open_tree(FILA_ENTREGAS_TREE)?;
+	let valor = serde_json::to_vec(&pendente)?;
+	match id {
+		Some(id) => tree.insert(id, valor)?,
+		None => tree.insert(db_novo_id(contexto.db, &tree)?, valor)?,
+	};
+	tree.flush()?;
+	Ok(())
+}
+
+/// Tenta de novo as entregas cuja espera venceu. Saídas removidas da configuração e entregas
+/// que esgotaram as tentativas são descartadas. Devolve (entregues, descartadas).
+pub async fn entregas_reenviar(
+	config: &SaidaConfig,
+	contexto: &ContextoSaida<'_>,
+) -> Result<(usize, usize), Box<dyn Error>> {
+	let tree = contexto.db.open_tree(FILA_ENTREGAS_TREE)?;
+	let agora = Utc::now();
+	let mut vencidas = Vec::new();
+	for entrada in tree.iter() {
+		let (id, valor) = entrada?;
+		match serde_json::from_slice::<EntregaPendente>(&valor) {
+			Ok(pendente) if pendente.proxima_em <= agora => vencidas.push((id, pendente)),
+			Ok(_) => {}
+			Err(e) => {
+				// Ilegível hoje, ilegível sempre: sai da fila para não ser lida a cada ciclo
+				warn!("Entrega pendente ilegível descartada: {}", e);
+				tree.remove(&id)?;
+			}
+		}
+	}
+
+	let saidas = config.ativas();
+	let local = Local::now();
+	let (mut entregues, mut descartadas) = (0, 0);
+	for (id, mut pendente) in vencidas {
+		let Some((saida, _)) = saidas.iter().find(|(s, _)| s.nome() == pendente.saida) else {
+			tree.remove(&id)?;
+			descartadas += 1;
+			continue;
+		};
+		// Uma nova tentativa que vence no silêncio espera com as demais
+		if config
+			.silencio
+			.as_ref()
+			.is_some_and(|s| s.segura(&pendente.saida, pendente.prioridade, local))
+		{
+			continue;
+		}
+		// Sem a chave certa (ex.: trocada na configuração), a entrega fica na fila para quando
+		// ela voltar, e as demais seguem
+		let mut registro = pendente.registro.clone();
+		if let Err(e) = registro.abrir(contexto.cifra) {
+			warn!(
+				saida = %pendente.saida,
+				"Entrega pendente de '{}' ignorada neste ciclo: {}",
+				pendente.registro.titulo,
+				e
+			);
+			continue;
+		}
+		let item = ItemEntregue {
+			chave: &pendente.chave,
+			registro: &registro,
+			numero: pendente.numero,
+			prioridade: pendente.prioridade,
+			importancia: pendente.importancia,
+			resumo_omitido: pendente.resumo_omitido,
+			tokens_filtro: pendente.tokens_filtro,
+			tokens_resumo: pendente.tokens_resumo,
+		};
+		match saida.entregar(contexto, &item).await {
+			Ok(()) => {
+				tree.remove(&id)?;
+				entregues += 1;
+			}
+			Err(e) if pendente.tentativas + 1 >= config.tentativas_entrega => {
+				error!(
+					saida = %pendente.saida,
+					"Entrega de '{}' descartada após {} tentativas: {}",
+					registro.titulo,
+					pendente.tentativas + 1,
+					e
+				);
+				tree.remove(&id)?;
+				descartadas += 1;
+			}
+			Err(e) => {
+				pendente.tentativas += 1;
+				pendente.proxima_em = Utc::now() + espera_tentativa(config, pendente.tentativas);
+				pendente.ultimo_erro = e.to_string();
+				entregas_gravar(contexto, Some(&id), pendente)?;
+			}
+		}
+	}
+	tree.flush()?;
+	Ok((entregues, descartadas))
+}
+
+impl Saida for TerminalConfig {
+	fn nome(&self) -> &'static str {
+		"TERMINAL"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		_: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		let registro = item.registro;
+		informar!(
+			"{}",
+			msg!(
+				"item.relevante",
+				BOLD_GREEN,
+				RESET,
+				item.numero.map(|n| format!("[{}] ", n)).unwrap_or_default(),
+				BOLD,
+				registro.titulo,
+				RESET
+			)
+		);
+		informar!("{}", msg!("item.link", BOLD, RESET, registro.link));
+		let fonte = msg!(
+			"item.fonte",
+			BOLD,
+			RESET,
+			registro.fonte.nome,
+			registro
+				.fonte
+				.dominio
+				.as_ref()
+				.map(|d| format!(" ({})", d))
+				.unwrap_or_default(),
+			registro
+				.fonte
+				.grupo
+				.as_ref()
+				.map(|g| format!(" [{}]", g))
+				.unwrap_or_default()
+		);
+		informar!("{}", fonte);
+		match &registro.resumo {
+			Some(resumo) => informar!(
+				"{}",
+				msg!(
+					"item.resumo",
+					BOLD,
+					registro.modelo_resumo.as_deref().unwrap_or_default(),
+					RESET,
+					resumo
+				)
+			),
+			None if item.resumo_omitido => {
+				informar!("{}", msg!("item.resumo_omitido", BOLD, RESET))
+			}
+			None => {}
+		}
+		Box::pin(async { Ok(()) })
+	}
+}
+
+impl Saida for ObsidianConfig {
+	fn nome(&self) -> &'static str {
+		"OBSIDIAN"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(async move {
+			let registro = item.registro;
+			if self.nota_diaria {
+				obsidian_anexar_nota_diaria(
+					self,
+					&registro.titulo,
+					&registro.link,
+					&registro.fonte,
+					registro.resumo.as_deref(),
+				)?;
+			}
+			if let Some(pasta_notas) = &self.pasta_notas {
+				exportar_markdown(
+					&Path::new(&self.cofre).join(pasta_notas),
+					contexto.layout,
+					registro,
+					Some(item.importancia),
+				)?;
+			}
+			Ok(())
+		})
+	}
+}
+
+impl Saida for DigestConfig {
+	fn nome(&self) -> &'static str {
+		"DIGEST"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		_: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(async move {
+			let registro = item.registro;
+			digest_anexar(
+				self,
+				&obsidian_entrada(
+					&registro.titulo,
+					&registro.link,
+					&registro.fonte,
+					registro.resumo.as_deref(),
+				),
+			)?;
+			Ok(())
+		})
+	}
+}
+
+impl Saida for TelegramConfig {
+	fn nome(&self) -> &'static str {
+		"TELEGRAM"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		let registro = item.registro;
+		Box::pin(telegram_enviar_item(
+			contexto.client,
+			self,
+			&registro.titulo,
+			&registro.link,
+			&registro.fonte,
+			registro.resumo.as_deref(),
+		))
+	}
+}
+
+impl Saida for MatrixConfig {
+	fn nome(&self) -> &'static str {
+		"MATRIX"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		let registro = item.registro;
+		Box::pin(matrix_enviar_item(
+			contexto.client,
+			self,
+			&registro.titulo,
+			&registro.link,
+			&registro.fonte,
+			registro.resumo.as_deref(),
+		))
+	}
+}
+
+impl Saida for XmppConfig {
+	fn nome(&self) -> &'static str {
+		"XMPP"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		_: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		let registro = item.registro;
+		let mut texto = format!(
+			"{}\n{}\n{}",
+			registro.titulo, registro.link, registro.fonte.nome
+		);
+		if let Some(resumo) = &registro.resumo {
+			texto.push_str("\n\n");
+			texto.push_str(resumo.trim());
+		}
+		Box::pin(async move { xmpp_enviar(self, &texto).await })
+	}
+}
+
+impl Saida for SlackConfig {
+	fn nome(&self) -> &'static str {
+		"SLACK"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		let registro = item.registro;
+		Box::pin(slack_enviar_item(
+			contexto.client,
+			self,
+			&registro.titulo,
+			&registro.link,
+			&registro.fonte,
+			registro.resumo.as_deref(),
+		))
+	}
+}
+
+impl Saida for NtfyConfig {
+	fn nome(&self) -> &'static str {
+		"NTFY"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		let registro = item.registro;
+		Box::pin(ntfy_enviar_item(
+			contexto.client,
+			self,
+			&registro.titulo,
+			&registro.link,
+			&registro.fonte,
+			registro.resumo.as_deref(),
+			item.prioridade,
+		))
+	}
+}
+
+impl Saida for GotifyConfig {
+	fn nome(&self) -> &'static str {
+		"GOTIFY"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		let registro = item.registro;
+		Box::pin(gotify_enviar_item(
+			contexto.client,
+			self,
+			&registro.titulo,
+			&registro.link,
+			&registro.fonte,
+			registro.resumo.as_deref(),
+			item.prioridade,
+		))
+	}
+}
+
+impl Saida for DesktopConfig {
+	fn nome(&self) -> &'static str {
+		"DESKTOP"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		_: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		let registro = item.registro;
+		Box::pin(desktop_notificar_item(
+			self,
+			&registro.titulo,
+			&registro.fonte,
+			registro.resumo.as_deref(),
+		))
+	}
+}
+
+impl Saida for MarkdownConfig {
+	fn nome(&self) -> &'static str {
+		"MARKDOWN"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(async move {
+			exportar_markdown(Path::new(&self.pasta), contexto.layout, item.registro, None)?;
+			Ok(())
+		})
+	}
+}
+
+impl Saida for SqliteConfig {
+	fn nome(&self) -> &'static str {
+		"SQLITE"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		_: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(async move {
+			sqlite_gravar(self, &[LinhaSqlite::relevante(item)])?;
+			Ok(())
+		})
+	}
+}
+
+impl Saida for CalendarioConfig {
+	fn nome(&self) -> &'static str {
+		"CALENDÁRIO"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(async move {
+			// Só os alertas com início e fim entram; o arquivo é regerado ao fim do ciclo
+			if let Some((inicio, fim)) = janela_alerta(&item.registro.descricao) {
+				calendario_registrar(contexto.db, item.chave, item.registro, inicio, fim)?;
+			}
+			Ok(())
+		})
+	}
+}
+
+impl Saida for JsonlConfig {
+	fn nome(&self) -> &'static str {
+		"JSONL"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		_: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(async move { jsonl_anexar(self, item.chave, item.registro) })
+	}
+}
+
+impl Saida for RssConfig {
+	fn nome(&self) -> &'static str {
+		"RSS"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		_: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(async move { rss_anexar(self, item.registro) })
+	}
+}
+
+impl Saida for WallabagConfig {
+	fn nome(&self) -> &'static str {
+		"WALLABAG"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(wallabag_salvar_item(contexto.client, self, item.registro))
+	}
+}
+
+impl Saida for ReadeckConfig {
+	fn nome(&self) -> &'static str {
+		"READECK"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(readeck_salvar_item(contexto.client, self, item.registro))
+	}
+}
+
+impl Saida for LinkdingConfig {
+	fn nome(&self) -> &'static str {
+		"LINKDING"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(linkding_salvar_item(contexto.client, self, item.registro))
+	}
+}
+
+impl Saida for ShioriConfig {
+	fn nome(&self) -> &'static str {
+		"SHIORI"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(shiori_salvar_item(contexto.client, self, item.registro))
+	}
+}
+
+impl Saida for WebhookConfig {
+	fn nome(&self) -> &'static str {
+		"WEBHOOK"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(webhook_enviar_item(contexto.client, self, item.registro))
+	}
+}
+
+impl Saida for ComandoConfig {
+	fn nome(&self) -> &'static str {
+		"COMANDO"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		_: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(comando_executar_item(self, item))
+	}
+}
+
+impl Saida for MqttConfig {
+	fn nome(&self) -> &'static str {
+		"MQTT"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		_: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(mqtt_publicar_item(self, item.registro, item.prioridade))
+	}
+}
+
+impl Saida for EmailConfig {
+	fn nome(&self) -> &'static str {
+		"EMAIL"
+	}
+
+	fn entregar<'a>(
+		&'a self,
+		contexto: &'a ContextoSaida<'a>,
+		item: &'a ItemEntregue<'a>,
+	) -> EnvioSaida<'a> {
+		Box::pin(email_registrar_item(
+			contexto.db,
+			self,
+			item.registro,
+			item.prioridade,
+			contexto.cifra,
+		))
+	}
+}
+
+// =================================================================
+// FUNÇÕES DE SAÍDA
+// =================================================================
+
+/// Escapa colchetes para não quebrar o link Markdown `[título](link)`.
+pub fn markdown_escapar_titulo(titulo: &str) -> String {
+	titulo.replace('[', "\\[").replace(']', "\\]")
+}
+
+/// Anexa o item sob `titulo_secao` na nota diária do Obsidian.
+pub fn obsidian_anexar_nota_diaria(
+	config: &ObsidianConfig,
+	title: &str,
+	link: &str,
+	metadados: &MetadadosFonte,
+	resumo: Option<&str>,
+) -> io::Result<()> {
+	let entrada = obsidian_entrada(title, link, metadados, resumo);
+	obsidian_anexar_na_secao(config, &config.titulo_secao, &entrada)
+}
+
+/// Linha Markdown do item (com o resumo como citação).
+pub fn obsidian_entrada(
+	title: &str,
+	link: &str,
+	metadados: &MetadadosFonte,
+	resumo: Option<&str>,
+) -> String {
+	let mut entrada = format!(
+		"- [{}]({}) — {}",
+		markdown_escapar_titulo(title),
+		link,
+		metadados.nome
+	);
+	if let Some(dominio) = &metadados.dominio {
+		entrada.push_str(&format!(" ({})", dominio));
+	}
+	if let Some(grupo) = &metadados.grupo {
+		entrada.push_str(&format!(" #{}", grupo.replace(' ', "-")));
+	}
+	entrada.push('\n');
+	if let Some(resumo) = resumo {
+		for linha in resumo.lines().filter(|l| !l.trim().is_empty()) {
+			entrada.push_str(&format!("  > {}\n", linha.trim()));
+		}
+	}
+	entrada
+}
+
+/// Anexa `entrada` sob o título `secao` na nota diária do Obsidian, criando a nota ou a seção
+/// se necessário. Itens novos entram no final da seção, antes do próximo título de mesmo nível.
+pub fn obsidian_anexar_na_secao(
+	config: &ObsidianConfig,
+	secao: &str,
+	entrada: &str,
+) -> io::Result<()> {
+	let nome_nota = format!("{}.md", Local::now().format(&config.formato_nota_diaria));
+	let caminho = std::path::Path::new(&config.cofre)
+		.join(&config.pasta_notas_diarias)
+		.join(nome_nota);
+
+	if let Some(pasta) = caminho.parent() {
+		fs::create_dir_all(pasta)?;
+	}
+
+	let conteudo = match fs::read_to_string(&caminho) {
+		Ok(c) => c,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+		Err(e) => return Err(e),
+	};
+	let mut linhas: Vec<String> = conteudo.lines().map(str::to_string).collect();
+
+	let secao = secao.trim();
+	let nivel = secao.chars().take_while(|c| *c == '#').count();
+
+	match linhas.iter().position(|l| l.trim() == secao) {
+		Some(inicio) => {
+			// Fim da seção: próximo título de nível igual ou superior
+			let fim = linhas[inicio + 1..]
+				.iter()
+				.position(|l| {
+					let n = l.chars().take_while(|c| *c == '#').count();
+					n > 0 && n <= nivel && l[n..].starts_with(' ')
+				})
+				.map(|p| p + inicio + 1)
+				.unwrap_or(linhas.len());
+
+			// Insere logo após a última linha não vazia da seção
+			let mut pos = fim;
+			while pos > inicio + 1 && linhas[pos - 1].trim().is_empty() {
+				pos -= 1;
+			}
+			for (i, linha) in entrada.lines().enumerate() {
+				linhas.insert(pos + i, linha.to_string());
+			}
+		}
+		None => {
+			if linhas.last().is_some_and(|l| !l.trim().is_empty()) {
+				linhas.push(String::new());
+			}
+			linhas.push(secao.to_string());
+			linhas.extend(entrada.lines().map(str::to_string));
+		}
+	}
+
+	let mut novo_conteudo = linhas.join("\n");
+	novo_conteudo.push('\n');
+	fs::write(caminho, novo_conteudo)
+}
+
+/// Acrescenta `texto` ao arquivo do dia, criando-o com um título se ainda não existir. O
+/// arquivo só cresce: nada já escrito é reordenado.
+pub fn digest_anexar(config: &DigestConfig, texto: &str) -> io::Result<()> {
+	let hoje = Local::now();
+	let caminho = Path::new(&config.pasta).join(format!("{}.md", hoje.format(&config.formato)));
+	fs::create_dir_all(&config.pasta)?;
+	let novo = !caminho.exists();
+	let mut arquivo = fs::OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(&caminho)?;
+	if novo {
+		writeln!(arquivo, "# Notícias de {}\n", hoje.format("%d/%m/%Y"))?;
+	}
+	arquivo.write_all(texto.as_bytes())
+}
+
+// Esquema documentado no README; datas em RFC 3339 (UTC)
+pub const SQLITE_ESQUEMA: &str = "CREATE TABLE IF NOT EXISTS itens (
+	chave TEXT PRIMARY KEY,
+	link TEXT NOT NULL,
+	titulo TEXT NOT NULL,
+	fonte TEXT NOT NULL,
+	grupo TEXT,
+	veredito TEXT NOT NULL,
+	resumo TEXT,
+	modelo TEXT,
+	publicado TEXT,
+	avaliado_em TEXT NOT NULL,
+	tokens_filtro INTEGER,
+	tokens_resumo INTEGER
+);
+CREATE INDEX IF NOT EXISTS itens_avaliado_em ON itens (avaliado_em);";
+
+/// Linha da tabela `itens` do espelho em SQLite.
+pub struct LinhaSqlite<'a> {
+	pub chave: &'a str,
+	pub link: &'a str,
+	pub titulo: &'a str,
+	pub fonte: &'a str,
+	pub grupo: Option<&'a str>,
+	// "relevante" ou "irrelevante"
+	pub veredito: &'a str,
+	pub resumo: Option<&'a str>,
+	pub modelo: Option<&'a str>,
+	pub publicado: Option<DateTime<Utc>>,
+	pub avaliado_em: DateTime<Utc>,
+	pub tokens_filtro: Option<u32>,
+	pub tokens_resumo: Option<u32>,
+}
+
+impl<'a> LinhaSqlite<'a> {
+	pub fn relevante(item: &ItemEntregue<'a>) -> Self {
+		let registro = item.registro;
+		LinhaSqlite {
+			chave: item.chave,
+			link: &registro.link,
+			titulo: &registro.titulo,
+			fonte: &registro.fonte.nome,
+			grupo: registro.fonte.grupo.as_deref(),
+			veredito: "relevante",
+			resumo: registro.resumo.as_deref(),
+			modelo: registro.modelo_resumo.as_deref(),
+			publicado: registro.publicado,
+			avaliado_em: registro.entregue_em,
+			tokens_filtro: item.tokens_filtro,
+			tokens_resumo: item.tokens_resumo,
+		}
+	}
+
+	pub fn rejeitado(item: &'a ItemRejeitado, avaliado_em: DateTime<Utc>) -> Self {
+		LinhaSqlite {
+			chave: &item.noticia.chave,
+			link: &item.noticia.link,
+			titulo: &item.noticia.titulo,
+			fonte: &item.noticia.fonte,
+			grupo: item.noticia.grupo.as_deref(),
+			veredito: "irrelevante",
+			resumo: None,
+			modelo: None,
+			publicado: item.noticia.publicado,
+			avaliado_em,
+			tokens_filtro: item.tokens_filtro,
+			tokens_resumo: None,
+		}
+	}
+}
+
+/// Grava as linhas numa transação. Um item reavaliado (ex.: promovido pela revisão) substitui
+/// a linha anterior.
+pub fn sqlite_gravar(config: &SqliteConfig, linhas: &[LinhaSqlite]) -> rusqlite::Result<()> {
+	let mut conexao = rusqlite::Connection::open(&config.caminho)?;
+	conexao.execute_batch(SQLITE_ESQUEMA)?;
+	let transacao = conexao.transaction()?;
+	{
+		let mut insercao = transacao.prepare(
+			"INSERT OR REPLACE INTO itens (chave, link, titulo, fonte, grupo, veredito, resumo, \
+			 modelo, publicado, avaliado_em, tokens_filtro, tokens_resumo) \
+			 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+		)?;
+		for linha in linhas {
+			insercao.execute(rusqlite::params![
+				linha.chave,
+				linha.link,
+				linha.titulo,
+				linha.fonte,
+				linha.grupo,
+				linha.veredito,
+				linha.resumo,
+				linha.modelo,
+				linha.publicado.map(|d| d.to_rfc3339()),
+				linha.avaliado_em.to_rfc3339(),
+				linha.tokens_filtro,
+				linha.tokens_resumo,
+			])?;
+		}
+	}
+	transacao.commit()
+}
+
+/// Acrescenta o registro (com a chave de deduplicação) como uma linha JSON ao arquivo do dia.
+pub fn jsonl_anexar(
+	config: &JsonlConfig,
+	chave: &str,
+	registro: &RegistroArquivo,
+) -> Result<(), Box<dyn Error>> {
+	let mut objeto = serde_json::to_value(registro)?;
+	objeto["chave"] = serde_json::Value::from(chave);
+	let mut linha = serde_json::to_string(&objeto)?;
+	linha.push('\n');
+
+	fs::create_dir_all(&config.pasta)?;
+	let caminho =
+		Path::new(&config.pasta).join(format!("{}.jsonl", Local::now().format(&config.formato)));
+	// Uma única escrita por linha, para leitores concorrentes não verem linhas partidas
+	fs::OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(caminho)?
+		.write_all(linha.as_bytes())?;
+	Ok(())
+}
+
+/// Sorteia até `quantidade` itens rejeitados no ciclo e os exibe numa seção de QA (também na
+/// nota diária do Obsidian, se configurada), para conferir falsos negativos por amostragem.
+pub fn entregar_amostra_qa(
+	rejeitados: &[ItemRejeitado],
+	quantidade: usize,
+	saida_config: &SaidaConfig,
+) {
+	let amostra: Vec<&ItemNoticia> = rejeitados
+		.choose_multiple(&mut rand::rng(), quantidade)
+		.map(|r| &r.noticia)
+		.collect();
+	if amostra.is_empty() {
+		return;
+	}
+
+	informar!(
+		"{}",
+		msg!(
+			"qa.titulo",
+			BOLD_YELLOW,
+			amostra.len(),
+			rejeitados.len(),
+			RESET
+		)
+	);
+	for noticia in &amostra {
+		informar!(
+			"{}",
+			msg!(
+				"qa.rejeitado",
+				BOLD_RED,
+				RESET,
+				noticia.titulo,
+				noticia.fonte
+			)
+		);
+		informar!("   {}", noticia.link);
+	}
+
+	if let Some(obsidian) = &saida_config.obsidian {
+		let entradas: String = amostra
+			.iter()
+			.map(|n| obsidian_entrada(&n.titulo, &n.link, &MetadadosFonte::de(n), None))
+			.collect();
+		if let Err(e) = obsidian_anexar_na_secao(obsidian, &obsidian.titulo_secao_qa, &entradas) {
+			error!("Falha ao anexar a amostra de QA: {}", e);
+		}
+	}
+}
+
+/// Varredura de falsos negativos: reavalia uma amostra do cache de irrelevância com um modelo
+/// maior. Os itens que ele aprovar são devolvidos, com a prioridade da fonte, para a fila de
+/// resumo; quem chama só os tira do cache depois que a entrega os marcar como processados,
+/// para que uma falha ou reinício no meio não perca o item. A taxa de
+/// discordância entre os dois modelos fica acumulada em METRICAS_TREE como medida de qualidade
+/// do filtro local.
+pub async fn revisar_irrelevantes(
+	client: &Client,
+	db: &Arc<sled::Db>,
+	revisao: &RevisaoConfig,
+	filtro_config: Arc<FiltroConfig>,
+	geral_config: &GeralConfig,
+	fontes: &[FonteConfigurada<'_>],
+	cifra: Option<&CifraArquivo>,
+) -> Result<Vec<ItemPendente>, Box<dyn Error>> {
+	let tree = db.open_tree(IRRELEVANT_CACHE_TREE)?;
+	let termos: Vec<String> = revisao
+		.palavras_chave
+		.iter()
+		.map(|t| t.to_lowercase())
+		.collect();
+
+	let mut candidatos = Vec::new();
+	for entrada in tree.iter() {
+		let (chave, valor) = entrada?;
+		let Ok(mut registro) = serde_json::from_slice::<RegistroIrrelevante>(&valor) else {
+			continue;
+		};
+		if registro.revisado {
+			continue;
+		}
+		if let Err(e) = registro.abrir(cifra) {
+			warn!(
+				"Item '{}' do cache de irrelevância ignorado: {}",
+				String::from_utf8_lossy(&chave),
+				e
+			);
+			continue;
+		}
+		if !termos.is_empty() {
+			let texto = format!("{} {}", registro.titulo, registro.descricao).to_lowercase();
+			if !termos.iter().any(|t| texto.contains(t.as_str())) {
+				continue;
+			}
+		}
+		candidatos.push((String::from_utf8_lossy(&chave).into_owned(), registro));
+	}
+
+	let amostra: Vec<&(String, RegistroIrrelevante)> = candidatos
+		.choose_multiple(&mut rand::rng(), revisao.amostras)
+		.collect();
+	if amostra.is_empty() {
+		return Ok(Vec::new());
+	}
+
+	let mut geral_revisor = geral_config.clone();
+	if let Some(endereco) = &revisao.endereco {
+		geral_revisor.endereco = endereco.clone();
+		geral_revisor.chave_api = None;
+	}
+	geral_revisor.modelo_resumo = revisao.modelo.clone();
+	if revisao.chave_api.is_some() {
+		geral_revisor.chave_api = revisao.chave_api.clone();
+	}
+	let geral_revisor = Arc::new(geral_revisor);
+
+	informar!(
+		"{}",
+		msg!(
+			"revisao.titulo",
+			BOLD_YELLOW,
+			amostra.len(),
+			candidatos.len(),
+			revisao.modelo,
+			RESET
+		)
+	);
+
+	let mut promovidos = Vec::new();
+	let mut avaliados = 0u64;
+	for (chave, registro) in amostra {
+		let (aprovado, tokens) = match call_llm_filter(
+			client,
+			&registro.titulo,
+			&registro.descricao,
+			Arc::clone(&filtro_config),
+			Arc::clone(&geral_revisor),
+		)
+		.await
+		{
+			Ok(aprovado) => aprovado,
+			Err(e) => {
+				error!("Falha no modelo revisor: {}", e);
+				break;
+			}
+		};
+		avaliados += 1;
+
+		if aprovado {
+			let noticia = registro.noticia(chave);
+			let prioridade = fontes
+				.iter()
+				.find(|f| f.nome() == noticia.fonte)
+				.map(|f| f.opcoes().prioridade)
+				.unwrap_or_default();
+			let urgencia = contar_termos(&noticia, &filtro_config.termos_urgencia);
+			let importancia = pontuar_importancia(&noticia, urgencia, &filtro_config);
+			informar!(
+				"{}",
+				msg!(
+					"revisao.promovido",
+					BOLD_GREEN,
+					RESET,
+					noticia.titulo,
+					noticia.fonte
+				)
+			);
+			promovidos.push(ItemPendente {
+				noticia,
+				prioridade,
+				urgencia,
+				importancia,
+				tokens_filtro: tokens,
+			});
+		} else {
+			let mut revisado = RegistroIrrelevante {
+				revisado: true,
+				..registro.clone()
+			};
+			if let Some(cifra) = cifra {
+				revisado.selar(cifra)?;
+			}
+			tree.insert(chave.as_bytes(), serde_json::to_vec(&revisado)?)?;
+		}
+	}
+	tree.flush()?;
+
+	if avaliados == 0 {
+		return Ok(promovidos);
+	}
+	let discordancias = promovidos.len() as u64;
+	let total_avaliados = db_somar_metrica(db, "revisao_avaliados", avaliados)?;
+	let total_promovidos = db_somar_metrica(db, "revisao_discordancias", discordancias)?;
+	info!(
+		"Discordância nesta varredura: {} de {} ({:.1}%). Acumulada: {} de {} ({:.1}%).",
+		discordancias,
+		avaliados,
+		discordancias as f64 * 100.0 / avaliados as f64,
+		total_promovidos,
+		total_avaliados,
+		total_promovidos as f64 * 100.0 / total_avaliados as f64
+	);
+	Ok(promovidos)
+}
+
+/// Converte um texto em trecho de nome de arquivo: minúsculas, letras e números separados por
+/// hífens, com até 80 caracteres.
+pub fn slug(texto: &str) -> String {
+	let mut slug = String::new();
+	for c in texto.chars().flat_map(char::to_lowercase) {
+		if c.is_alphanumeric() {
+			slug.push(c);
+		} else if !slug.is_empty() && !slug.ends_with('-') {
+			slug.push('-');
+		}
+		if slug.chars().count() >= 80 {
+			break;
+		}
+	}
+	let slug = slug.trim_end_matches('-');
+	if slug.is_empty() {
+		"item".to_string()
+	} else {
+		slug.to_string()
+	}
+}
+
+/// Valor de um marcador do layout como um único componente de caminho (sem separadores).
+pub fn componente_caminho(texto: &str) -> String {
+	let componente: String = texto
+		.chars()
+		.map(|c| {
+			if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() {
+				'-'
+			} else {
+				c
+			}
+		})
+		.collect();
+	let componente = componente.trim().trim_start_matches('.');
+	if componente.is_empty() {
+		"sem-nome".to_string()
+	} else {
+		componente.to_string()
+	}
+}
+
+/// Caminho relativo do item segundo o `layout` de `[saida]`. A data é a de publicação, ou a
+/// de entrega se a fonte não informar.
+pub fn caminho_exportacao(layout: &str, registro: &RegistroArquivo) -> PathBuf {
+	let data = registro
+		.publicado
+		.unwrap_or(registro.entregue_em)
+		.with_timezone(&Local);
+	let grupo = registro.fonte.grupo.as_deref().unwrap_or("sem-grupo");
+	let dominio = registro.fonte.dominio.as_deref().unwrap_or("sem-dominio");
+	let caminho = layout
+		.replace("{ano}", &format!("{:04}", data.year()))
+		.replace("{mes}", &format!("{:02}", data.month()))
+		.replace("{dia}", &format!("{:02}", data.day()))
+		.replace("{fonte}", &componente_caminho(&registro.fonte.nome))
+		.replace("{grupo}", &componente_caminho(grupo))
+		.replace("{dominio}", &componente_caminho(dominio))
+		.replace("{slug}", &slug(&registro.titulo))
+		.replace(
+			"{id}",
+			&format!("{:08x}", hash_fnv1a(&registro.link) as u32),
+		);
+	// Componentes vazios ou ".." não saem da pasta do exportador
+	caminho
+		.split(['/', '\\'])
+		.filter(|c| !c.is_empty() && *c != "." && *c != "..")
+		.collect()
+}
+
+/// Nota Markdown do item, com propriedades (front matter) legíveis pelo Obsidian. A
+/// `importancia` só é conhecida na entrega; as notas exportadas depois saem sem ela.
+pub fn markdown_do_registro(registro: &RegistroArquivo, importancia: Option<u32>) -> String {
+	// Strings JSON também são strings YAML válidas, já escapadas
+	let texto = |s: &str| serde_json::to_string(s).unwrap_or_default();
+	let mut nota = String::from("---\n");
+	nota.push_str(&format!("titulo: {}\n", texto(&registro.titulo)));
+	nota.push_str(&format!("link: {}\n", texto(&registro.link)));
+	nota.push_str(&format!("fonte: {}\n", texto(&registro.fonte.nome)));
+	if let Some(dominio) = &registro.fonte.dominio {
+		nota.push_str(&format!("dominio: {}\n", texto(dominio)));
+	}
+	if let Some(grupo) = &registro.fonte.grupo {
+		nota.push_str(&format!("grupo: {}\n", texto(grupo)));
+	}
+	if let Some(publicado) = registro.publicado {
+		nota.push_str(&format!("publicado: {}\n", publicado.to_rfc3339()));
+	}
+	nota.push_str(&format!(
+		"entregue_em: {}\n",
+		registro.entregue_em.to_rfc3339()
+	));
+	// Tags do Obsidian não aceitam espaços
+	let tags: Vec<String> = registro.fonte.grupo.iter().map(|g| slug(g)).collect();
+	nota.push_str(&format!(
+		"tags: {}\n",
+		serde_json::to_string(&tags).unwrap_or_default()
+	));
+	if let Some(importancia) = importancia {
+		nota.push_str(&format!("importancia: {}\n", importancia));
+	}
+	nota.push_str("---\n\n");
+	nota.push_str(&format!(
+		"# [{}]({})\n\n",
+		markdown_escapar_titulo(&registro.titulo),
+		registro.link
+	));
+	match &registro.resumo {
+		Some(resumo) => nota.push_str(resumo.trim()),
+		None => nota.push_str(registro.descricao.trim()),
+	}
+	nota.push('\n');
+	nota
+}
+
+/// Caminho com "/" (para links), a partir dos componentes do caminho relativo.
+pub fn caminho_url(caminho: &Path) -> String {
+	caminho
+		.components()
+		.map(|c| c.as_os_str().to_string_lossy())
+		.collect::<Vec<_>>()
+		.join("/")
+}
+
+/// Página HTML completa, com estilo embutido (o site não depende de outros arquivos).
+pub fn site_pagina(titulo: &str, conteudo: &str) -> String {
+	format!(
+		"<!DOCTYPE html>\n<html lang=\"pt-BR\"><head><meta charset=\"utf-8\">\
+		 <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+		 <title>{}</title><style>body{{font-family:sans-serif;max-width:50em;margin:auto;\
+		 padding:1em;line-height:1.5}}.fonte{{color:#666;font-size:.9em}}\
+		 article{{margin-bottom:1.5em}}table{{border-collapse:collapse}}\
+		 th,td{{padding:.2em .6em;border-bottom:1px solid #ddd;text-align:left}}\
+		 .falha{{color:#b00}}</style></head><body>\n{}\n</body></html>\n",
+		html_escapar(titulo),
+		conteudo
+	)
+}
+
+/// Bloco HTML de um item; `pagina` é o link relativo para a página própria do item.
+pub fn site_artigo(registro: &RegistroArquivo, pagina: Option<&str>) -> String {
+	let mut fonte = html_escapar(&registro.fonte.nome);
+	if let Some(dominio) = &registro.fonte.dominio {
+		fonte.push_str(&format!(" ({})", html_escapar(dominio)));
+	}
+	if let Some(grupo) = &registro.fonte.grupo {
+		fonte.push_str(&format!(" · {}", html_escapar(grupo)));
+	}
+	let mut artigo = format!(
+		"<article><h3><a href=\"{}\">{}</a></h3><div class=\"fonte\">{}",
+		html_escapar(&registro.link),
+		html_escapar(&registro.titulo),
+		fonte
+	);
+	if let Some(pagina) = pagina {
+		artigo.push_str(&format!(
+			" · <a href=\"{}\">página</a>",
+			html_escapar(pagina)
+		));
+	}
+	artigo.push_str("</div>");
+	if let Some(resumo) = &registro.resumo {
+		artigo.push_str(&format!(
+			"<p>{}</p>",
+			html_escapar(resumo.trim()).replace('\n', "<br>")
+		));
+	}
+	artigo.push_str("</article>\n");
+	artigo
+}
+
+/// Regera o site: índice, uma página por dia de entrega (dias/AAAA-MM-DD.html) e uma por item
+/// no caminho do `[saida].layout` (com extensão .html). Devolve quantos itens entraram.
+pub fn site_gerar(
+	db: &Db,
+	config: &SiteConfig,
+	layout: &str,
+	cifra: Option<&CifraArquivo>,
+) -> Result<usize, Box<dyn Error>> {
+	let mut por_dia: BTreeMap<NaiveDate, Vec<RegistroArquivo>> = BTreeMap::new();
+	for entrada in db.open_tree(ARQUIVO_TREE)?.iter() {
+		let (_, valor) = entrada?;
+		let registro = db_ler_arquivo(&valor, cifra)?;
+		por_dia
+			.entry(registro.entregue_em.with_timezone(&Local).date_naive())
+			.or_default()
+			.push(registro);
+	}
+
+	let pasta = Path::new(&config.pasta);
+	fs::create_dir_all(pasta.join("dias"))?;
+	let mut indice = format!("<h1>{}</h1>\n<ul>\n", html_escapar(&config.titulo));
+	let mut total = 0;
+	for (dia, registros) in por_dia.iter_mut().rev().take(config.dias) {
+		registros.sort_by_key(|r| std::cmp::Reverse(r.entregue_em));
+		let mut pagina_dia = format!(
+			"<p><a href=\"../index.html\">{}</a></p>\n<h1>{}</h1>\n",
+			html_escapar(&config.titulo),
+			dia.format("%d/%m/%Y")
+		);
+		for registro in registros.iter() {
+			let relativo = caminho_exportacao(layout, registro).with_extension("html");
+			let subida = "../".repeat(relativo.components().count().saturating_sub(1));
+			let pagina_item = site_pagina(
+				&registro.titulo,
+				&format!(
+					"<p><a href=\"{}index.html\">{}</a></p>\n{}",
+					subida,
+					html_escapar(&config.titulo),
+					site_artigo(registro, None)
+				),
+			);
+			let caminho = pasta.join(&relativo);
+			if let Some(pai) = caminho.parent() {
+				fs::create_dir_all(pai)?;
+			}
+			fs::write(caminho, pagina_item)?;
+			pagina_dia.push_str(&site_artigo(
+				registro,
+				Some(&format!("../{}", caminho_url(&relativo))),
+			));
+		}
+		let nome_dia = format!("{}.html", dia.format("%Y-%m-%d"));
+		fs::write(
+			pasta.join("dias").join(&nome_dia),
+			site_pagina(
+				&format!("{} — {}", config.titulo, dia.format("%d/%m/%Y")),
+				&pagina_dia,
+			),
+		)?;
+		indice.push_str(&format!(
+			"<li><a href=\"dias/{}\">{}</a> ({} itens)</li>\n",
+			nome_dia,
+			dia.format("%d/%m/%Y"),
+			registros.len()
+		));
+		total += registros.len();
+	}
+	indice.push_str("</ul>\n");
+	fs::write(
+		pasta.join("index.html"),
+		site_pagina(&config.titulo, &indice),
+	)?;
+	Ok(total)
+}
+
+/// Acrescenta o item ao início do arquivo RSS, mantendo no máximo `config.maximo` itens.
+pub fn rss_anexar(config: &RssConfig, registro: &RegistroArquivo) -> Result<(), Box<dyn Error>> {
+	let caminho = Path::new(&config.arquivo);
+	let mut canal = match fs::read(caminho) {
+		Ok(conteudo) => Channel::read_from(&conteudo[..])?,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Channel::default(),
+		Err(e) => return Err(e.into()),
+	};
+	canal.set_title(config.titulo.clone());
+	canal.set_link(config.link.clone());
+	canal.set_description("Itens relevantes selecionados pelo FilterFlow".to_string());
+
+	let mut guid = Guid::default();
+	guid.set_value(registro.link.clone());
+	guid.set_permalink(true);
+	let mut item = Item::default();
+	item.set_title(registro.titulo.clone());
+	item.set_link(registro.link.clone());
+	item.set_description(
+		registro
+			.resumo
+			.clone()
+			.unwrap_or_else(|| registro.descricao.clone()),
+	);
+	item.set_guid(guid);
+	item.set_pub_date(registro.entregue_em.to_rfc2822());
+	item.set_author(registro.fonte.nome.clone());
+
+	let mut itens = vec![item];
+	itens.extend(
+		canal
+			.items()
+			.iter()
+			.filter(|i| i.link() != Some(registro.link.as_str()))
+			.take(config.maximo.saturating_sub(1))
+			.cloned(),
+	);
+	canal.set_items(itens);
+
+	if let Some(pai) = caminho.parent()
+		&& !pai.as_os_str().is_empty()
+	{
+		fs::create_dir_all(pai)?;
+	}
+	let temporario = caminho.with_extension("xml.tmp");
+	canal.write_to(fs::File::create(&temporario)?)?;
+	fs::rename(&temporario, caminho)?;
+	Ok(())
+}
+
+/// Alerta guardado para o calendário até o fim da vigência.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AlertaCalendario {
+	pub titulo: String,
+	pub link: String,
+	pub fonte: String,
+	pub inicio: DateTime<Utc>,
+	pub fim: DateTime<Utc>,
+}
+
+/// Início e fim da vigência informados na descrição do alerta. O INMET publica os horários
+/// em hora de Brasília, que não tem horário de verão desde 2019 (UTC−3); eles são convertidos
+/// para UTC, e o calendário os mostra na hora local de quem o assina.
+pub fn janela_alerta(descricao: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+	let brasilia = FixedOffset::west_opt(3 * 3600)?;
+	let mut inicio = None;
+	let mut fim = None;
+	for caps in JANELA_ALERTA_REGEX_LAZY.captures_iter(descricao) {
+		let data = NaiveDateTime::parse_from_str(&caps[2].replace('T', " "), "%Y-%m-%d %H:%M")
+			.ok()
+			.and_then(|d| brasilia.from_local_datetime(&d).single())
+			.map(|d| d.with_timezone(&Utc));
+		if caps[1].eq_ignore_ascii_case("fim") {
+			fim = fim.or(data);
+		} else {
+			inicio = inicio.or(data);
+		}
+	}
+	match (inicio, fim) {
+		(Some(inicio), Some(fim)) if inicio < fim => Some((inicio, fim)),
+		_ => None,
+	}
+}
+
+pub fn calendario_registrar(
+	db: &Db,
+	chave: &str,
+	registro: &RegistroArquivo,
+	inicio: DateTime<Utc>,
+	fim: DateTime<Utc>,
+) -> Result<(), Box<dyn Error>> {
+	let alerta = AlertaCalendario {
+		titulo: registro.titulo.clone(),
+		link: registro.link.clone(),
+		fonte: registro.fonte.nome.clone(),
+		inicio,
+		fim,
+	};
+	db.open_tree(ALERTAS_TREE)?
+		.insert(chave.as_bytes(), serde_json::to_vec(&alerta)?)?;
+	Ok(())
+}
+
+/// Escapa um texto do iCalendar (RFC 5545).
+pub fn ics_escapar(texto: &str) -> String {
+	texto
+		.replace('\\', "\\\\")
+		.replace(';', "\\;")
+		.replace(',', "\\,")
+		.replace('\n', "\\n")
+}
+
+/// Linha do iCalendar dobrada em 75 bytes, terminada em CRLF.
+pub fn ics_linha(linha: &str) -> String {
+	let mut saida = String::new();
+	let mut tamanho = 0;
+	for c in linha.chars() {
+		if tamanho + c.len_utf8() > 75 {
+			saida.push_str("\r\n ");
+			tamanho = 1;
+		}
+		saida.push(c);
+		tamanho += c.len_utf8();
+	}
+	saida.push_str("\r\n");
+	saida
+}
+
+/// Reescreve o `.ics` com os alertas ainda vigentes e apaga do banco os expirados. Devolve
+/// quantos eventos ficaram no calendário.
+pub fn calendario_gerar(db: &Db, config: &CalendarioConfig) -> Result<usize, Box<dyn Error>> {
+	let tree = db.open_tree(ALERTAS_TREE)?;
+	let agora = Utc::now();
+	let formato = "%Y%m%dT%H%M%SZ";
+	let mut ics = String::new();
+	for linha in [
+		"BEGIN:VCALENDAR",
+		"VERSION:2.0",
+		"PRODID:-//FilterFlow//Alertas//PT",
+		"X-WR-CALNAME:FilterFlow",
+	] {
+		ics.push_str(&ics_linha(linha));
+	}
+
+	let mut total = 0;
+	for entrada in tree.iter() {
+		let (chave, valor) = entrada?;
+		let alerta: AlertaCalendario = match serde_json::from_slice(&valor) {
+			Ok(a) => a,
+			Err(_) => {
+				tree.remove(&chave)?;
+				continue;
+			}
+		};
+		if alerta.fim <= agora {
+			tree.remove(&chave)?;
+			continue;
+		}
+		let uid = format!(
+			"{:016x}@filterflow",
+			hash_fnv1a(&String::from_utf8_lossy(&chave))
+		);
+		for linha in [
+			"BEGIN:VEVENT".to_string(),
+			format!("UID:{}", uid),
+			format!("DTSTAMP:{}", agora.format(formato)),
+			format!("DTSTART:{}", alerta.inicio.format(formato)),
+			format!("DTEND:{}", alerta.fim.format(formato)),
+			format!("SUMMARY:{}", ics_escapar(&alerta.titulo)),
+			format!("DESCRIPTION:{}", ics_escapar(&alerta.fonte)),
+			format!("URL:{}", alerta.link),
+			"END:VEVENT".to_string(),
+		] {
+			ics.push_str(&ics_linha(&linha));
+		}
+		total += 1;
+	}
+	ics.push_str(&ics_linha("END:VCALENDAR"));
+
+	let caminho = Path::new(&config.arquivo);
+	if let Some(pai) = caminho.parent()
+		&& !pai.as_os_str().is_empty()
+	{
+		fs::create_dir_all(pai)?;
+	}
+	fs::write(caminho, ics)?;
+	Ok(total)
+}
+
+/// Grava (ou sobrescreve) o item como nota Markdown em `pasta`, no caminho do layout.
+pub fn exportar_markdown(
+	pasta: &Path,
+	layout: &str,
+	registro: &RegistroArquivo,
+	importancia: Option<u32>,
+) -> Result<PathBuf, io::Error> {
+	let caminho = pasta.join(caminho_exportacao(layout, registro));
+	if let Some(pai) = caminho.parent() {
+		fs::create_dir_all(pai)?;
+	}
+	fs::write(&caminho, markdown_do_registro(registro, importancia))?;
+	Ok(caminho)
+}
+
+// Limite da Bot API é 4096 caracteres por mensagem; a folga cobre a contagem em UTF-16
+pub const TELEGRAM_MAX_MENSAGEM: usize = 4000;
+
+/// Escapa os caracteres reservados do MarkdownV2 do Telegram.
+pub fn telegram_escapar(texto: &str) -> String {
+	let mut escapado = String::with_capacity(texto.len());
+	for c in texto.chars() {
+		if "_*[]()~`>#+-=|{}.!\\".contains(c) {
+			escapado.push('\\');
+		}
+		escapado.push(c);
+	}
+	escapado
+}
+
+/// Escapa a URL dentro de `[texto](url)`, onde só `)` e `\` são reservados.
+pub fn telegram_escapar_url(url: &str) -> String {
+	url.replace('\\', "\\\\").replace(')', "\\)")
+}
+
+/// Divide um texto já escapado em partes de até `limite` caracteres, preferindo quebras de
+/// linha e espaços e nunca separando uma barra de escape do caractere que ela protege.
+pub fn telegram_dividir(texto: &str, limite: usize) -> Vec<String> {
+	let mut partes = Vec::new();
+	let mut resto = texto;
+	while resto.chars().count() > limite {
+		let corte = resto
+			.char_indices()
+			.nth(limite)
+			.map(|(i, _)| i)
+			.unwrap_or(resto.len());
+		let janela = &resto[..corte];
+		let mut fim = janela
+			.rfind('\n')
+			.or_else(|| janela.rfind(' '))
+			.filter(|&i| i > 0)
+			.unwrap_or(corte);
+		// Sem espaço disponível: recua se o corte cair logo após uma barra de escape
+		let barras_finais = resto[..fim]
+			.chars()
+			.rev()
+			.take_while(|c| *c == '\\')
+			.count();
+		if fim == corte && barras_finais % 2 == 1 {
+			fim -= 1;
+		}
+		partes.push(resto[..fim].trim_end().to_string());
+		resto = resto[fim..].trim_start();
+	}
+	if !resto.is_empty() {
+		partes.push(resto.to_string());
+	}
+	partes
+}
+
+/// Envia o item (título com link, fonte e resumo) ao chat configurado, em várias mensagens se
+/// o resumo for longo.
+pub async fn telegram_enviar_item(
+	client: &Client,
+	config: &TelegramConfig,
+	title: &str,
+	link: &str,
+	metadados: &MetadadosFonte,
+	resumo: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+	let mut fonte = metadados.nome.clone();
+	if let Some(dominio) = &metadados.dominio {
+		fonte.push_str(&format!(" ({})", dominio));
+	}
+	let mut texto = format!(
+		"*[{}]({})*\n_{}_",
+		telegram_escapar(title),
+		telegram_escapar_url(link),
+		telegram_escapar(&fonte)
+	);
+	if let Some(resumo) = resumo {
+		texto.push_str("\n\n");
+		texto.push_str(&telegram_escapar(resumo));
+	}
+
+	let url = format!("https://api.telegram.org/bot{}/sendMessage", config.token);
+	for parte in telegram_dividir(&texto, TELEGRAM_MAX_MENSAGEM) {
+		let corpo = serde_json::json!({
+			"chat_id": config.chat_id,
+			"text": parte,
+			"parse_mode": "MarkdownV2",
+			"link_preview_options": { "is_disabled": config.sem_previa },
+		});
+		let response = client
+			.post(&url)
+			.json(&corpo)
+			.timeout(StdDuration::from_secs(20))
+			.send()
+			.await?;
+		if !response.status().is_success() {
+			let status = response.status();
+			let detalhe = response.text().await.unwrap_or_default();
+			return Err(format!("HTTP {}: {}", status, detalhe).into());
+		}
+	}
+	Ok(())
+}
+
+pub fn html_escapar(texto: &str) -> String {
+	texto
+		.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+}
+
+/// Publica o item (título com link, fonte e resumo) na sala configurada. O corpo em texto puro
+/// acompanha o HTML para clientes que não o exibem.
+pub async fn matrix_enviar_item(
+	client: &Client,
+	config: &MatrixConfig,
+	title: &str,
+	link: &str,
+	metadados: &MetadadosFonte,
+	resumo: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+	let mut fonte = metadados.nome.clone();
+	if let Some(dominio) = &metadados.dominio {
+		fonte.push_str(&format!(" ({})", dominio));
+	}
+	let mut texto = format!("{}\n{}\n{}", title, link, fonte);
+	let mut html = format!(
+		"<b><a href=\"{}\">{}</a></b><br><i>{}</i>",
+		html_escapar(link),
+		html_escapar(title),
+		html_escapar(&fonte)
+	);
+	if let Some(resumo) = resumo {
+		texto.push_str("\n\n");
+		texto.push_str(resumo);
+		html.push_str("<br><br>");
+		html.push_str(&html_escapar(resumo).replace('\n', "<br>"));
+	}
+
+	// Id de transação único por mensagem, para o homeserver descartar reenvios
+	let transacao = format!(
+		"filterflow-{}-{:x}",
+		Utc::now().timestamp_micros(),
+		hash_fnv1a(link)
+	);
+	let mut url = Url::parse(&config.homeserver)?;
+	url.path_segments_mut()
+		.map_err(|_| "homeserver do Matrix inválido")?
+		.pop_if_empty()
+		.extend([
+			"_matrix",
+			"client",
+			"v3",
+			"rooms",
+			config.sala.as_str(),
+			"send",
+			"m.room.message",
+			transacao.as_str(),
+		]);
+	let corpo = serde_json::json!({
+		"msgtype": "m.text",
+		"body": texto,
+		"format": "org.matrix.custom.html",
+		"formatted_body": html,
+	});
+	let response = client
+		.put(url)
+		.bearer_auth(&config.token)
+		.json(&corpo)
+		.timeout(StdDuration::from_secs(20))
+		.send()
+		.await?;
+	if !response.status().is_success() {
+		let status = response.status();
+		let detalhe = response.text().await.unwrap_or_default();
+		return Err(format!("HTTP {}: {}", status, detalhe).into());
+	}
+	Ok(())
+}
+
+// Limite do campo de texto de um bloco "section" do Block Kit
+pub const SLACK_MAX_TEXTO: usize = 3000;
+
+/// Escapa os caracteres de controle do mrkdwn do Slack.
+pub fn slack_escapar(texto: &str) -> String {
+	texto
+		.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+}
+
+/// Publica o item no canal do grupo da fonte (ou no padrão), com título, fonte e resumo em
+/// blocos separados.
+pub async fn slack_enviar_item(
+	client: &Client,
+	config: &SlackConfig,
+	title: &str,
+	link: &str,
+	metadados: &MetadadosFonte,
+	resumo: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+	let mut fonte = slack_escapar(&metadados.nome);
+	if let Some(dominio) = &metadados.dominio {
+		fonte.push_str(&format!(" ({})", slack_escapar(dominio)));
+	}
+	if let Some(grupo) = &metadados.grupo {
+		fonte.push_str(&format!(" · {}", slack_escapar(grupo)));
+	}
+
+	let mut blocos = vec![
+		serde_json::json!({
+			"type": "section",
+			"text": {
+				"type": "mrkdwn",
+				"text": format!("*<{}|{}>*", link.replace('|', "%7C"), slack_escapar(title)),
+			},
+		}),
+		serde_json::json!({
+			"type": "context",
+			"elements": [{ "type": "mrkdwn", "text": fonte }],
+		}),
+	];
+	if let Some(resumo) = resumo {
+		let mut texto = slack_escapar(resumo);
+		if texto.chars().count() > SLACK_MAX_TEXTO {
+			texto = texto.chars().take(SLACK_MAX_TEXTO - 1).collect::<String>() + "…";
+		}
+		blocos.push(serde_json::json!({
+			"type": "section",
+			"text": { "type": "mrkdwn", "text": texto },
+		}));
+	}
+	let corpo = serde_json::json!({
+		// Texto puro das notificações e dos clientes sem Block Kit
+		"text": format!("{} — {}", title, link),
+		"blocks": blocos,
+		"unfurl_links": false,
+	});
+
+	let response = client
+		.post(config.webhook_para(metadados.grupo.as_deref()))
+		.json(&corpo)
+		.timeout(StdDuration::from_secs(20))
+		.send()
+		.await?;
+	if !response.status().is_success() {
+		let status = response.status();
+		let detalhe = response.text().await.unwrap_or_default();
+		return Err(format!("HTTP {}: {}", status, detalhe).into());
+	}
+	Ok(())
+}
+
+/// Publica o item no tópico ntfy: título na notificação, resumo no corpo e link no clique.
+pub async fn ntfy_enviar_item(
+	client: &Client,
+	config: &NtfyConfig,
+	title: &str,
+	link: &str,
+	metadados: &MetadadosFonte,
+	resumo: Option<&str>,
+	prioridade_fonte: i32,
+) -> Result<(), Box<dyn Error>> {
+	let mut texto = metadados.nome.clone();
+	if let Some(dominio) = &metadados.dominio {
+		texto.push_str(&format!(" ({})", dominio));
+	}
+	if let Some(resumo) = resumo {
+		texto.push_str("\n\n");
+		texto.push_str(resumo.trim());
+	}
+	let mut corpo = serde_json::json!({
+		"topic": config.topico,
+		"title": title,
+		"message": texto,
+		"click": link,
+		"priority": prioridade_por_faixa(&config.prioridades, prioridade_fonte, config.prioridade_padrao),
+	});
+	if let Some(grupo) = &metadados.grupo {
+		corpo["tags"] = serde_json::json!([grupo]);
+	}
+
+	// A API JSON é publicada na raiz do servidor, com o tópico no corpo
+	let mut request = client
+		.post(config.servidor.trim_end_matches('/'))
+		.json(&corpo)
+		.timeout(StdDuration::from_secs(20));
+	if let Some(token) = &config.token {
+		request = request.bearer_auth(token);
+	}
+	let response = request.send().await?;
+	if !response.status().is_success() {
+		let status = response.status();
+		let detalhe = response.text().await.unwrap_or_default();
+		return Err(format!("HTTP {}: {}", status, detalhe).into());
+	}
+	Ok(())
+}
+
+/// Publica o item no Gotify, com o link e o resumo em Markdown e o clique abrindo o link.
+pub async fn gotify_enviar_item(
+	client: &Client,
+	config: &GotifyConfig,
+	title: &str,
+	link: &str,
+	metadados: &MetadadosFonte,
+	resumo: Option<&str>,
+	prioridade_fonte: i32,
+) -> Result<(), Box<dyn Error>> {
+	let mut texto = format!(
+		"[{}]({})\n\n_{}_",
+		markdown_escapar_titulo(title),
+		link,
+		metadados.nome
+	);
+	if let Some(dominio) = &metadados.dominio {
+		texto.push_str(&format!(" _({})_", dominio));
+	}
+	if let Some(resumo) = resumo {
+		texto.push_str("\n\n");
+		texto.push_str(resumo.trim());
+	}
+	let corpo = serde_json::json!({
+		"title": title,
+		"message": texto,
+		"priority": prioridade_por_faixa(&config.prioridades, prioridade_fonte, config.prioridade_padrao),
+		"extras": {
+			"client::display": { "contentType": "text/markdown" },
+			"client::notification": { "click": { "url": link } },
+		},
+	});
+
+	let response = client
+		.post(format!("{}/message", config.servidor.trim_end_matches('/')))
+		.header("X-Gotify-Key", &config.token)
+		.json(&corpo)
+		.timeout(StdDuration::from_secs(20))
+		.send()
+		.await?;
+	if !response.status().is_success() {
+		let status = response.status();
+		let detalhe = response.text().await.unwrap_or_default();
+		return Err(format!("HTTP {}: {}", status, detalhe).into());
+	}
+	Ok(())
+}
+
+/// Mostra a notificação nativa do item, respeitando o limite de `maximo` por janela. As
+/// omitidas são contadas e avisadas na primeira notificação mostrada depois que a janela libera.
+pub async fn desktop_notificar_item(
+	config: &DesktopConfig,
+	title: &str,
+	metadados: &MetadadosFonte,
+	resumo: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+	let omitidas = {
+		let mut estado = NOTIFICACOES_DESKTOP.lock().unwrap();
+		let (mostradas, omitidas) = &mut *estado;
+		let janela = StdDuration::from_secs(config.janela_minutos.max(1) * 60);
+		while mostradas.front().is_some_and(|t| t.elapsed() >= janela) {
+			mostradas.pop_front();
+		}
+		if mostradas.len() >= config.maximo {
+			if *omitidas == 0 {
+				info!(
+					"Limite de {} notificações em {} min atingido; as próximas serão omitidas.",
+					config.maximo, config.janela_minutos
+				);
+			}
+			*omitidas += 1;
+			return Ok(());
+		}
+		mostradas.push_back(Instant::now());
+		std::mem::take(omitidas)
+	};
+
+	let mut corpo = resumo
+		.and_then(|r| r.lines().map(str::trim).find(|l| !l.is_empty()))
+		.map(|l| format!("{}\n", l))
+		.unwrap_or_default();
+	corpo.push_str(&metadados.nome);
+	if omitidas > 0 {
+		corpo.push_str(&format!(
+			"\n(+{} itens relevantes omitidos; veja o terminal)",
+			omitidas
+		));
+	}
+	let titulo = title.to_string();
+	tokio::task::spawn_blocking(move || {
+		notify_rust::Notification::new()
+			.appname("FilterFlow")
+			.summary(&titulo)
+			.body(&corpo)
+			.show()
+			.map(|_| ())
+	})
+	.await??;
+	Ok(())
+}
+
+/// Tags fixas da saída mais o grupo da fonte, sem repetições.
+pub fn tags_do_registro(fixas: &[String], registro: &RegistroArquivo) -> Vec<String> {
+	let mut tags = fixas.to_vec();
+	if let Some(grupo) = &registro.fonte.grupo
+		&& !tags.contains(grupo)
+	{
+		tags.push(grupo.clone());
+	}
+	tags
+}
+
+/// Token OAuth do Wallabag, pedido de novo (com usuário e senha) só quando o anterior expira.
+pub async fn wallabag_token(
+	client: &Client,
+	config: &WallabagConfig,
+) -> Result<String, Box<dyn Error>> {
+	if let Some((token, expira)) = TOKEN_WALLABAG.lock().unwrap().as_ref()
+		&& Instant::now() < *expira
+	{
+		return Ok(token.clone());
+	}
+
+	#[derive(Deserialize)]
+	struct RespostaToken {
+		access_token: String,
+		expires_in: u64,
+	}
+	let response = client
+		.post(format!(
+			"{}/oauth/v2/token",
+			config.servidor.trim_end_matches('/')
+		))
+		.form(&[
+			("grant_type", "password"),
+			("client_id", &config.client_id),
+			("client_secret", &config.client_secret),
+			("username", &config.usuario),
+			("password", &config.senha),
+		])
+		.timeout(StdDuration::from_secs(20))
+		.send()
+		.await?;
+	if !response.status().is_success() {
+		let status = response.status();
+		let detalhe = response.text().await.unwrap_or_default();
+		return Err(format!("HTTP {} ao obter o token: {}", status, detalhe).into());
+	}
+	let resposta: RespostaToken = response.json().await?;
+	// Margem de um minuto para não usar um token prestes a expirar
+	let expira = Instant::now() + StdDuration::from_secs(resposta.expires_in.saturating_sub(60));
+	*TOKEN_WALLABAG.lock().unwrap() = Some((resposta.access_token.clone(), expira));
+	Ok(resposta.access_token)
+}
+
+pub async fn wallabag_salvar_item(
+	client: &Client,
+	config: &WallabagConfig,
+	registro: &RegistroArquivo,
+) -> Result<(), Box<dyn Error>> {
+	let token = wallabag_token(client, config).await?;
+	let corpo = serde_json::json!({
+		"url": registro.link,
+		"title": registro.titulo,
+		"tags": tags_do_registro(&config.tags, registro).join(","),
+	});
+	let response = client
+		.post(format!(
+			"{}/api/entries.json",
+			config.servidor.trim_end_matches('/')
+		))
+		.bearer_auth(token)
+		.json(&corpo)
+		.timeout(StdDuration::from_secs(30))
+		.send()
+		.await?;
+	if !response.status().is_success() {
+		// Sem o token em cache, a próxima tentativa autentica de novo
+		if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+			*TOKEN_WALLABAG.lock().unwrap() = None;
+		}
+		let status = response.status();
+		let detalhe = response.text().await.unwrap_or_default();
+		return Err(format!("HTTP {}: {}", status, detalhe).into());
+	}
+	Ok(())
+}
+
+pub async fn readeck_salvar_item(
+	client: &Client,
+	config: &ReadeckConfig,
+	registro: &RegistroArquivo,
+) -> Result<(), Box<dyn Error>> {
+	let corpo = serde_json::json!({
+		"url": registro.link,
+		"title": registro.titulo,
+		"labels": tags_do_registro(&config.rotulos, registro),
+	});
+	let response = client
+		.post(format!(
+			"{}/api/bookmarks",
+			config.servidor.trim_end_matches('/')
+		))
+		.bearer_auth(&config.token)
+		.json(&corpo)
+		.timeout(StdDuration::from_secs(30))
+		.send()
+		.await?;
+	if !response.status().is_success() {
+		let status = response.status();
+		let detalhe = response.text().await.unwrap_or_default();
+		return Err(format!("HTTP {}: {}", status, detalhe).into());
+	}
+	Ok(())
+}
+
+/// Resumo do item (ou a descrição, se não houve resumo), usado como descrição do favorito.
+pub fn descricao_favorito(registro: &RegistroArquivo) -> &str {
+	registro
+		.resumo
+		.as_deref()
+		.unwrap_or(&registro.descricao)
+		.trim()
+}
+
+pub async fn linkding_salvar_item(
+	client: &Client,
+	config: &LinkdingConfig,
+	registro: &RegistroArquivo,
+) -> Result<(), Box<dyn Error>> {
+	let corpo = serde_json::json!({
+		"url": registro.link,
+		"title": registro.titulo,
+		"description": descricao_favorito(registro),
+		"tag_names": tags_do_registro(&config.tags, registro)
+			.iter()
+			.map(|t| slug(t))
+			.collect::<Vec<_>>(),
+	});
+	let response = client
+		.post(format!(
+			"{}/api/bookmarks/",
+			config.servidor.trim_end_matches('/')
+		))
+		.header("Authorization", format!("Token {}", config.token))
+		.json(&corpo)
+		.timeout(StdDuration::from_secs(30))
+		.send()
+		.await?;
+	if !response.status().is_success() {
+		let status = response.status();
+		let detalhe = response.text().await.unwrap_or_default();
+		return Err(format!("HTTP {}: {}", status, detalhe).into());
+	}
+	Ok(())
+}
+
+/// Token de sessão do Shiori (API v1), obtido com usuário e senha na primeira entrega.
+pub async fn shiori_token(
+	client: &Client,
+	config: &ShioriConfig,
+) -> Result<String, Box<dyn Error>> {
+	if let Some(token) = TOKEN_SHIORI.lock().unwrap().as_ref() {
+		return Ok(token.clone());
+	}
+
+	#[derive(Deserialize)]
+	struct Sessao {
+		token: String,
+	}
+	#[derive(Deserialize)]
+	struct RespostaLogin {
+		message: Sessao,
+	}
+	let response = client
+		.post(format!(
+			"{}/api/v1/auth/login",
+			config.servidor.trim_end_matches('/')
+		))
+		.json(&serde_json::json!({
+			"username": config.usuario,
+			"password": config.senha,
+			"remember_me": true,
+		}))
+		.timeout(StdDuration::from_secs(20))
+		.send()
+		.await?;
+	if !response.status().is_success() {
+		let status = response.status();
+		let detalhe = response.text().await.unwrap_or_default();
+		return Err(format!("HTTP {} ao entrar no Shiori: {}", status, detalhe).into());
+	}
+	let token = response.json::<RespostaLogin>().await?.message.token;
+	*TOKEN_SHIORI.lock().unwrap() = Some(token.clone());
+	Ok(token)
+}
+
+pub async fn shiori_salvar_item(
+	client: &Client,
+	config: &ShioriConfig,
+	registro: &RegistroArquivo,
+) -> Result<(), Box<dyn Error>> {
+	let token = shiori_token(client, config).await?;
+	let tags: Vec<serde_json::Value> = tags_do_registro(&config.tags, registro)
+		.into_iter()
+		.map(|t| serde_json::json!({ "name": t }))
+		.collect();
+	let corpo = serde_json::json!({
+		"url": registro.link,
+		"title": registro.titulo,
+		"excerpt": descricao_favorito(registro),
+		"tags": tags,
+		"createArchive": false,
+	});
+	let response = client
+		.post(format!(
+			"{}/api/v1/bookmarks",
+			config.servidor.trim_end_matches('/')
+		))
+		.bearer_auth(token)
+		.json(&corpo)
+		.timeout(StdDuration::from_secs(30))
+		.send()
+		.await?;
+	if !response.status().is_success() {
+		// Sessão expirada: a próxima tentativa entra de novo
+		if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+			*TOKEN_SHIORI.lock().unwrap() = None;
+		}
+		let status = response.status();
+		let detalhe = response.text().await.unwrap_or_default();
+		return Err(format!("HTTP {}: {}", status, detalhe).into());
+	}
+	Ok(())
+}
+
+/// Documento JSON do item enviado pelo webhook e pelo MQTT.
+pub fn registro_json(registro: &RegistroArquivo) -> serde_json::Value {
+	let tags: Vec<&str> = registro.fonte.grupo.iter().map(String::as_str).collect();
+	serde_json::json!({
+		"fonte": registro.fonte,
+		"link": registro.link,
+		"titulo": registro.titulo,
+		"descricao": registro.descricao,
+		"resumo": registro.resumo,
+		"tags": tags,
+		"publicado": registro.publicado,
+		"entregue_em": registro.entregue_em,
+	})
+}
+
+/// Publica o item no tópico configurado, com a prioridade da fonte no documento para que as
+/// automações possam separar alertas. Abre uma conexão por item e espera a confirmação do QoS.
+pub async fn mqtt_publicar_item(
+	config: &MqttConfig,
+	registro: &RegistroArquivo,
+	prioridade_fonte: i32,
+) -> Result<(), Box<dyn Error>> {
+	use rumqttc::{AsyncClient, Event, MqttOptions, Outgoing, Packet, QoS};
+
+	let qos = match config.qos {
+		0 => QoS::AtMostOnce,
+		1 => QoS::AtLeastOnce,
+		_ => QoS::ExactlyOnce,
+	};
+	let id = config
+		.id_cliente
+		.clone()
+		.unwrap_or_else(|| format!("filterflow-{}", std::process::id()));
+	let mut opcoes = MqttOptions::new(id, &config.servidor, config.porta);
+	opcoes.set_keep_alive(StdDuration::from_secs(30));
+	if let Some(usuario) = &config.usuario {
+		opcoes.set_credentials(usuario, config.senha.clone().unwrap_or_default());
+	}
+
+	let mut documento = registro_json(registro);
+	documento["prioridade"] = prioridade_fonte.into();
+	let (cliente, mut eventos) = AsyncClient::new(opcoes, 10);
+	cliente
+		.publish(
+			&config.topico,
+			qos,
+			config.retida,
+			serde_json::to_vec(&documento)?,
+		)
+		.await?;
+
+	time::timeout(StdDuration::from_secs(20), async {
+		loop {
+			match eventos.poll().await? {
+				Event::Outgoing(Outgoing::Publish(_)) if qos == QoS::AtMostOnce => break,
+				Event::Incoming(Packet::PubAck(_)) if qos == QoS::AtLeastOnce => break,
+				Event::Incoming(Packet::PubComp(_)) => break,
+				_ => {}
+			}
+		}
+		Ok::<_, rumqttc::ConnectionError>(())
+	})
+	.await
+	.map_err(|_| "tempo esgotado esperando o broker")??;
+
+	// Encerramento educado; falhas aqui não afetam a entrega já confirmada
+	if cliente.disconnect().await.is_ok() {
+		let _ = time::timeout(StdDuration::from_secs(5), async {
+			while let Ok(evento) = eventos.poll().await {
+				if matches!(evento, Event::Outgoing(Outgoing::Disconnect)) {
+					break;
+				}
+			}
+		})
+		.await;
+	}
+	Ok(())
+}
+
+/// Envia o item como JSON (fonte, link, título, resumo, tags e datas), assinado se houver
+/// `segredo`: o receptor confere `sha256=<hex>` com o HMAC-SHA256 do corpo.
+pub async fn webhook_enviar_item(
+	client: &Client,
+	config: &WebhookConfig,
+	registro: &RegistroArquivo,
+) -> Result<(), Box<dyn Error>> {
+	let corpo = serde_json::to_vec(&registro_json(registro))?;
+
+	let mut request = client
+		.post(&config.url)
+		.header("Content-Type", "application/json")
+		.timeout(StdDuration::from_secs(20));
+	if let Some(segredo) = &config.segredo {
+		let chave = hmac::Key::new(hmac::HMAC_SHA256, segredo.as_bytes());
+		let assinatura: String = hmac::sign(&chave, &corpo)
+			.as_ref()
+			.iter()
+			.map(|b| format!("{:02x}", b))
+			.collect();
+		request = request.header("X-FilterFlow-Assinatura", format!("sha256={}", assinatura));
+	}
+	let response = request.body(corpo).send().await?;
+	if !response.status().is_success() {
+		let status = response.status();
+		let detalhe = response.text().await.unwrap_or_default();
+		return Err(format!("HTTP {}: {}", status, detalhe).into());
+	}
+	Ok(())
+}
+
+/// Roda `comando` com `entrada` no stdin e espera que termine em até `tempo_limite`; ao
+/// estourar, o processo é encerrado. Um programa que não lê o stdin fecha o pipe, o que não é
+/// falha: vale o código de saída, que quem chama confere. `rotulo` ("comando", "script") nomeia
+/// o programa nas mensagens de erro.
+pub async fn executar_programa(
+	comando: &[String],
+	ambiente: &[(&str, String)],
+	entrada: &[u8],
+	tempo_limite: StdDuration,
+	stdout: Stdio,
+	rotulo: &str,
+) -> Result<Output, Box<dyn Error>> {
+	let (programa, argumentos) = comando
+		.split_first()
+		.ok_or_else(|| format!("{} sem programa", rotulo))?;
+	let mut processo = Command::new(programa)
+		.args(argumentos)
+		.envs(ambiente.iter().cloned())
+		.stdin(Stdio::piped())
+		.stdout(stdout)
+		.stderr(Stdio::piped())
+		.kill_on_drop(true)
+		.spawn()
+		.map_err(|e| format!("não foi possível executar '{}': {}", programa, e))?;
+	let mut pipe = processo
+		.stdin
+		.take()
+		.ok_or_else(|| format!("stdin do {} indisponível", rotulo))?;
+	let saida = time::timeout(tempo_limite, async move {
+		if let Err(e) = pipe.write_all(entrada).await
+			&& e.kind() != io::ErrorKind::BrokenPipe
+		{
+			return Err(e);
+		}
+		drop(pipe);
+		processo.wait_with_output().await
+	})
+	.await
+	.map_err(|_| format!("o {} passou de {} s", rotulo, tempo_limite.as_secs()))??;
+	Ok(saida)
+}
+
+/// Roda o comando com o documento do webhook (mais `prioridade` e `importancia`) no stdin e
+/// os campos principais em variáveis `FILTERFLOW_*`, para comandos que não leem JSON. O stdout
+/// é descartado; um código de saída diferente de zero é falha, com o stderr na mensagem.
+pub async fn comando_executar_item(
+	config: &ComandoConfig,
+	item: &ItemEntregue<'_>,
+) -> Result<(), Box<dyn Error>> {
+	let registro = item.registro;
+	let mut documento = registro_json(registro);
+	documento["prioridade"] = item.prioridade.into();
+	documento["importancia"] = item.importancia.into();
+
+	let ambiente = [
+		("FILTERFLOW_TITULO", registro.titulo.clone()),
+		("FILTERFLOW_LINK", registro.link.clone()),
+		("FILTERFLOW_FONTE", registro.fonte.nome.clone()),
+		(
+			"FILTERFLOW_GRUPO",
+			registro.fonte.grupo.clone().unwrap_or_default(),
+		),
+		(
+			"FILTERFLOW_RESUMO",
+			registro.resumo.clone().unwrap_or_default(),
+		),
+		("FILTERFLOW_PRIORIDADE", item.prioridade.to_string()),
+		("FILTERFLOW_IMPORTANCIA", item.importancia.to_string()),
+	];
+	let saida = executar_programa(
+		&config.comando,
+		&ambiente,
+		documento.to_string().as_bytes(),
+		StdDuration::from_secs(config.tempo_limite_segundos),
+		Stdio::null(),
+		"comando",
+	)
+	.await?;
+
+	if !saida.status.success() {
+		let erros = String::from_utf8_lossy(&saida.stderr);
+		return Err(format!("o comando terminou com {}: {}", saida.status, erros.trim()).into());
+	}
+	Ok(())
+}
+
+/// Corpo HTML do e-mail com os itens, na ordem em que foram entregues.
+pub fn email_html(registros: &[RegistroArquivo]) -> String {
+	let mut html =
+		String::from("<html><body style=\"font-family: sans-serif\"><h2>FilterFlow</h2>\n");
+	for registro in registros {
+		let mut fonte = html_escapar(&registro.fonte.nome);
+		if let Some(dominio) = &registro.fonte.dominio {
+			fonte.push_str(&format!(" ({})", html_escapar(dominio)));
+		}
+		html.push_str(&format!(
+			"<p><b><a href=\"{}\">{}</a></b><br><i>{}</i>",
+			html_escapar(&registro.link),
+			html_escapar(&registro.titulo),
+			fonte
+		));
+		if let Some(resumo) = &registro.resumo {
+			html.push_str("<br>");
+			html.push_str(&html_escapar(resumo.trim()).replace('\n', "<br>"));
+		}
+		html.push_str("</p>\n");
+	}
+	html.push_str("</body></html>\n");
+	html
+}
+
+/// Envia o item na hora se a fonte for prioritária; senão o guarda para o próximo resumo.
+pub async fn email_registrar_item(
+	db: &Db,
+	config: &EmailConfig,
+	registro: &RegistroArquivo,
+	prioridade: i32,
+	cifra: Option<&CifraArquivo>,
+) -> Result<(), Box<dyn Error>> {
+	if config.prioridade_imediata.is_some_and(|p| prioridade >= p) {
+		let assunto = format!("[FilterFlow] {}", registro.titulo);
+		return smtp_enviar(
+			config,
+			&assunto,
+			&email_html(std::slice::from_ref(registro)),
+		)
+		.await;
+	}
+	let mut registro = registro.clone();
+	if let Some(cifra) = cifra {
+		registro.selar(cifra)?;
+	}
+	let tree = db.open_tree(FILA_EMAIL_TREE)?;
+	tree.insert(db_novo_id(db, &tree)?, serde_json::to_vec(&registro)?)?;
+	tree.flush()?;
+	Ok(())
+}
+
+/// Envia o resumo com os itens enfileirados e os retira da fila. Devolve quantos foram enviados.
+pub async fn email_enviar_resumo(
+	db: &Db,
+	config: &EmailConfig,
+	cifra: Option<&CifraArquivo>,
+) -> Result<usize, Box<dyn Error>> {
+	let tree = db.open_tree(FILA_EMAIL_TREE)?;
+	let mut chaves = Vec::new();
+	let mut registros = Vec::new();
+	for entrada in tree.iter() {
+		let (chave, valor) = entrada?;
+		registros.push(db_ler_arquivo(&valor, cifra)?);
+		chaves.push(chave);
+	}
+	if registros.is_empty() {
+		return Ok(0);
+	}
+
+	let assunto = format!(
+		"[FilterFlow] {} notícias — {}",
+		registros.len(),
+		Local::now().format("%d/%m/%Y %H:%M")
+	);
+	smtp_enviar(config, &assunto, &email_html(&registros)).await?;
+	for chave in chaves {
+		tree.remove(chave)?;
+	}
+	tree.flush()?;
+	Ok(registros.len())
+}
+
+/// Lê uma resposta SMTP (possivelmente em várias linhas "250-...") e confere o código.
+pub async fn smtp_resposta<S: AsyncRead + AsyncWrite + Unpin>(
+	conexao: &mut AsyncBufReader<S>,
+	esperado: u16,
+) -> Result<(), Box<dyn Error>> {
+	loop {
+		let mut linha = String::new();
+		if conexao.read_line(&mut linha).await? == 0 {
+			return Err("O servidor SMTP fechou a conexão.".into());
+		}
+		let codigo: u16 = linha.get(..3).and_then(|c| c.parse().ok()).unwrap_or(0);
+		if codigo != esperado {
+			return Err(format!("Resposta SMTP inesperada: {}", linha.trim_end()).into());
+		}
+		// "250-" continua a resposta; "250 " a encerra
+		if linha.as_bytes().get(3) != Some(&b'-') {
+			return Ok(());
+		}
+	}
+}
+
+pub async fn smtp_comando<S: AsyncRead + AsyncWrite + Unpin>(
+	conexao: &mut AsyncBufReader<S>,
+	comando: &str,
+	esperado: u16,
+) -> Result<(), Box<dyn Error>> {
+	conexao
+		.get_mut()
+		.write_all(format!("{}\r\n", comando).as_bytes())
+		.await?;
+	smtp_resposta(conexao, esperado).await
+}
+
+/// Parte da conversa SMTP após a saudação (e o TLS, se houver): autenticação e envio.
+pub async fn smtp_sessao<S: AsyncRead + AsyncWrite + Unpin>(
+	conexao: &mut AsyncBufReader<S>,
+	config: &EmailConfig,
+	mensagem: &str,
+) -> Result<(), Box<dyn Error>> {
+	smtp_comando(conexao, "EHLO filterflow", 250).await?;
+	if let (Some(usuario), Some(senha)) = (&config.usuario, &config.senha) {
+		let credenciais = BASE64.encode(format!("\0{}\0{}", usuario, senha));
+		smtp_comando(conexao, &format!("AUTH PLAIN {}", credenciais), 235).await?;
+	}
+	smtp_comando(conexao, &format!("MAIL FROM:<{}>", config.de), 250).await?;
+	for destinatario in &config.para {
+		smtp_comando(conexao, &format!("RCPT TO:<{}>", destinatario), 250).await?;
+	}
+	smtp_comando(conexao, "DATA", 354).await?;
+	conexao.get_mut().write_all(mensagem.as_bytes()).await?;
+	smtp_comando(conexao, ".", 250).await?;
+	// Falhas ao encerrar não desfazem o envio
+	let _ = smtp_comando(conexao, "QUIT", 221).await;
+	Ok(())
+}
+
+/// Envia uma mensagem HTML pelo servidor SMTP configurado.
+pub async fn smtp_enviar(
+	config: &EmailConfig,
+	assunto: &str,
+	html: &str,
+) -> Result<(), Box<dyn Error>> {
+	// Corpo em base64 (linhas de 76 caracteres), que dispensa o escape de "." no DATA
+	let corpo = BASE64.encode(html);
+	let corpo: Vec<&str> = corpo
+		.as_bytes()
+		.chunks(76)
+		.map(|c| std::str::from_utf8(c).unwrap_or_default())
+		.collect();
+	let mensagem = format!(
+		"From: {}\r\nTo: {}\r\nSubject: =?UTF-8?B?{}?=\r\nDate: {}\r\nMIME-Version: 1.0\r\n\
+		 Content-Type: text/html; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+		config.de,
+		config.para.join(", "),
+		BASE64.encode(assunto),
+		Local::now().to_rfc2822(),
+		corpo.join("\r\n")
+	);
+
+	let envio = async {
+		let tcp = TcpStream::connect((config.servidor.as_str(), config.porta)).await?;
+		let tls = TlsConnector::from(native_tls::TlsConnector::new()?);
+		match config.seguranca {
+			SegurancaSmtp::Implicita => {
+				let mut conexao = AsyncBufReader::new(tls.connect(&config.servidor, tcp).await?);
+				smtp_resposta(&mut conexao, 220).await?;
+				smtp_sessao(&mut conexao, config, &mensagem).await
+			}
+			SegurancaSmtp::Starttls => {
+				let mut conexao = AsyncBufReader::new(tcp);
+				smtp_resposta(&mut conexao, 220).await?;
+				smtp_comando(&mut conexao, "EHLO filterflow", 250).await?;
+				smtp_comando(&mut conexao, "STARTTLS", 220).await?;
+				let tcp = conexao.into_inner();
+				let mut conexao = AsyncBufReader::new(tls.connect(&config.servidor, tcp).await?);
+				smtp_sessao(&mut conexao, config, &mensagem).await
+			}
+			SegurancaSmtp::Nenhuma => {
+				let mut conexao = AsyncBufReader::new(tcp);
+				smtp_resposta(&mut conexao, 220).await?;
+				smtp_sessao(&mut conexao, config, &mensagem).await
+			}
+		}
+	};
+	time::timeout(StdDuration::from_secs(60), envio)
+		.await
+		.map_err(|_| "Tempo esgotado no envio SMTP.")?
+}
+
+/// Escapa texto para conteúdo ou atributo (entre aspas simples) de XML.
+pub fn xml_escapar(texto: &str) -> String {
+	html_escapar(texto).replace('\'', "&apos;")
+}
+
+pub async fn xmpp_escrever<S: AsyncWrite + Unpin>(
+	conexao: &mut S,
+	texto: &str,
+) -> Result<(), Box<dyn Error>> {
+	conexao.write_all(texto.as_bytes()).await?;
+	conexao.flush().await?;
+	Ok(())
+}
+
+/// Lê do servidor até receber um dos `marcadores`. Erros de fluxo e de SASL viram `Err`.
+pub async fn xmpp_ler_ate<S: AsyncRead + Unpin>(
+	conexao: &mut S,
+	marcadores: &[&str],
+) -> Result<String, Box<dyn Error>> {
+	let mut recebido = Vec::new();
+	let mut buffer = [0u8; 4096];
+	loop {
+		let lidos = conexao.read(&mut buffer).await?;
+		if lidos == 0 {
+			return Err("Conexão XMPP encerrada pelo servidor.".into());
+		}
+		recebido.extend_from_slice(&buffer[..lidos]);
+		let texto = String::from_utf8_lossy(&recebido);
+		if texto.contains("<stream:error") || texto.contains("<failure") {
+			return Err(format!("Servidor XMPP recusou: {}", texto).into());
+		}
+		if marcadores.iter().any(|m| texto.contains(m)) {
+			return Ok(texto.into_owned());
+		}
+	}
+}
+
+/// Abre (ou reabre, após TLS e SASL) o fluxo XML e devolve os recursos anunciados.
+pub async fn xmpp_abrir_fluxo<S: AsyncRead + AsyncWrite + Unpin>(
+	conexao: &mut S,
+	dominio: &str,
+) -> Result<String, Box<dyn Error>> {
+	xmpp_escrever(
+		conexao,
+		&format!(
+			"<?xml version='1.0'?><stream:stream to='{}' version='1.0' xmlns='jabber:client' \
+			 xmlns:stream='http://etherx.jabber.org/streams'>",
+			xml_escapar(dominio)
+		),
+	)
+	.await?;
+	xmpp_ler_ate(conexao, &["</stream:features>", "<stream:features/>"]).await
+}
+
+/// Cliente XMPP mínimo (STARTTLS obrigatório, SASL PLAIN e bind), uma conexão por item, como
+/// no envio de e-mail.
+pub async fn xmpp_enviar(config: &XmppConfig, texto: &str) -> Result<(), Box<dyn Error>> {
+	let (usuario, dominio) = config
+		.jid
+		.split('/')
+		.next()
+		.and_then(|j| j.split_once('@'))
+		.ok_or("[saida.xmpp].jid deve ter a forma usuario@dominio.")?;
+
+	let envio = async {
+		let servidor = config.servidor.as_deref().unwrap_or(dominio);
+		let mut tcp = TcpStream::connect((servidor, config.porta)).await?;
+		let recursos = xmpp_abrir_fluxo(&mut tcp, dominio).await?;
+		if !recursos.contains("urn:ietf:params:xml:ns:xmpp-tls") {
+			return Err("O servidor XMPP não oferece STARTTLS.".into());
+		}
+		xmpp_escrever(
+			&mut tcp,
+			"<starttls xmlns='urn:ietf:params:xml:ns:xmpp-tls'/>",
+		)
+		.await?;
+		xmpp_ler_ate(&mut tcp, &["<proceed"]).await?;
+
+		// O certificado do serviço é emitido para o domínio do JID
+		let tls = TlsConnector::from(native_tls::TlsConnector::new()?);
+		let mut conexao = tls.connect(dominio, tcp).await?;
+		xmpp_abrir_fluxo(&mut conexao, dominio).await?;
+		let credenciais = BASE64.encode(format!("\0{}\0{}", usuario, config.senha));
+		xmpp_escrever(
+			&mut conexao,
+			&format!(
+				"<auth xmlns='urn:ietf:params:xml:ns:xmpp-sasl' mechanism='PLAIN'>{}</auth>",
+				credenciais
+			),
+		)
+		.await?;
+		xmpp_ler_ate(&mut conexao, &["<success"]).await?;
+
+		xmpp_abrir_fluxo(&mut conexao, dominio).await?;
+		xmpp_escrever(
+			&mut conexao,
+			"<iq type='set' id='bind'><bind xmlns='urn:ietf:params:xml:ns:xmpp-bind'>\
+			 <resource>filterflow</resource></bind></iq>",
+		)
+		.await?;
+		let resposta = xmpp_ler_ate(&mut conexao, &["</iq>"]).await?;
+		if resposta.contains("type='error'") || resposta.contains("type=\"error\"") {
+			return Err(format!("Falha no bind XMPP: {}", resposta).into());
+		}
+
+		let para = xml_escapar(&config.para);
+		let tipo = if config.sala {
+			// Entra na sala sem histórico e espera a própria presença (status 110)
+			xmpp_escrever(
+				&mut conexao,
+				&format!(
+					"<presence to='{}/{}'><x xmlns='http://jabber.org/protocol/muc'>\
+					 <history maxstanzas='0'/></x></presence>",
+					para,
+					xml_escapar(&config.apelido)
+				),
+			)
+			.await?;
+			xmpp_ler_ate(&mut conexao, &["code='110'", "code=\"110\""]).await?;
+			"groupchat"
+		} else {
+			"chat"
+		};
+		xmpp_escrever(
+			&mut conexao,
+			&format!(
+				"<message to='{}' type='{}'><body>{}</body></message></stream:stream>",
+				para,
+				tipo,
+				xml_escapar(texto)
+			),
+		)
+		.await?;
+		let _ = conexao.shutdown().await;
+		Ok(())
+	};
+	time::timeout(StdDuration::from_secs(60), envio)
+		.await
+		.map_err(|_| "Tempo esgotado no envio XMPP.")?
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn utc(ano: i32, mes: u32, dia: u32, hora: u32, minuto: u32) -> DateTime<Utc> {
+		Utc.with_ymd_and_hms(ano, mes, dia, hora, minuto, 0)
+			.unwrap()
+	}
+
+	#[test]
+	fn janela_alerta_converte_hora_de_brasilia() {
+		let descricao = "Aviso de chuvas. Início: 2026-01-10 10:00 Fim: 2026-01-11T09:30";
+		assert_eq!(
+			janela_alerta(descricao),
+			Some((utc(2026, 1, 10, 13, 0), utc(2026, 1, 11, 12, 30)))
+		);
+	}
+
+	#[test]
+	fn janela_alerta_incompleta_ou_invertida() {
+		assert_eq!(janela_alerta("Inicio 2026-01-10 10:00"), None);
+		assert_eq!(
+			janela_alerta("Início 2026-01-11 10:00 Fim 2026-01-10 10:00"),
+			None
+		);
+	}
+
+	#[test]
+	fn espera_tentativa_dobra_ate_um_dia() {
+		let config = SaidaConfig {
+			espera_tentativa_minutos: 5,
+			..Default::default()
+		};
+		assert_eq!(espera_tentativa(&config, 1), Duration::minutes(5));
+		assert_eq!(espera_tentativa(&config, 3), Duration::minutes(20));
+		assert_eq!(espera_tentativa(&config, 40), Duration::hours(24));
+		let config = SaidaConfig {
+			espera_tentativa_minutos: 0,
+			..Default::default()
+		};
+		assert_eq!(espera_tentativa(&config, 1), Duration::minutes(1));
+	}
+
+	fn sh(script: &str) -> Vec<String> {
+		["sh", "-c", script].map(str::to_string).to_vec()
+	}
+
+	#[tokio::test]
+	async fn programa_que_nao_le_o_stdin_vale_pelo_codigo_de_saida() {
+		let entrada = vec![b'x'; 1 << 20];
+		let segundo = StdDuration::from_secs(5);
+		let saida = executar_programa(
+			&sh("echo \"$NOME\""),
+			&[("NOME", "ok".to_string())],
+			&entrada,
+			segundo,
+			Stdio::piped(),
+			"script",
+		)
+		.await
+		.unwrap();
+		assert!(saida.status.success());
+		assert_eq!(saida.stdout, b"ok\n");
+
+		let saida = executar_programa(
+			&sh("exit 3"),
+			&[],
+			&entrada,
+			segundo,
+			Stdio::null(),
+			"comando",
+		)
+		.await
+		.unwrap();
+		assert_eq!(saida.status.code(), Some(3));
+	}
+
+	#[tokio::test]
+	async fn programa_que_passa_do_tempo_limite_e_falha() {
+		let erro = executar_programa(
+			&sh("sleep 5"),
+			&[],
+			b"",
+			StdDuration::from_millis(100),
+			Stdio::null(),
+			"comando",
+		)
+		.await
+		.unwrap_err();
+		assert_eq!(erro.to_string(), "o comando passou de 0 s");
+	}
+}
@@ -0,0 +1,443 @@
+//! Sincronização dos itens já vistos entre instâncias do agente.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sled::Db;
+use std::{error::Error, io, sync::Arc, time::Duration as StdDuration};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader as AsyncBufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::error;
+
+use crate::{pipeline::*, store::*};
+
+// =================================================================
+// SINCRONIZAÇÃO ENTRE INSTÂNCIAS
+// =================================================================
+
+// As chaves são divididas em 256 baldes pelo hash; só os baldes com resumos diferentes são
+// trocados, então pares já sincronizados trocam apenas os resumos.
+pub const SINC_BALDES: usize = 256;
+pub const SINC_MAX_CORPO: usize = 64 * 1024 * 1024;
+
+/// Resumo (XOR dos hashes das chaves) de cada balde, por árvore.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResumoSincronizacao {
+	pub processados: Vec<u64>,
+	pub irrelevantes: Vec<u64>,
+}
+
+/// Chaves dos baldes divergentes, enviadas nos dois sentidos.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ChavesSincronizacao {
+	pub baldes_processados: Vec<u8>,
+	pub baldes_irrelevantes: Vec<u8>,
+	pub processados: Vec<String>,
+	pub irrelevantes: Vec<String>,
+}
+
+pub fn sinc_balde(chave: &[u8]) -> u8 {
+	(hash_fnv1a(&String::from_utf8_lossy(chave)) >> 56) as u8
+}
+
+pub fn sinc_resumo_arvore(tree: &sled::Tree) -> Result<Vec<u64>, sled::Error> {
+	let mut resumo = vec![0u64; SINC_BALDES];
+	for entrada in tree.iter() {
+		let (chave, _) = entrada?;
+		resumo[sinc_balde(&chave) as usize] ^= hash_fnv1a(&String::from_utf8_lossy(&chave));
+	}
+	Ok(resumo)
+}
+
+pub fn sinc_resumo(db: &Db) -> Result<ResumoSincronizacao, sled::Error> {
+	Ok(ResumoSincronizacao {
+		processados: sinc_resumo_arvore(db)?,
+		irrelevantes: sinc_resumo_arvore(&db.open_tree(IRRELEVANT_CACHE_TREE)?)?,
+	})
+}
+
+pub fn sinc_chaves_nos_baldes(
+	tree: &sled::Tree,
+	baldes: &[u8],
+) -> Result<Vec<String>, sled::Error> {
+	let mut chaves = Vec::new();
+	for entrada in tree.iter() {
+		let (chave, _) = entrada?;
+		if baldes.contains(&sinc_balde(&chave)) {
+			chaves.push(String::from_utf8_lossy(&chave).into_owned());
+		}
+	}
+	Ok(chaves)
+}
+
+/// Chaves locais dos baldes pedidos.
+pub fn sinc_chaves(
+	db: &Db,
+	baldes_processados: Vec<u8>,
+	baldes_irrelevantes: Vec<u8>,
+) -> Result<ChavesSincronizacao, sled::Error> {
+	Ok(ChavesSincronizacao {
+		processados: sinc_chaves_nos_baldes(db, &baldes_processados)?,
+		irrelevantes: sinc_chaves_nos_baldes(
+			&db.open_tree(IRRELEVANT_CACHE_TREE)?,
+			&baldes_irrelevantes,
+		)?,
+		baldes_processados,
+		baldes_irrelevantes,
+	})
+}
+
+/// Grava as chaves que faltam localmente e devolve quantas eram novas. Registros existentes
+/// (ex.: os itens guardados para a revisão de irrelevantes) não são sobrescritos.
+pub fn sinc_mesclar(db: &Db, chaves: &ChavesSincronizacao) -> Result<usize, sled::Error> {
+	let irrelevantes = db.open_tree(IRRELEVANT_CACHE_TREE)?;
+	let mut novas = 0;
+	for chave in &chaves.processados {
+		if !db.contains_key(chave.as_bytes())? {
+			db.insert(chave.as_bytes(), b"processed")?;
+			novas += 1;
+		}
+	}
+	for chave in &chaves.irrelevantes {
+		if !irrelevantes.contains_key(chave.as_bytes())? {
+			irrelevantes.insert(chave.as_bytes(), b"1")?;
+			novas += 1;
+		}
+	}
+	db.flush()?;
+	irrelevantes.flush()?;
+	Ok(novas)
+}
+
+/// Sincroniza com o par: compara os resumos, envia as chaves dos baldes divergentes e grava
+/// as que o par devolver. Devolve (chaves recebidas novas, chaves enviadas).
+pub async fn sincronizar_com_par(
+	client: &Client,
+	db: &Db,
+	par: &str,
+	token: Option<&str>,
+) -> Result<(usize, usize), Box<dyn Error>> {
+	let base = par.trim_end_matches('/');
+	let autenticar = |request: reqwest::RequestBuilder| match token {
+		Some(token) => request.bearer_auth(token),
+		None => request,
+	};
+
+	let remoto: ResumoSincronizacao = autenticar(client.get(format!("{}/sync/resumo", base)))
+		.timeout(StdDuration::from_secs(30))
+		.send()
+		.await?
+		.error_for_status()?
+		.json()
+		.await?;
+	let local = sinc_resumo(db)?;
+	let divergentes = |a: &[u64], b: &[u64]| -> Vec<u8> {
+		(0..SINC_BALDES)
+			.filter(|&i| a.get(i) != b.get(i))
+			.map(|i| i as u8)
+			.collect()
+	};
+	let baldes_processados = divergentes(&local.processados, &remoto.processados);
+	let baldes_irrelevantes = divergentes(&local.irrelevantes, &remoto.irrelevantes);
+	if baldes_processados.is_empty() && baldes_irrelevantes.is_empty() {
+		return Ok((0, 0));
+	}
+
+	let enviadas = sinc_chaves(db, baldes_processados, baldes_irrelevantes)?;
+	let total_enviadas = enviadas.processados.len() + enviadas.irrelevantes.len();
+	let recebidas: ChavesSincronizacao = autenticar(client.post(format!("{}/sync/trocar", base)))
+		.json(&enviadas)
+		.timeout(StdDuration::from_secs(120))
+		.send()
+		.await?
+		.error_for_status()?
+		.json()
+		.await?;
+	Ok((sinc_mesclar(db, &recebidas)?, total_enviadas))
+}
+
+/// Atende uma requisição do par e devolve (status HTTP, corpo JSON).
+pub fn sinc_responder(db: &Db, metodo: &str, caminho: &str, corpo: &[u8]) -> (u16, String) {
+	let resposta = match (metodo, caminho) {
+		("GET", "/sync/resumo") => sinc_resumo(db)
+			.map_err(|e| e.to_string())
+			.and_then(|r| serde_json::to_string(&r).map_err(|e| e.to_string())),
+		("POST", "/sync/trocar") => serde_json::from_slice::<ChavesSincronizacao>(corpo)
+			.map_err(|e| e.to_string())
+			.and_then(|recebidas| {
+				// As chaves locais são lidas antes da mescla, para devolver só o estado deste lado
+				let locais = sinc_chaves(
+					db,
+					recebidas.baldes_processados.clone(),
+					recebidas.baldes_irrelevantes.clone(),
+				)
+				.map_err(|e| e.to_string())?;
+				sinc_mesclar(db, &recebidas).map_err(|e| e.to_string())?;
+				serde_json::to_string(&locais).map_err(|e| e.to_string())
+			}),
+		_ => return (404, "{}".to_string()),
+	};
+	match resposta {
+		Ok(corpo) => (200, corpo),
+		Err(e) => (500, serde_json::json!({ "erro": e }).to_string()),
+	}
+}
+
+/// Requisição lida por `ler_requisicao`.
+pub struct RequisicaoHttp {
+	pub metodo: String,
+	pub caminho: String,
+	pub autorizacao: Option<String>,
+	// Cabeçalho `X-FilterFlow-Cliente`, com que os clientes do próprio programa se identificam
+	pub cliente: Option<String>,
+	pub corpo: Vec<u8>,
+}
+
+// Limites dos servidores embutidos (sincronização e painel): quem não manda os cabeçalhos a
+// tempo ou manda linhas grandes demais é desconectado, sem prender a tarefa nem a memória.
+pub const HTTP_MAX_LINHA: usize = 8 * 1024;
+pub const HTTP_MAX_CABECALHOS: usize = 64;
+pub const HTTP_PRAZO_CABECALHOS: StdDuration = StdDuration::from_secs(10);
+pub const HTTP_PRAZO_CORPO: StdDuration = StdDuration::from_secs(120);
+
+async fn ler_linha(conexao: &mut AsyncBufReader<TcpStream>) -> Result<String, u16> {
+	let mut linha = Vec::new();
+	(&mut *conexao)
+		.take(HTTP_MAX_LINHA as u64)
+		.read_until(b'\n', &mut linha)
+		.await
+		.map_err(|_| 400u16)?;
+	if !linha.ends_with(b"\n") {
+		// Linha cortada pelo limite ou conexão fechada no meio dela
+		return Err(if linha.len() >= HTTP_MAX_LINHA {
+			431
+		} else {
+			400
+		});
+	}
+	String::from_utf8(linha).map_err(|_| 400)
+}
+
+async fn ler_cabecalhos(
+	conexao: &mut AsyncBufReader<TcpStream>,
+) -> Result<(RequisicaoHttp, usize), u16> {
+	let linha = ler_linha(conexao).await?;
+	let mut partes = linha.split_whitespace();
+	let mut requisicao = RequisicaoHttp {
+		metodo: partes.next().ok_or(400u16)?.to_string(),
+		caminho: partes.next().ok_or(400u16)?.to_string(),
+		autorizacao: None,
+		cliente: None,
+		corpo: Vec::new(),
+	};
+
+	let mut tamanho = 0;
+	for _ in 0..=HTTP_MAX_CABECALHOS {
+		let cabecalho = ler_linha(conexao).await?;
+		if cabecalho.trim().is_empty() {
+			return Ok((requisicao, tamanho));
+		}
+		if let Some((nome, valor)) = cabecalho.split_once(':') {
+			match nome.trim().to_lowercase().as_str() {
+				"content-length" => tamanho = valor.trim().parse().map_err(|_| 400u16)?,
+				"authorization" => requisicao.autorizacao = Some(valor.trim().to_string()),
+				"x-filterflow-cliente" => requisicao.cliente = Some(valor.trim().to_string()),
+				_ => {}
+			}
+		}
+	}
+	Err(431)
+}
+
+/// Lê a linha de pedido e os cabeçalhos de uma requisição HTTP/1.1 simples, sem o corpo, e
+/// devolve o tamanho anunciado dele. Linhas longas demais, cabeçalhos em excesso, corpo acima
+/// de `max_corpo` ou cabeçalhos que não chegam no prazo viram o status da recusa.
+pub async fn ler_cabecalhos_requisicao(
+	conexao: &mut AsyncBufReader<TcpStream>,
+	max_corpo: usize,
+) -> Result<(RequisicaoHttp, usize), u16> {
+	let (requisicao, tamanho) =
+		tokio::time::timeout(HTTP_PRAZO_CABECALHOS, ler_cabecalhos(conexao))
+			.await
+			.map_err(|_| 408u16)??;
+	if tamanho > max_corpo {
+		return Err(413);
+	}
+	Ok((requisicao, tamanho))
+}
+
+/// Lê o corpo de `tamanho` bytes anunciado nos cabeçalhos, dentro de `HTTP_PRAZO_CORPO`.
+pub async fn ler_corpo(
+	conexao: &mut AsyncBufReader<TcpStream>,
+	requisicao: &mut RequisicaoHttp,
+	tamanho: usize,
+) -> Result<(), u16> {
+	requisicao.corpo = vec![0u8; tamanho];
+	tokio::time::timeout(HTTP_PRAZO_CORPO, conexao.read_exact(&mut requisicao.corpo))
+		.await
+		.map_err(|_| 408u16)?
+		.map_err(|_| 400u16)?;
+	Ok(())
+}
+
+/// Lê uma requisição HTTP/1.1 simples (uma por conexão), cabeçalhos e corpo.
+pub async fn ler_requisicao(
+	conexao: &mut AsyncBufReader<TcpStream>,
+	max_corpo: usize,
+) -> Result<RequisicaoHttp, u16> {
+	let (mut requisicao, tamanho) = ler_cabecalhos_requisicao(conexao, max_corpo).await?;
+	ler_corpo(conexao, &mut requisicao, tamanho).await?;
+	Ok(requisicao)
+}
+
+/// Compara um segredo recebido com o esperado em tempo constante (os dois passam antes pelo
+/// SHA-256, para que nem o tamanho influa no tempo).
+pub fn segredo_confere(recebido: &str, esperado: &str) -> bool {
+	let a = ring::digest::digest(&ring::digest::SHA256, recebido.as_bytes());
+	let b = ring::digest::digest(&ring::digest::SHA256, esperado.as_bytes());
+	a.as_ref()
+		.iter()
+		.zip(b.as_ref())
+		.fold(0u8, |diferenca, (x, y)| diferenca | (x ^ y))
+		== 0
+}
+
+pub fn motivo_http(status: u16) -> &'static str {
+	match status {
+		200 => "OK",
+		400 => "Bad Request",
+		401 => "Unauthorized",
+		404 => "Not Found",
+		408 => "Request Timeout",
+		413 => "Payload Too Large",
+		431 => "Request Header Fields Too Large",
+		_ => "Internal Server Error",
+	}
+}
+
+/// Lê uma requisição do par e responde; sem o token correto, o pedido é recusado.
+pub async fn sinc_conexao(stream: TcpStream, db: Arc<Db>, token: String) -> io::Result<()> {
+	let mut conexao = AsyncBufReader::new(stream);
+	// O token é conferido antes de ler o corpo: sem ele, ninguém faz o servidor alocar e
+	// esperar até `SINC_MAX_CORPO` bytes
+	let (status, resposta) = match ler_cabecalhos_requisicao(&mut conexao, SINC_MAX_CORPO).await {
+		Err(status) => (status, "{}".to_string()),
+		Ok((requisicao, _))
+			if !requisicao
+				.autorizacao
+				.as_deref()
+				.and_then(|a| a.strip_prefix("Bearer "))
+				.is_some_and(|t| segredo_confere(t, &token)) =>
+		{
+			(401, "{}".to_string())
+		}
+		Ok((mut requisicao, tamanho)) => {
+			match ler_corpo(&mut conexao, &mut requisicao, tamanho).await {
+				Err(status) => (status, "{}".to_string()),
+				Ok(()) => {
+					let db = Arc::clone(&db);
+					tokio::task::spawn_blocking(move || {
+						sinc_responder(
+							&db,
+							&requisicao.metodo,
+							&requisicao.caminho,
+							&requisicao.corpo,
+						)
+					})
+					.await
+					.map_err(io::Error::other)?
+				}
+			}
+		}
+	};
+	let cabecalhos = format!(
+		"HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+		status,
+		motivo_http(status),
+		resposta.len()
+	);
+	let stream = conexao.get_mut();
+	stream.write_all(cabecalhos.as_bytes()).await?;
+	stream.write_all(resposta.as_bytes()).await?;
+	stream.shutdown().await
+}
+
+/// Atende os pares em `endereco` enquanto o agente roda.
+pub async fn sinc_atender(listener: TcpListener, db: Arc<Db>, token: String) {
+	loop {
+		match listener.accept().await {
+			Ok((stream, _)) => {
+				let (db, token) = (Arc::clone(&db), token.clone());
+				tokio::spawn(async move {
+					if let Err(e) = sinc_conexao(stream, db, token).await {
+						error!("Falha ao atender o par: {}", e);
+					}
+				});
+			}
+			Err(e) => error!("Falha ao aceitar conexão: {}", e),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn mesclar_grava_so_as_chaves_que_faltam() {
+		let db = sled::Config::new().temporary(true).open().unwrap();
+		db.insert(b"a", b"registro local".as_slice()).unwrap();
+		let chaves = ChavesSincronizacao {
+			baldes_processados: Vec::new(),
+			baldes_irrelevantes: Vec::new(),
+			processados: vec!["a".to_string(), "b".to_string()],
+			irrelevantes: vec!["c".to_string()],
+		};
+		assert_eq!(sinc_mesclar(&db, &chaves).unwrap(), 2);
+		assert_eq!(
+			db.get(b"a").unwrap().as_deref(),
+			Some(b"registro local".as_slice())
+		);
+		assert_eq!(
+			db.get(b"b").unwrap().as_deref(),
+			Some(b"processed".as_slice())
+		);
+		assert!(
+			db.open_tree(IRRELEVANT_CACHE_TREE)
+				.unwrap()
+				.contains_key(b"c")
+				.unwrap()
+		);
+		assert_eq!(sinc_mesclar(&db, &chaves).unwrap(), 0);
+	}
+
+	#[tokio::test]
+	async fn sem_token_recusa_antes_de_ler_o_corpo() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let endereco = listener.local_addr().unwrap();
+		let db = Arc::new(sled::Config::new().temporary(true).open().unwrap());
+		let servidor = tokio::spawn(async move {
+			let (stream, _) = listener.accept().await.unwrap();
+			sinc_conexao(stream, db, "segredo".to_string()).await
+		});
+
+		// Anuncia um corpo grande e não manda nenhum byte dele: a recusa tem de vir já
+		let mut cliente = TcpStream::connect(endereco).await.unwrap();
+		cliente
+			.write_all(
+				format!("POST /sinc HTTP/1.1\r\nContent-Length: {SINC_MAX_CORPO}\r\n\r\n")
+					.as_bytes(),
+			)
+			.await
+			.unwrap();
+		let mut resposta = String::new();
+		tokio::time::timeout(
+			StdDuration::from_secs(5),
+			cliente.read_to_string(&mut resposta),
+		)
+		.await
+		.unwrap()
+		.unwrap();
+		assert!(resposta.starts_with("HTTP/1.1 401 "));
+		servidor.await.unwrap().unwrap();
+	}
+}
@@ -0,0 +1,386 @@
+//! Proxy SOCKS5 (Tor, túneis `ssh -D`) para o cliente HTTP, que só fala com proxies HTTP: uma
+//! ponte em `127.0.0.1` recebe as requisições como um proxy HTTP comum (`CONNECT` para HTTPS,
+//! forma absoluta para HTTP, uma por conexão) e abre cada conexão pelo servidor SOCKS. Com
+//! `socks5h://`, o nome do host vai ao servidor SOCKS, que o resolve (necessário para endereços
+//! `.onion`); com `socks5://`, ele é resolvido aqui. Com usuário e senha (no endereço ou em
+//! `[proxy]`), a ponte se autentica no servidor SOCKS (RFC 1929). A própria ponte só atende
+//! quem apresenta, em `Proxy-Authorization`, o segredo sorteado no início do processo (ver
+//! `credenciais_ponte`), para que outros usuários da máquina não a usem como proxy aberto.
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use once_cell::sync::Lazy;
+use percent_encoding::percent_decode_str;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::{
+	collections::HashMap,
+	error::Error,
+	io,
+	net::{IpAddr, SocketAddr},
+	sync::{Arc, Mutex},
+};
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::{TcpListener, TcpStream, lookup_host},
+};
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::sincronizacao::segredo_confere;
+
+// Maior cabeçalho aceito na primeira requisição de uma conexão
+const CABECALHO_MAXIMO: usize = 64 * 1024;
+
+/// Servidor SOCKS de uma ponte.
+#[derive(Debug)]
+struct DestinoSocks {
+	// host:porta do servidor SOCKS
+	servidor: String,
+	resolver_remoto: bool,
+	// Usuário e senha; sem eles, a ponte só aceita servidores sem autenticação
+	credenciais: Option<(String, String)>,
+}
+
+// Uma ponte por endereço SOCKS e credenciais, reaproveitada pelos clientes criados a cada ciclo
+type ChavePonte = (String, Option<(String, String)>);
+static PONTES: Lazy<Mutex<HashMap<ChavePonte, SocketAddr>>> = Lazy::new(Default::default);
+
+const USUARIO_PONTE: &str = "filterflow";
+static SEGREDO_PONTE: Lazy<String> = Lazy::new(|| {
+	let mut bytes = [0u8; 32];
+	SystemRandom::new()
+		.fill(&mut bytes)
+		.expect("falha ao sortear o segredo da ponte SOCKS");
+	bytes.iter().map(|b| format!("{:02x}", b)).collect()
+});
+
+/// Usuário e senha que o cliente HTTP apresenta às pontes deste processo.
+pub fn credenciais_ponte() -> (&'static str, &'static str) {
+	(USUARIO_PONTE, SEGREDO_PONTE.as_str())
+}
+
+/// Confere o `Proxy-Authorization` da requisição recebida pela ponte.
+fn cliente_autorizado(cabecalho: &str) -> bool {
+	let esperado = BASE64.encode(format!("{}:{}", USUARIO_PONTE, SEGREDO_PONTE.as_str()));
+	cabecalho.split("\r\n").skip(1).any(|linha| {
+		linha.split_once(':').is_some_and(|(nome, valor)| {
+			nome.trim().eq_ignore_ascii_case("proxy-authorization")
+				&& valor
+					.trim()
+					.strip_prefix("Basic ")
+					.is_some_and(|v| segredo_confere(v.trim(), &esperado))
+		})
+	})
+}
+
+pub fn eh_socks(endereco: &str) -> bool {
+	let endereco = endereco.to_ascii_lowercase();
+	endereco.starts_with("socks5://") || endereco.starts_with("socks5h://")
+}
+
+/// Endereço (`http://127.0.0.1:<porta>`) da ponte para o proxy SOCKS `endereco`, aberta na
+/// primeira chamada. As `credenciais` prevalecem sobre as do endereço. Precisa de um runtime
+/// do tokio em execução.
+pub fn ponte_socks(
+	endereco: &str,
+	credenciais: Option<(&str, &str)>,
+) -> Result<String, Box<dyn Error>> {
+	let url = Url::parse(endereco)?;
+	let resolver_remoto = match url.scheme() {
+		"socks5h" => true,
+		"socks5" => false,
+		outro => return Err(format!("esquema de proxy SOCKS desconhecido: '{}'", outro).into()),
+	};
+	let host = url
+		.host_str()
+		.ok_or_else(|| format!("proxy SOCKS sem host: '{}'", endereco))?;
+	let credenciais = match credenciais {
+		Some((usuario, senha)) => Some((usuario.to_string(), senha.to_string())),
+		None if !url.username().is_empty() => {
+			let decodificar = |s: &str| percent_decode_str(s).decode_utf8_lossy().into_owned();
+			Some((
+				decodificar(url.username()),
+				decodificar(url.password().unwrap_or_default()),
+			))
+		}
+		None => None,
+	};
+	let servidor = format!("{}:{}", host, url.port().unwrap_or(1080));
+	let chave = (endereco.to_string(), credenciais.clone());
+	let destino = Arc::new(DestinoSocks {
+		servidor: servidor.clone(),
+		resolver_remoto,
+		credenciais,
+	});
+
+	let mut pontes = PONTES.lock().unwrap();
+	if let Some(local) = pontes.get(&chave) {
+		return Ok(format!("http://{}", local));
+	}
+	let runtime = tokio::runtime::Handle::try_current()?;
+	let ouvinte = std::net::TcpListener::bind("127.0.0.1:0")?;
+	ouvinte.set_nonblocking(true)?;
+	let local = ouvinte.local_addr()?;
+	let ouvinte = {
+		let _dentro = runtime.enter();
+		TcpListener::from_std(ouvinte)?
+	};
+	runtime.spawn(async move {
+		loop {
+			match ouvinte.accept().await {
+				Ok((conexao, _)) => {
+					let destino = Arc::clone(&destino);
+					tokio::spawn(async move {
+						if let Err(e) = atender(conexao, &destino).await {
+							debug!("Conexão pela ponte SOCKS encerrada: {}", e);
+						}
+					});
+				}
+				Err(e) => warn!("Falha ao aceitar conexão na ponte SOCKS: {}", e),
+			}
+		}
+	});
+	debug!(%local, %servidor, "Ponte SOCKS aberta");
+	pontes.insert(chave, local);
+	Ok(format!("http://{}", local))
+}
+
+/// Atende uma conexão do cliente HTTP: lê a primeira requisição para saber o destino, conecta
+/// pelo SOCKS e, daí em diante, só repassa os bytes nos dois sentidos.
+async fn atender(mut cliente: TcpStream, destino: &DestinoSocks) -> io::Result<()> {
+	let mut cabecalho = Vec::new();
+	let mut bloco = [0u8; 4096];
+	let fim = loop {
+		if let Some(fim) = cabecalho.windows(4).position(|j| j == b"\r\n\r\n") {
+			break fim + 4;
+		}
+		if cabecalho.len() > CABECALHO_MAXIMO {
+			return Err(io::Error::other("cabeçalho grande demais"));
+		}
+		let lidos = cliente.read(&mut bloco).await?;
+		if lidos == 0 {
+			return Ok(());
+		}
+		cabecalho.extend_from_slice(&bloco[..lidos]);
+	};
+
+	let primeira = String::from_utf8_lossy(&cabecalho[..fim]);
+	if !cliente_autorizado(&primeira) {
+		cliente
+			.write_all(
+				b"HTTP/1.1 407 Proxy Authentication Required\r\n\
+				  Proxy-Authenticate: Basic realm=\"filterflow\"\r\nContent-Length: 0\r\n\r\n",
+			)
+			.await?;
+		return Err(io::Error::other("cliente sem o segredo da ponte"));
+	}
+	let mut partes = primeira.split_whitespace();
+	let metodo = partes.next().unwrap_or_default().to_string();
+	let alvo = partes.next().unwrap_or_default().to_string();
+	let tunel = metodo.eq_ignore_ascii_case("CONNECT");
+	let (host, porta) = if tunel {
+		let (host, porta) = alvo
+			.rsplit_once(':')
+			.ok_or_else(|| io::Error::other(format!("CONNECT sem porta: '{}'", alvo)))?;
+		let porta = porta
+			.parse()
+			.map_err(|_| io::Error::other(format!("porta inválida: '{}'", alvo)))?;
+		(host.trim_matches(['[', ']']).to_string(), porta)
+	} else {
+		let url = Url::parse(&alvo).map_err(io::Error::other)?;
+		let host = url
+			.host_str()
+			.ok_or_else(|| io::Error::other(format!("requisição sem host: '{}'", alvo)))?;
+		(
+			host.trim_matches(['[', ']']).to_string(),
+			url.port_or_known_default().unwrap_or(80),
+		)
+	};
+
+	let mut servidor = match conectar_socks(destino, &host, porta).await {
+		Ok(servidor) => servidor,
+		Err(e) => {
+			let _ = cliente
+				.write_all(b"HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\n\r\n")
+				.await;
+			return Err(e);
+		}
+	};
+	if tunel {
+		cliente
+			.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+			.await?;
+		servidor.write_all(&cabecalho[fim..]).await?;
+	} else {
+		servidor
+			.write_all(&cabecalho_de_origem(&primeira, &alvo))
+			.await?;
+		servidor.write_all(&cabecalho[fim..]).await?;
+	}
+	tokio::io::copy_bidirectional(&mut cliente, &mut servidor).await?;
+	Ok(())
+}
+
+/// Cabeçalho de uma requisição HTTP em forma absoluta reescrito para o servidor de origem:
+/// só o caminho na primeira linha, sem os cabeçalhos do proxy (inclusive o segredo da ponte) e
+/// com `Connection: close`. As
+/// requisições seguintes do cliente abrem outra conexão, e cada uma é reescrita do mesmo modo.
+fn cabecalho_de_origem(cabecalho: &str, alvo: &str) -> Vec<u8> {
+	let caminho = Url::parse(alvo)
+		.map(|url| match url.query() {
+			Some(consulta) => format!("{}?{}", url.path(), consulta),
+			None => url.path().to_string(),
+		})
+		.unwrap_or_else(|_| "/".to_string());
+	let mut linhas = cabecalho.split("\r\n").filter(|l| !l.is_empty());
+	let primeira = linhas
+		.next()
+		.unwrap_or_default()
+		.replacen(alvo, &caminho, 1);
+	let mut saida = format!("{}\r\n", primeira);
+	for linha in linhas {
+		let nome = linha.split(':').next().unwrap_or_default().trim();
+		if nome.eq_ignore_ascii_case("connection")
+			|| nome.eq_ignore_ascii_case("proxy-connection")
+			|| nome.eq_ignore_ascii_case("proxy-authorization")
+		{
+			continue;
+		}
+		saida.push_str(linha);
+		saida.push_str("\r\n");
+	}
+	saida.push_str("Connection: close\r\n\r\n");
+	saida.into_bytes()
+}
+
+/// Abre uma conexão a `host:porta` pelo servidor SOCKS5 (RFC 1928), sem autenticação ou com
+/// usuário e senha (RFC 1929).
+async fn conectar_socks(destino: &DestinoSocks, host: &str, porta: u16) -> io::Result<TcpStream> {
+	let mut conexao = TcpStream::connect(&destino.servidor).await?;
+	match &destino.credenciais {
+		Some(_) => conexao.write_all(&[5, 2, 0, 2]).await?,
+		None => conexao.write_all(&[5, 1, 0]).await?,
+	}
+	let mut escolha = [0u8; 2];
+	conexao.read_exact(&mut escolha).await?;
+	match (escolha, &destino.credenciais) {
+		([5, 0], _) => {}
+		([5, 2], Some((usuario, senha))) => autenticar(&mut conexao, usuario, senha).await?,
+		(_, None) => {
+			return Err(io::Error::other(format!(
+				"o servidor SOCKS {} não aceita conexões sem autenticação",
+				destino.servidor
+			)));
+		}
+		(_, Some(_)) => {
+			return Err(io::Error::other(format!(
+				"o servidor SOCKS {} não aceita autenticação por usuário e senha",
+				destino.servidor
+			)));
+		}
+	}
+
+	let mut pedido = vec![5, 1, 0];
+	let ip = match host.parse::<IpAddr>() {
+		Ok(ip) => Some(ip),
+		Err(_) if destino.resolver_remoto => None,
+		Err(_) => Some(
+			lookup_host((host, porta))
+				.await?
+				.next()
+				.ok_or_else(|| io::Error::other(format!("'{}' não resolve", host)))?
+				.ip(),
+		),
+	};
+	match ip {
+		Some(IpAddr::V4(ip)) => {
+			pedido.push(1);
+			pedido.extend(ip.octets());
+		}
+		Some(IpAddr::V6(ip)) => {
+			pedido.push(4);
+			pedido.extend(ip.octets());
+		}
+		None => {
+			let nome = host.as_bytes();
+			let tamanho = u8::try_from(nome.len())
+				.map_err(|_| io::Error::other(format!("nome de host longo demais: '{}'", host)))?;
+			pedido.push(3);
+			pedido.push(tamanho);
+			pedido.extend(nome);
+		}
+	}
+	pedido.extend(porta.to_be_bytes());
+	conexao.write_all(&pedido).await?;
+
+	let mut resposta = [0u8; 4];
+	conexao.read_exact(&mut resposta).await?;
+	if resposta[1] != 0 {
+		return Err(io::Error::other(format!(
+			"o servidor SOCKS recusou a conexão a {}:{} (código {})",
+			host, porta, resposta[1]
+		)));
+	}
+	// Endereço ligado pelo servidor, que não interessa aqui
+	let tamanho = match resposta[3] {
+		1 => 4,
+		4 => 16,
+		3 => {
+			let mut tamanho = [0u8; 1];
+			conexao.read_exact(&mut tamanho).await?;
+			usize::from(tamanho[0])
+		}
+		outro => {
+			return Err(io::Error::other(format!(
+				"resposta SOCKS com tipo de endereço desconhecido ({})",
+				outro
+			)));
+		}
+	};
+	let mut ligado = vec![0u8; tamanho + 2];
+	conexao.read_exact(&mut ligado).await?;
+	Ok(conexao)
+}
+
+/// Autenticação por usuário e senha no servidor SOCKS (RFC 1929).
+async fn autenticar(conexao: &mut TcpStream, usuario: &str, senha: &str) -> io::Result<()> {
+	let tamanho = |campo: &str| {
+		u8::try_from(campo.len())
+			.map_err(|_| io::Error::other("usuário ou senha do SOCKS com mais de 255 bytes"))
+	};
+	let mut pedido = vec![1, tamanho(usuario)?];
+	pedido.extend(usuario.as_bytes());
+	pedido.push(tamanho(senha)?);
+	pedido.extend(senha.as_bytes());
+	conexao.write_all(&pedido).await?;
+	let mut resposta = [0u8; 2];
+	conexao.read_exact(&mut resposta).await?;
+	if resposta[1] != 0 {
+		return Err(io::Error::other(
+			"o servidor SOCKS recusou o usuário e a senha",
+		));
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn cabecalho_de_origem_usa_o_caminho_e_tira_os_do_proxy() {
+		let cabecalho = "GET http://exemplo.com/feed?page=2 HTTP/1.1\r\n\
+			Host: exemplo.com\r\n\
+			Proxy-Authorization: Basic dTpw\r\n\
+			Proxy-Connection: keep-alive\r\n\
+			connection: keep-alive\r\n\
+			Accept: */*\r\n\r\n";
+		let saida = cabecalho_de_origem(cabecalho, "http://exemplo.com/feed?page=2");
+		assert_eq!(
+			String::from_utf8(saida).unwrap(),
+			"GET /feed?page=2 HTTP/1.1\r\n\
+			 Host: exemplo.com\r\n\
+			 Accept: */*\r\n\
+			 Connection: close\r\n\r\n"
+		);
+	}
+}
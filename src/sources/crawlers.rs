@@ -0,0 +1,196 @@
+//! Rastreamento das páginas de um site a partir de uma URL inicial.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Client;
+use std::{collections::BTreeSet, error::Error, sync::Arc, time::Duration as StdDuration};
+use tokio::time;
+use tracing::warn;
+use url::Url;
+
+use super::{
+	Coleta, Coletor, ContextoColeta, HTML_ATTR_REGEX_LAZY, Repeticao, clean_html_content,
+	feeds::parece_html, sitemaps::fetch_sitemap_content,
+};
+use crate::{config::*, pipeline::*, store::*};
+
+pub static TITLE_TAG_REGEX_LAZY: Lazy<Regex> =
+	Lazy::new(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap());
+pub static META_TAG_REGEX_LAZY: Lazy<Regex> =
+	Lazy::new(|| Regex::new(r"(?is)<meta\b[^>]*>").unwrap());
+pub static A_HREF_REGEX_LAZY: Lazy<Regex> = Lazy::new(|| {
+	Regex::new(r#"(?is)<a\b[^>]*?\bhref\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap()
+});
+
+/// Prefixos de `Disallow` do grupo `User-agent: *` do robots.txt.
+pub async fn regras_robots(client: &Client, repeticao: Repeticao, url: &Url) -> Vec<String> {
+	let Ok(robots_url) = url.join("/robots.txt") else {
+		return Vec::new();
+	};
+	let Ok(conteudo) = fetch_sitemap_content(client, repeticao, robots_url.as_str()).await else {
+		return Vec::new();
+	};
+
+	let mut proibidos = Vec::new();
+	let mut grupo_geral = false;
+	for linha in String::from_utf8_lossy(&conteudo).lines() {
+		let linha = linha.split('#').next().unwrap_or_default();
+		let Some((chave, valor)) = linha.split_once(':') else {
+			continue;
+		};
+		let (chave, valor) = (chave.trim().to_lowercase(), valor.trim());
+		match chave.as_str() {
+			"user-agent" => grupo_geral = valor == "*",
+			"disallow" if grupo_geral && !valor.is_empty() => proibidos.push(valor.to_string()),
+			_ => {}
+		}
+	}
+	proibidos
+}
+
+/// Título (og:title ou <title>) e descrição (description/og:description) de uma página.
+pub fn metadados_pagina(html: &str) -> (Option<String>, String) {
+	let mut titulo = TITLE_TAG_REGEX_LAZY
+		.captures(html)
+		.map(|c| clean_html_content(&c[1]))
+		.filter(|t| !t.is_empty());
+	let mut descricao = String::new();
+
+	for tag in META_TAG_REGEX_LAZY.find_iter(html) {
+		let mut nome = String::new();
+		let mut conteudo = String::new();
+		for caps in HTML_ATTR_REGEX_LAZY.captures_iter(tag.as_str()) {
+			let valor = caps
+				.get(2)
+				.or_else(|| caps.get(3))
+				.or_else(|| caps.get(4))
+				.map(|v| v.as_str().trim().to_string())
+				.unwrap_or_default();
+			match caps[1].to_lowercase().as_str() {
+				"name" | "property" => nome = valor.to_lowercase(),
+				"content" => conteudo = valor,
+				_ => {}
+			}
+		}
+		match nome.as_str() {
+			"og:title" if !conteudo.is_empty() => titulo = Some(clean_html_content(&conteudo)),
+			"description" | "og:description" if descricao.is_empty() => {
+				descricao = clean_html_content(&conteudo)
+			}
+			_ => {}
+		}
+	}
+	(titulo, descricao)
+}
+
+/// Links `<a href>` da página, resolvidos contra a URL base e sem fragmento.
+pub fn links_da_pagina(html: &str, base: &Url) -> Vec<Url> {
+	A_HREF_REGEX_LAZY
+		.captures_iter(html)
+		.filter_map(|c| {
+			let href = c.get(1).or_else(|| c.get(2)).or_else(|| c.get(3))?;
+			let mut url = base.join(href.as_str().trim()).ok()?;
+			url.set_fragment(None);
+			Some(url)
+		})
+		.collect()
+}
+
+impl Coletor for CrawlerConfig {
+	fn tipo(&self) -> &'static str {
+		"site"
+	}
+
+	fn coletar<'a>(&'a self, contexto: &'a ContextoColeta<'a>) -> Coleta<'a> {
+		Box::pin(coletar_crawler(
+			contexto.client,
+			contexto.repeticao,
+			contexto.db,
+			self,
+		))
+	}
+}
+
+/// Percorre o site em largura a partir de `url_inicial`. Cada página visitada (exceto a
+/// inicial) vira um item; páginas já vistas na profundidade máxima nem são baixadas.
+pub async fn coletar_crawler(
+	client: &Client,
+	repeticao: Repeticao,
+	db: &Arc<sled::Db>,
+	config: &CrawlerConfig,
+) -> Result<Vec<ItemNoticia>, Box<dyn Error>> {
+	let inicio = Url::parse(&config.url_inicial)?;
+	let host = inicio.host_str().unwrap_or_default().to_string();
+	let prefixo = config
+		.prefixo_caminho
+		.clone()
+		.unwrap_or_else(|| inicio.path().to_string());
+	let proibidos = regras_robots(client, repeticao, &inicio).await;
+	let permitido = |url: &Url| {
+		url.host_str() == Some(host.as_str())
+			&& matches!(url.scheme(), "http" | "https")
+			&& url.path().starts_with(&prefixo)
+			&& !proibidos.iter().any(|p| url.path().starts_with(p.as_str()))
+	};
+	let ja_visto = |link: &str| {
+		db.contains_key(link.as_bytes()).unwrap_or(false)
+			|| db_is_irrelevant(db, link).unwrap_or(false)
+	};
+
+	let mut fila = std::collections::VecDeque::from([(inicio.clone(), 0u32)]);
+	let mut descobertos: BTreeSet<String> = BTreeSet::from([inicio.to_string()]);
+	let mut itens = Vec::new();
+	let mut visitadas = 0;
+
+	while let Some((url, profundidade)) = fila.pop_front() {
+		if visitadas >= config.max_paginas {
+			break;
+		}
+		let link = url.to_string();
+		let conhecido = profundidade > 0 && ja_visto(&link);
+		if conhecido && profundidade >= config.profundidade_maxima {
+			continue;
+		}
+
+		// Intervalo entre requisições ao mesmo host
+		if visitadas > 0 {
+			time::sleep(StdDuration::from_millis(config.atraso_ms)).await;
+		}
+		visitadas += 1;
+		let html = match fetch_sitemap_content(client, repeticao, &link).await {
+			Ok(conteudo) if parece_html(&conteudo) => {
+				String::from_utf8_lossy(&conteudo).into_owned()
+			}
+			Ok(_) => continue,
+			Err(e) => {
+				warn!("Falha ao rastrear '{}': {}", link, e);
+				continue;
+			}
+		};
+
+		if profundidade > 0 && !conhecido {
+			let (titulo, descricao) = metadados_pagina(&html);
+			itens.push(ItemNoticia {
+				fonte: config.nome.clone(),
+				chave: link.clone(),
+				titulo: titulo.unwrap_or_else(|| link.clone()),
+				link,
+				descricao,
+				publicado: None,
+				guid: None,
+				grupo: None,
+			});
+		}
+
+		if profundidade < config.profundidade_maxima {
+			for proximo in links_da_pagina(&html, &url) {
+				if permitido(&proximo) && descobertos.insert(proximo.to_string()) {
+					fila.push_back((proximo, profundidade + 1));
+				}
+			}
+		}
+	}
+
+	informar!("{}", msg!("crawler.visitadas", config.nome, visitadas));
+	Ok(itens)
+}
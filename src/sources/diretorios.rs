@@ -0,0 +1,219 @@
+//! Arquivos JSON, JSONL e RSS deixados em pastas locais.
+
+use chrono::{DateTime, Local, Utc};
+use serde::Deserialize;
+use std::{error::Error, fs, io, time::Duration as StdDuration};
+use tracing::{debug, error};
+use url::Url;
+
+use super::feeds::{ler_canal, noticia_do_item_rss};
+use super::{Coleta, Coletor, ContextoColeta};
+use crate::{config::*, pipeline::*};
+
+// Arquivos modificados há menos tempo que isso podem estar sendo escritos e ficam para o
+// próximo ciclo
+pub const ASSENTAMENTO_ARQUIVO: StdDuration = StdDuration::from_secs(10);
+
+/// Item de um arquivo .json deixado na pasta monitorada.
+#[derive(Debug, Deserialize)]
+pub struct ItemArquivo {
+	#[serde(alias = "title")]
+	pub titulo: String,
+	#[serde(default, alias = "url")]
+	pub link: Option<String>,
+	#[serde(default, alias = "description", alias = "conteudo")]
+	pub descricao: String,
+	#[serde(default)]
+	pub publicado: Option<DateTime<Utc>>,
+}
+
+/// Um arquivo .json pode trazer um único item ou uma lista deles.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ArquivoJson {
+	Varios(Vec<ItemArquivo>),
+	Um(ItemArquivo),
+}
+
+/// Link usado para itens sem URL: o próprio arquivo, com o hash do conteúdo para diferenciar
+/// arquivos de mesmo nome deixados em dias diferentes.
+pub fn link_do_arquivo(caminho: &std::path::Path, conteudo: &str) -> String {
+	let caminho = fs::canonicalize(caminho).unwrap_or_else(|_| caminho.to_path_buf());
+	match Url::from_file_path(&caminho) {
+		Ok(mut url) => {
+			url.set_fragment(Some(&format!("{:016x}", hash_fnv1a(conteudo))));
+			url.to_string()
+		}
+		Err(_) => caminho.display().to_string(),
+	}
+}
+
+/// Lê os itens de um arquivo conforme a extensão: .xml (RSS/RDF), .json ou .txt (primeira
+/// linha = título; o restante = descrição; a primeira URL do texto, se houver, é o link).
+pub fn ler_arquivo_de_itens(
+	fonte: &str,
+	caminho: &std::path::Path,
+	extensao: &str,
+) -> Result<Vec<ItemNoticia>, Box<dyn Error>> {
+	let bytes = fs::read(caminho)?;
+	let noticia = |titulo: String, link: String, descricao: String, publicado| ItemNoticia {
+		fonte: fonte.to_string(),
+		chave: link.clone(),
+		link,
+		titulo,
+		descricao,
+		publicado,
+		guid: None,
+		grupo: None,
+	};
+
+	let itens = match extensao {
+		"xml" => ler_canal(&bytes)?
+			.items()
+			.iter()
+			.filter_map(|item| noticia_do_item_rss(fonte, item))
+			.collect(),
+		"json" => {
+			let texto = String::from_utf8_lossy(&bytes);
+			let itens = match serde_json::from_str(&texto)? {
+				ArquivoJson::Varios(itens) => itens,
+				ArquivoJson::Um(item) => vec![item],
+			};
+			itens
+				.into_iter()
+				.map(|item| {
+					let link = item.link.filter(|l| !l.is_empty()).unwrap_or_else(|| {
+						link_do_arquivo(caminho, &format!("{}{}", item.titulo, item.descricao))
+					});
+					noticia(item.titulo, link, item.descricao, item.publicado)
+				})
+				.collect()
+		}
+		_ => {
+			let texto = String::from_utf8_lossy(&bytes);
+			let mut linhas = texto.lines().filter(|l| !l.trim().is_empty());
+			let Some(titulo) = linhas.next() else {
+				return Ok(Vec::new());
+			};
+			let descricao = linhas.collect::<Vec<_>>().join("\n");
+			let link = texto
+				.split_whitespace()
+				.find(|p| p.starts_with("http://") || p.starts_with("https://"))
+				.map(str::to_string)
+				.unwrap_or_else(|| link_do_arquivo(caminho, &texto));
+			vec![noticia(titulo.trim().to_string(), link, descricao, None)]
+		}
+	};
+	Ok(itens)
+}
+
+/// Move o arquivo lido para `pasta_arquivo` (sem sobrescrever) ou o apaga.
+pub fn descartar_arquivo_lido(
+	config: &DiretorioConfig,
+	caminho: &std::path::Path,
+) -> io::Result<()> {
+	let Some(pasta) = &config.pasta_arquivo else {
+		return fs::remove_file(caminho);
+	};
+	let pasta = std::path::Path::new(pasta);
+	fs::create_dir_all(pasta)?;
+	let nome = caminho.file_name().unwrap_or_default().to_string_lossy();
+	let mut destino = pasta.join(nome.as_ref());
+	if destino.exists() {
+		destino = pasta.join(format!("{}-{}", Local::now().format("%Y%m%d%H%M%S"), nome));
+	}
+	fs::rename(caminho, &destino).or_else(|_| {
+		// Pastas em sistemas de arquivos diferentes não aceitam rename
+		fs::copy(caminho, &destino)?;
+		fs::remove_file(caminho)
+	})
+}
+
+impl Coletor for DiretorioConfig {
+	fn tipo(&self) -> &'static str {
+		"pasta"
+	}
+
+	fn coletar<'a>(&'a self, contexto: &'a ContextoColeta<'a>) -> Coleta<'a> {
+		Box::pin(async move {
+			let (itens, lidos) =
+				coletar_diretorio(self).map_err(|e| format!("{}: {}", self.caminho, e))?;
+			contexto
+				.arquivos_lidos
+				.lock()
+				.unwrap()
+				.insert(self.nome.clone(), lidos);
+			Ok(itens)
+		})
+	}
+
+	/// Os arquivos lidos só saem da pasta com os itens já na fila; em simulação, ficam nela.
+	fn coleta_gravada(&self, contexto: &ContextoColeta) {
+		let lidos = contexto
+			.arquivos_lidos
+			.lock()
+			.unwrap()
+			.remove(&self.nome)
+			.unwrap_or_default();
+		if contexto.simulacao {
+			return;
+		}
+		for caminho in lidos {
+			if let Err(e) = descartar_arquivo_lido(self, &caminho) {
+				error!(
+					"Não foi possível arquivar/apagar '{}': {}",
+					caminho.display(),
+					e
+				);
+			}
+		}
+	}
+}
+
+/// Lê os arquivos .xml/.json/.txt da pasta monitorada, em ordem alfabética, e devolve os itens
+/// e os arquivos lidos, que a coleta descarta depois de gravar os itens. Arquivos modificados
+/// há menos de `ASSENTAMENTO_ARQUIVO` ficam para o próximo ciclo.
+pub fn coletar_diretorio(
+	config: &DiretorioConfig,
+) -> Result<(Vec<ItemNoticia>, Vec<std::path::PathBuf>), Box<dyn Error>> {
+	let mut arquivos: Vec<std::path::PathBuf> = fs::read_dir(&config.caminho)?
+		.filter_map(|entrada| entrada.ok().map(|e| e.path()))
+		.filter(|caminho| caminho.is_file())
+		.collect();
+	arquivos.sort();
+
+	let mut itens = Vec::new();
+	let mut lidos = Vec::new();
+	for caminho in arquivos {
+		let extensao = caminho
+			.extension()
+			.map(|e| e.to_string_lossy().to_lowercase())
+			.unwrap_or_default();
+		if !matches!(extensao.as_str(), "xml" | "json" | "txt") {
+			continue;
+		}
+		// Uma data no futuro também conta como recente
+		let assentado = fs::metadata(&caminho)
+			.and_then(|m| m.modified())
+			.ok()
+			.and_then(|m| m.elapsed().ok())
+			.is_some_and(|idade| idade >= ASSENTAMENTO_ARQUIVO);
+		if !assentado {
+			debug!(
+				"'{}' mudou há pouco; fica para o próximo ciclo.",
+				caminho.display()
+			);
+			continue;
+		}
+
+		match ler_arquivo_de_itens(&config.nome, &caminho, &extensao) {
+			Ok(novos) => {
+				itens.extend(novos);
+				lidos.push(caminho);
+			}
+			// O arquivo fica na pasta para ser corrigido
+			Err(e) => error!("Arquivo '{}' ignorado: {}", caminho.display(), e),
+		}
+	}
+	Ok((itens, lidos))
+}
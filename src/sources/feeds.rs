@@ -0,0 +1,421 @@
+//! Feeds RSS e RDF, com descoberta do feed em páginas HTML.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use quick_xml::{Reader as XmlReader, escape::resolve_predefined_entity, events::Event};
+use regex::Regex;
+use reqwest::{
+	Client, RequestBuilder, StatusCode,
+	header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+};
+use rss::{Channel, Guid, Item};
+use std::{error::Error, sync::Arc, time::Duration as StdDuration};
+use tracing::error;
+use url::Url;
+
+use super::{Coleta, Coletor, ContextoColeta, HTML_ATTR_REGEX_LAZY, Repeticao, clean_html_content};
+use crate::{config::*, pipeline::*, store::*};
+
+pub static LINK_TAG_REGEX_LAZY: Lazy<Regex> =
+	Lazy::new(|| Regex::new(r"(?is)<link\b[^>]*>").unwrap());
+
+pub async fn baixar_feed(
+	client: &Client,
+	url: &str,
+	autenticacao: Option<(&str, Option<&str>)>,
+) -> Result<bytes::Bytes, reqwest::Error> {
+	com_autenticacao(client.get(url), autenticacao)
+		.timeout(StdDuration::from_secs(20))
+		.send()
+		.await?
+		.bytes()
+		.await
+}
+
+/// Acrescenta o HTTP Basic do feed (`usuario`/`senha`), se houver, à requisição.
+pub fn com_autenticacao(
+	requisicao: RequestBuilder,
+	autenticacao: Option<(&str, Option<&str>)>,
+) -> RequestBuilder {
+	match autenticacao {
+		Some((usuario, senha)) => requisicao.basic_auth(usuario, senha),
+		None => requisicao,
+	}
+}
+
+/// Como `baixar_feed`, mas reenvia os validadores da última coleta. Devolve `None` quando o
+/// servidor responde 304 (nada mudou) e, caso contrário, o conteúdo com os validadores novos.
+pub async fn baixar_feed_condicional(
+	client: &Client,
+	repeticao: Repeticao,
+	url: &str,
+	anteriores: Option<&ValidadoresHttp>,
+	autenticacao: Option<(&str, Option<&str>)>,
+) -> Result<Option<(bytes::Bytes, ValidadoresHttp)>, Box<dyn Error>> {
+	let mut requisicao =
+		com_autenticacao(client.get(url), autenticacao).timeout(StdDuration::from_secs(20));
+	if let Some(anteriores) = anteriores {
+		if let Some(etag) = &anteriores.etag {
+			requisicao = requisicao.header(IF_NONE_MATCH, etag);
+		}
+		if let Some(data) = &anteriores.ultima_modificacao {
+			requisicao = requisicao.header(IF_MODIFIED_SINCE, data);
+		}
+	}
+	let resposta = repeticao.enviar(requisicao, url).await?;
+	if resposta.status() == StatusCode::NOT_MODIFIED {
+		return Ok(None);
+	}
+
+	// Só uma resposta de sucesso vale como referência para a próxima coleta
+	let cabecalho = |nome| {
+		resposta
+			.headers()
+			.get(nome)
+			.and_then(|v| v.to_str().ok())
+			.map(str::to_string)
+	};
+	let validadores = if resposta.status().is_success() {
+		ValidadoresHttp {
+			etag: cabecalho(ETAG),
+			ultima_modificacao: cabecalho(LAST_MODIFIED),
+		}
+	} else {
+		ValidadoresHttp::default()
+	};
+	Ok(Some((resposta.bytes().await?, validadores)))
+}
+
+/// Validadores guardados para a URL; uma falha do banco só faz a coleta ser completa.
+fn validadores_de(db: &sled::Db, url: &str) -> Option<ValidadoresHttp> {
+	db_validadores(db, url)
+		.inspect_err(|e| error!("Falha ao ler os validadores HTTP de '{}': {}", url, e))
+		.ok()
+		.flatten()
+}
+
+/// Verifica se o conteúdo baixado é uma página HTML em vez de um documento XML.
+pub fn parece_html(data: &[u8]) -> bool {
+	let inicio = String::from_utf8_lossy(&data[..data.len().min(1024)]).to_lowercase();
+	let inicio = inicio.trim_start_matches('\u{feff}').trim_start();
+	inicio.starts_with("<!doctype html") || inicio.starts_with("<html")
+}
+
+/// Extrai os feeds RSS anunciados com `<link rel="alternate" type="application/rss+xml">`,
+/// resolvendo links relativos contra a URL da página.
+pub fn descobrir_feeds_html(html: &str, base: &str) -> Vec<String> {
+	let base = Url::parse(base).ok();
+	let mut feeds = Vec::new();
+
+	for tag in LINK_TAG_REGEX_LAZY.find_iter(html) {
+		let mut rel = String::new();
+		let mut tipo = String::new();
+		let mut href = None;
+
+		for caps in HTML_ATTR_REGEX_LAZY.captures_iter(tag.as_str()) {
+			let valor = caps
+				.get(2)
+				.or_else(|| caps.get(3))
+				.or_else(|| caps.get(4))
+				.map(|v| v.as_str().trim().to_string())
+				.unwrap_or_default();
+			match caps[1].to_lowercase().as_str() {
+				"rel" => rel = valor.to_lowercase(),
+				"type" => tipo = valor.to_lowercase(),
+				"href" => href = Some(valor),
+				_ => {}
+			}
+		}
+
+		let is_feed_rss = tipo == "application/rss+xml" || tipo == "application/rdf+xml";
+		if !rel.split_whitespace().any(|r| r == "alternate") || !is_feed_rss {
+			continue;
+		}
+
+		let url = href.and_then(|h| match &base {
+			Some(b) => b.join(&h).ok().map(|u| u.to_string()),
+			None => Some(h),
+		});
+		if let Some(url) = url
+			&& !feeds.contains(&url)
+		{
+			feeds.push(url);
+		}
+	}
+
+	feeds
+}
+
+/// Detecta feeds RSS 1.0 (RDF), cujo elemento raiz é `<rdf:RDF>` com qualquer prefixo.
+pub fn is_rdf_feed(data: &[u8]) -> bool {
+	let mut reader = XmlReader::from_reader(data);
+	let mut buf = Vec::new();
+
+	loop {
+		match reader.read_event_into(&mut buf) {
+			Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+				return e.local_name().as_ref().eq_ignore_ascii_case(b"RDF");
+			}
+			Ok(Event::Eof) | Err(_) => return false,
+			_ => {}
+		}
+		buf.clear();
+	}
+}
+
+/// Aplica o conteúdo de um elemento filho de `<item>` do RDF ao item normalizado.
+pub fn rdf_aplicar_campo(item: &mut Item, campo: &str, texto: &str) {
+	let texto = texto.trim().to_string();
+	if texto.is_empty() {
+		return;
+	}
+
+	match campo {
+		"title" => item.set_title(texto),
+		"link" => item.set_link(texto),
+		"description" => item.set_description(texto),
+		"encoded" => item.set_content(texto),
+		"creator" => item.set_author(texto),
+		// dc:date (ISO 8601) é convertido para o formato RFC 2822 do <pubDate>
+		"date" => match DateTime::parse_from_rfc3339(&texto) {
+			Ok(dt) => item.set_pub_date(dt.to_rfc2822()),
+			Err(_) => item.set_pub_date(texto),
+		},
+		_ => {}
+	}
+}
+
+/// Analisa um feed RSS 1.0 (RDF), normalizando os itens na mesma estrutura dos feeds RSS 2.0.
+/// Itens sem `<link>` usam o atributo `rdf:about` como link.
+pub fn parse_rdf_feed(data: &[u8]) -> Result<Channel, Box<dyn Error>> {
+	let mut reader = XmlReader::from_reader(data);
+	let mut buf = Vec::new();
+
+	let mut items = Vec::new();
+	let mut atual: Option<(Item, Option<String>)> = None; // (item, rdf:about)
+	let mut campo: Option<String> = None;
+	let mut profundidade = 0; // Elementos aninhados dentro do campo atual
+	let mut texto = String::new();
+
+	loop {
+		match reader.read_event_into(&mut buf)? {
+			Event::Start(e) => {
+				let nome = String::from_utf8_lossy(e.local_name().as_ref()).to_lowercase();
+
+				if nome == "item" {
+					let sobre = e
+						.attributes()
+						.flatten()
+						.find(|a| a.key.local_name().as_ref().eq_ignore_ascii_case(b"about"))
+						.and_then(|a| {
+							a.decoded_and_normalized_value(
+								quick_xml::XmlVersion::Implicit1_0,
+								reader.decoder(),
+							)
+							.ok()
+							.map(|v| v.to_string())
+						});
+					atual = Some((Item::default(), sobre));
+				} else if campo.is_some() {
+					profundidade += 1;
+				} else if atual.is_some() {
+					campo = Some(nome);
+					texto.clear();
+				}
+			}
+			Event::Text(t) if campo.is_some() => texto.push_str(&t.decode()?),
+			Event::CData(c) if campo.is_some() => texto.push_str(&String::from_utf8_lossy(&c)),
+			Event::GeneralRef(r) if campo.is_some() => {
+				let entidade = r.decode()?;
+				if let Some(resolvida) = resolve_predefined_entity(&entidade) {
+					texto.push_str(resolvida);
+				} else if let Some(ch) = r.resolve_char_ref()? {
+					texto.push(ch);
+				}
+			}
+			Event::End(e) => {
+				if profundidade > 0 {
+					profundidade -= 1;
+				} else if let Some(nome_campo) = campo.take() {
+					if let Some((item, _)) = atual.as_mut() {
+						rdf_aplicar_campo(item, &nome_campo, &texto);
+					}
+				} else if e.local_name().as_ref().eq_ignore_ascii_case(b"item")
+					&& let Some((mut item, sobre)) = atual.take()
+				{
+					if let Some(sobre) = sobre {
+						if item.link().is_none() {
+							item.set_link(sobre.clone());
+						}
+						let mut guid = Guid::default();
+						guid.set_value(sobre);
+						item.set_guid(guid);
+					}
+					items.push(item);
+				}
+			}
+			Event::Eof => break,
+			_ => {}
+		}
+		buf.clear();
+	}
+
+	let mut channel = Channel::default();
+	channel.set_items(items);
+	Ok(channel)
+}
+
+impl Coletor for FeedConfig {
+	fn tipo(&self) -> &'static str {
+		"feed"
+	}
+
+	fn coletar<'a>(&'a self, contexto: &'a ContextoColeta<'a>) -> Coleta<'a> {
+		Box::pin(coletar_feed(
+			contexto.cliente_da_fonte(&self.nome),
+			contexto.repeticao,
+			contexto.db,
+			self,
+		))
+	}
+}
+
+/// Baixa o feed e extrai seus itens (a filtragem ocorre depois, em `filtrar_round_robin`).
+pub async fn coletar_feed(
+	client: &Client,
+	repeticao: Repeticao,
+	db: &Arc<sled::Db>,
+	feed: &FeedConfig,
+) -> Result<Vec<ItemNoticia>, Box<dyn std::error::Error>> {
+	// 1. Faz a requisição HTTP, condicional aos validadores da última coleta
+	// Falhas de rede sobem para `coletar_fontes`, que as registra e conta no ciclo
+	let mut url = feed.url.clone();
+	let Some((mut response, mut validadores)) = baixar_feed_condicional(
+		client,
+		repeticao,
+		&url,
+		validadores_de(db, &url).as_ref(),
+		feed.autenticacao_para(&url),
+	)
+	.await
+	.map_err(|e| format!("Erro de requisição: {}", e))?
+	else {
+		informar!("{}", msg!("feed.inalterado", feed.nome));
+		return Ok(Vec::new());
+	};
+
+	// 1.1. Autodescoberta: a URL é uma página HTML que anuncia o feed em <link rel="alternate">
+	if parece_html(&response) {
+		let descobertos = descobrir_feeds_html(&String::from_utf8_lossy(&response), &feed.url);
+		let Some(feed_url) = descobertos.first() else {
+			return Err(format!(
+				"A URL '{}' retornou HTML sem <link rel=\"alternate\"> de feed RSS.",
+				feed.url
+			)
+			.into());
+		};
+
+		informar!(
+			"{}",
+			msg!(
+				"feed.descoberto",
+				feed.url,
+				descobertos.join(", "),
+				feed_url
+			)
+		);
+
+		// Os validadores ficam com o feed descoberto; a página é sempre baixada por inteiro
+		url = feed_url.clone();
+		let Some(completa) = baixar_feed_condicional(
+			client,
+			repeticao,
+			&url,
+			validadores_de(db, &url).as_ref(),
+			feed.autenticacao_para(&url),
+		)
+		.await
+		.map_err(|e| format!("Erro de requisição: {}", e))?
+		else {
+			informar!("{}", msg!("feed.inalterado", feed.nome));
+			return Ok(Vec::new());
+		};
+		(response, validadores) = completa;
+	}
+
+	// 2. Analisa o XML (RSS 2.0 ou RSS 1.0/RDF); os validadores só valem para um feed legível
+	let channel = ler_canal(&response)?;
+	if let Err(e) = db_gravar_validadores(db, &url, &validadores) {
+		error!("Falha ao gravar os validadores HTTP de '{}': {}", url, e);
+	}
+	let mut itens = Vec::new();
+
+	// 3. Itera sobre os itens (notícias)
+	for item in channel.items() {
+		// --- FILTRAGEM DE DATA PARA ALERTAS (INMET) ---
+		if feed.nome.contains("INMET") && is_inmet_alert_expired(item) {
+			if let Some(link_str) = item.link()
+				&& let Err(e) = db_cache_as_irrelevant(db, link_str)
+			{
+				error!("Falha ao salvar alerta expirado no cache: {}", e);
+			}
+			continue;
+		}
+		// --------------------------------------------------
+
+		if let Some(noticia) = noticia_do_item_rss(&feed.nome, item) {
+			itens.push(noticia);
+		}
+	}
+
+	Ok(itens)
+}
+
+/// Analisa um documento RSS 2.0 ou RSS 1.0/RDF.
+pub fn ler_canal(conteudo: &[u8]) -> Result<Channel, Box<dyn Error>> {
+	if is_rdf_feed(conteudo) {
+		parse_rdf_feed(conteudo)
+	} else {
+		Ok(Channel::read_from(conteudo)?)
+	}
+}
+
+/// Converte um item RSS em `ItemNoticia` (itens sem link são descartados).
+pub fn noticia_do_item_rss(fonte: &str, item: &Item) -> Option<ItemNoticia> {
+	let link = item.link().unwrap_or_default().to_string();
+	if link.is_empty() {
+		return None;
+	}
+
+	// --- EXTRAÇÃO DE DADOS ---
+	let title = item.title().unwrap_or(&link).to_string();
+
+	let description_raw = item
+		.content()
+		.or_else(|| item.description())
+		.unwrap_or("")
+		.to_string();
+
+	let description = if description_raw.trim().starts_with("<ol>") {
+		"".to_string()
+	} else {
+		clean_html_content(&description_raw)
+	};
+	// --------------------------------------------------
+
+	let publicado = item
+		.pub_date()
+		.and_then(|d| DateTime::parse_from_rfc2822(d).ok())
+		.map(|d| d.with_timezone(&Utc));
+
+	Some(ItemNoticia {
+		fonte: fonte.to_string(),
+		chave: link.clone(),
+		link,
+		titulo: title,
+		descricao: description,
+		publicado,
+		guid: item.guid().map(|g| g.value().to_string()),
+		grupo: None,
+	})
+}
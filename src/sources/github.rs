@@ -0,0 +1,163 @@
+//! Releases e issues de repositórios do GitHub, pela API REST.
+
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+use std::{error::Error, time::Duration as StdDuration};
+
+use super::{Coleta, Coletor, ContextoColeta, Repeticao, clean_html_content};
+use crate::{config::*, pipeline::*};
+
+pub const GITHUB_API: &str = "https://api.github.com";
+// Limite de caracteres das notas de release/issues enviadas ao LLM
+pub const GITHUB_MAX_CORPO: usize = 4000;
+
+// --- Estruturas da API REST do GitHub ---
+
+#[derive(Debug, Deserialize)]
+pub struct GithubRelease {
+	pub html_url: String,
+	pub name: Option<String>,
+	pub tag_name: String,
+	pub body: Option<String>,
+	pub draft: bool,
+	pub published_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubIssue {
+	pub html_url: String,
+	pub title: String,
+	pub body: Option<String>,
+	pub created_at: DateTime<Utc>,
+	// Presente apenas quando o item é um Pull Request
+	pub pull_request: Option<serde::de::IgnoredAny>,
+}
+
+/// Requisição GET à API REST do GitHub, com token opcional e novas tentativas.
+pub async fn fetch_github_api<T: serde::de::DeserializeOwned>(
+	client: &Client,
+	repeticao: Repeticao,
+	url: &str,
+	token: Option<&str>,
+) -> Result<T, Box<dyn Error>> {
+	let mut request = client
+		.get(url)
+		.header("Accept", "application/vnd.github+json")
+		.header("X-GitHub-Api-Version", "2022-11-28")
+		.timeout(StdDuration::from_secs(20));
+
+	if let Some(token) = token {
+		request = request.bearer_auth(token);
+	}
+
+	let response = repeticao.enviar(request, url).await?;
+
+	if !response.status().is_success() {
+		return Err(format!(
+			"Erro de Status HTTP na API do GitHub ({}): {}",
+			url,
+			response.status()
+		)
+		.into());
+	}
+
+	Ok(response.json::<T>().await?)
+}
+
+/// Monta o item de um release/issue. As notas de release costumam ser longas: limitamos o texto
+/// enviado ao LLM.
+pub fn github_noticia(
+	repo: &GithubConfig,
+	link: String,
+	titulo: String,
+	corpo: Option<String>,
+	publicado: Option<DateTime<Utc>>,
+) -> ItemNoticia {
+	let descricao = clean_html_content(&corpo.unwrap_or_default())
+		.chars()
+		.take(GITHUB_MAX_CORPO)
+		.collect();
+
+	ItemNoticia {
+		fonte: repo.nome.clone(),
+		chave: link.clone(),
+		link,
+		titulo,
+		descricao,
+		publicado,
+		guid: None,
+		grupo: None,
+	}
+}
+
+impl Coletor for GithubConfig {
+	fn tipo(&self) -> &'static str {
+		"repositório GitHub"
+	}
+
+	fn coletar<'a>(&'a self, contexto: &'a ContextoColeta<'a>) -> Coleta<'a> {
+		Box::pin(fetch_github_items(
+			contexto.client,
+			contexto.repeticao,
+			self,
+		))
+	}
+}
+
+/// Converte releases ou issues de um repositório em itens de notícia.
+pub async fn fetch_github_items(
+	client: &Client,
+	repeticao: Repeticao,
+	repo: &GithubConfig,
+) -> Result<Vec<ItemNoticia>, Box<dyn Error>> {
+	let token = repo.token.as_deref();
+
+	let items = match repo.tipo {
+		GithubTipo::Releases => {
+			let url = format!(
+				"{}/repos/{}/releases?per_page=20",
+				GITHUB_API, repo.repositorio
+			);
+			let releases: Vec<GithubRelease> =
+				fetch_github_api(client, repeticao, &url, token).await?;
+
+			releases
+				.into_iter()
+				.filter(|r| !r.draft)
+				.map(|r| {
+					let nome = r
+						.name
+						.filter(|n| !n.trim().is_empty())
+						.unwrap_or(r.tag_name);
+					let title = format!("{} {}", repo.repositorio, nome);
+					github_noticia(repo, r.html_url, title, r.body, r.published_at)
+				})
+				.collect()
+		}
+		GithubTipo::Issues => {
+			let mut url = format!(
+				"{}/repos/{}/issues?state=open&per_page=20",
+				GITHUB_API, repo.repositorio
+			);
+			if let Some(rotulo) = &repo.rotulo {
+				url.push_str("&labels=");
+				url.push_str(
+					&url::form_urlencoded::byte_serialize(rotulo.as_bytes()).collect::<String>(),
+				);
+			}
+			let issues: Vec<GithubIssue> = fetch_github_api(client, repeticao, &url, token).await?;
+
+			issues
+				.into_iter()
+				.filter(|i| i.pull_request.is_none())
+				.map(|i| {
+					let title = format!("[{}] {}", repo.repositorio, i.title);
+					github_noticia(repo, i.html_url, title, i.body, Some(i.created_at))
+				})
+				.collect()
+		}
+	};
+
+	Ok(items)
+}
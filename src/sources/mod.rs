@@ -0,0 +1,346 @@
+//! Leitura das fontes: feeds RSS/RDF, sitemaps, GitHub, sites rastreados e pastas locais.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use regex::Regex;
+use reqwest::{Client, RequestBuilder, Response, StatusCode, header::RETRY_AFTER};
+use sled::Db;
+use std::{
+	collections::HashMap,
+	error::Error,
+	path::PathBuf,
+	pin::Pin,
+	sync::{Arc, Mutex},
+	time::Duration as StdDuration,
+};
+use tokio::time::{self, Instant};
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::{config::*, pipeline::*};
+
+pub mod crawlers;
+pub mod diretorios;
+pub mod feeds;
+pub mod github;
+pub mod sitemaps;
+
+pub static HTML_ATTR_REGEX_LAZY: Lazy<Regex> =
+	Lazy::new(|| Regex::new(r#"(?is)([a-z-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap());
+
+/// O que as fontes compartilham durante a coleta.
+pub struct ContextoColeta<'a> {
+	pub client: &'a Client,
+	// Clientes dos feeds e sitemaps com opções HTTP próprias, pelo nome da fonte
+	pub clientes_fonte: &'a HashMap<String, Client>,
+	pub db: &'a Arc<Db>,
+	// `--dry-run`: as fontes não alteram nada fora do banco (ex.: arquivos lidos das pastas)
+	pub simulacao: bool,
+	pub repeticao: Repeticao,
+	// Arquivos lidos das pastas monitoradas, por fonte; só saem da pasta depois que os itens
+	// deles são gravados na fila (ver `Coletor::coleta_gravada`)
+	pub arquivos_lidos: Mutex<HashMap<String, Vec<PathBuf>>>,
+}
+
+impl ContextoColeta<'_> {
+	/// Cliente HTTP da fonte: o próprio, se ela tiver opções HTTP, ou o padrão.
+	pub fn cliente_da_fonte(&self, nome: &str) -> &Client {
+		self.clientes_fonte.get(nome).unwrap_or(self.client)
+	}
+}
+
+/// Novas tentativas dos downloads das fontes (`[geral].tentativas_download`). Só as falhas
+/// transitórias são repetidas: erro de conexão (inclusive DNS), timeout, 5xx, 408 e 429. Um 404
+/// ou outro 4xx volta na hora, para quem chamou tratar. Cada tentativa respeita também o
+/// intervalo mínimo entre requisições ao mesmo host (`[geral].intervalo_minimo_host_ms`).
+#[derive(Debug, Clone, Copy)]
+pub struct Repeticao {
+	pub tentativas: u32,
+	pub espera_inicial: StdDuration,
+	pub intervalo_host: StdDuration,
+}
+
+impl Repeticao {
+	pub fn de(geral: &GeralConfig) -> Self {
+		Self {
+			tentativas: geral.tentativas_download,
+			espera_inicial: StdDuration::from_millis(geral.espera_tentativa_download_ms),
+			intervalo_host: StdDuration::from_millis(geral.intervalo_minimo_host_ms),
+		}
+	}
+
+	/// Espera antes da tentativa seguinte à `falhas`-ésima: a inicial dobrada a cada falha, mais
+	/// até metade dela ao acaso, para as fontes de um mesmo servidor não voltarem juntas.
+	pub fn espera(&self, falhas: u32) -> StdDuration {
+		let base = self.espera_inicial.saturating_mul(1 << falhas.min(16));
+		let folga = rand::rng().random_range(0..=base.as_millis() as u64 / 2);
+		base + StdDuration::from_millis(folga)
+	}
+
+	/// Envia a requisição, repetindo-a enquanto a falha for transitória e houver tentativas. Um
+	/// `Retry-After` (em 429 ou 503) pausa o host inteiro pelo tempo pedido; se a pausa passa de
+	/// `ESPERA_MAXIMA_PAUSA`, a resposta volta na hora e o host fica de fora até o fim dela.
+	pub async fn enviar(
+		&self,
+		requisicao: RequestBuilder,
+		url: &str,
+	) -> Result<Response, Box<dyn Error>> {
+		let mut falhas = 0;
+		loop {
+			esperar_vez_do_host(url, self.intervalo_host).await?;
+			// Uma requisição com corpo em fluxo não pode ser repetida
+			let Some(tentativa) = requisicao.try_clone() else {
+				return Ok(requisicao.send().await?);
+			};
+			let resultado = tentativa.send().await;
+			let (motivo, pausa) = match &resultado {
+				Ok(resposta) if falha_transitoria(resposta.status()) => {
+					(resposta.status().to_string(), espera_retry_after(resposta))
+				}
+				Err(e) if e.is_timeout() || e.is_connect() => (e.to_string(), None),
+				_ => return Ok(resultado?),
+			};
+			if let Some(pausa) = pausa {
+				pausar_host(url, pausa);
+			}
+			if falhas >= self.tentativas || pausa.is_some_and(|p| p > ESPERA_MAXIMA_PAUSA) {
+				return Ok(resultado?);
+			}
+			// Com `Retry-After`, a vez do host já cobre a espera
+			let espera = match pausa {
+				Some(_) => StdDuration::ZERO,
+				None => self.espera(falhas),
+			};
+			falhas += 1;
+			warn!(
+				"Falha transitória ao baixar '{}' ({}); tentativa {} de {} em {:.1?}.",
+				url,
+				motivo,
+				falhas + 1,
+				self.tentativas + 1,
+				pausa.unwrap_or(espera)
+			);
+			time::sleep(espera).await;
+		}
+	}
+}
+
+/// Maior pausa pedida por `Retry-After` que ainda é esperada no meio do ciclo. Acima dela, as
+/// requisições ao host falham na hora até a pausa acabar, e a fonte volta num ciclo seguinte.
+pub const ESPERA_MAXIMA_PAUSA: StdDuration = StdDuration::from_secs(60);
+
+/// Teto de uma pausa de host: o `Retry-After` vem do servidor e pode pedir qualquer coisa.
+pub const PAUSA_MAXIMA_HOST: StdDuration = StdDuration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy)]
+struct VezHost {
+	// Quando o host pode receber a próxima requisição, pelo intervalo mínimo
+	proxima: Instant,
+	// Fim da pausa pedida pelo servidor com `Retry-After`
+	pausa_ate: Option<Instant>,
+}
+
+static VEZ_POR_HOST: Lazy<Mutex<HashMap<String, VezHost>>> = Lazy::new(Default::default);
+
+// O host com a porta: um LLM local e uma fonte servida na mesma máquina não dividem a vez
+fn host_de(url: &str) -> Option<String> {
+	let url = Url::parse(url).ok()?;
+	let host = url.host_str()?;
+	Some(match url.port_or_known_default() {
+		Some(porta) => format!("{}:{}", host, porta),
+		None => host.to_string(),
+	})
+}
+
+/// Espera a vez do host da URL: requisições ao mesmo host (as dezenas de sub-sitemaps de um
+/// índice, vários feeds de um site) saem com pelo menos `intervalo` entre si, mesmo vindas de
+/// fontes coletadas ao mesmo tempo, e só depois de uma pausa pedida pelo servidor. Hosts
+/// diferentes não esperam uns pelos outros.
+pub async fn esperar_vez_do_host(url: &str, intervalo: StdDuration) -> Result<(), Box<dyn Error>> {
+	let Some(host) = host_de(url) else {
+		return Ok(());
+	};
+	let vez = {
+		let mut vezes = VEZ_POR_HOST.lock().unwrap();
+		let agora = Instant::now();
+		let anterior = vezes.get(&host).copied();
+		let pausa_ate = anterior.and_then(|v| v.pausa_ate).filter(|p| *p > agora);
+		if let Some(pausa_ate) = pausa_ate
+			&& pausa_ate - agora > ESPERA_MAXIMA_PAUSA
+		{
+			return Err(format!(
+				"{} pediu uma pausa (Retry-After); requisições suspensas por mais {:.0?}",
+				host,
+				pausa_ate - agora
+			)
+			.into());
+		}
+		let vez = anterior
+			.map_or(agora, |v| v.proxima.max(agora))
+			.max(pausa_ate.unwrap_or(agora));
+		vezes.insert(
+			host.clone(),
+			VezHost {
+				proxima: vez + intervalo,
+				pausa_ate,
+			},
+		);
+		vez
+	};
+	if vez > Instant::now() {
+		debug!(host, espera = ?(vez - Instant::now()), "Aguardando a vez do host");
+		time::sleep_until(vez).await;
+	}
+	Ok(())
+}
+
+/// Suspende as requisições ao host da URL por `duracao` (a partir de agora), até
+/// `PAUSA_MAXIMA_HOST`.
+pub fn pausar_host(url: &str, duracao: StdDuration) {
+	let Some(host) = host_de(url) else {
+		return;
+	};
+	let agora = Instant::now();
+	let ate = agora + duracao.min(PAUSA_MAXIMA_HOST);
+	let mut vezes = VEZ_POR_HOST.lock().unwrap();
+	let vez = vezes.entry(host).or_insert(VezHost {
+		proxima: agora,
+		pausa_ate: None,
+	});
+	vez.pausa_ate = Some(vez.pausa_ate.map_or(ate, |p| p.max(ate)));
+}
+
+/// Tempo pedido pelo cabeçalho `Retry-After`, em segundos ou como data HTTP, até
+/// `PAUSA_MAXIMA_HOST`.
+pub fn espera_retry_after(resposta: &Response) -> Option<StdDuration> {
+	let valor = resposta.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+	if let Ok(segundos) = valor.parse::<u64>() {
+		return Some(StdDuration::from_secs(segundos).min(PAUSA_MAXIMA_HOST));
+	}
+	let data = DateTime::parse_from_rfc2822(valor).ok()?;
+	Some(
+		(data.with_timezone(&Utc) - Utc::now())
+			.to_std()
+			.unwrap_or_default()
+			.min(PAUSA_MAXIMA_HOST),
+	)
+}
+
+pub fn falha_transitoria(status: StatusCode) -> bool {
+	status.is_server_error()
+		|| matches!(
+			status,
+			StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS
+		)
+}
+
+pub type Coleta<'a> = Pin<Box<dyn Future<Output = Result<Vec<ItemNoticia>, Box<dyn Error>>> + 'a>>;
+
+/// Um tipo de fonte configurado no TOML. A coleta só extrai os itens; chave de deduplicação,
+/// grupo e cota ficam a cargo de `coletar_fontes`, iguais para todas.
+pub trait Coletor: Fonte {
+	/// Tipo da fonte nas mensagens de erro, ex.: "feed".
+	fn tipo(&self) -> &'static str;
+
+	fn coletar<'a>(&'a self, contexto: &'a ContextoColeta<'a>) -> Coleta<'a>;
+
+	/// Linha do log ao fim de uma coleta bem-sucedida.
+	fn resumo_coleta(&self, quantidade: usize) -> String {
+		msg!("fonte.itens", quantidade)
+	}
+
+	/// Chamado quando os itens coletados já estão gravados na fila de itens (ou decididos na
+	/// primeira coleta), para a fonte liberar o que ficou retido até lá.
+	fn coleta_gravada(&self, _contexto: &ContextoColeta) {}
+}
+
+impl Coletor for FonteConfigurada<'_> {
+	fn tipo(&self) -> &'static str {
+		self.coletor().tipo()
+	}
+
+	fn coletar<'a>(&'a self, contexto: &'a ContextoColeta<'a>) -> Coleta<'a> {
+		self.coletor().coletar(contexto)
+	}
+
+	fn resumo_coleta(&self, quantidade: usize) -> String {
+		self.coletor().resumo_coleta(quantidade)
+	}
+
+	fn coleta_gravada(&self, contexto: &ContextoColeta) {
+		self.coletor().coleta_gravada(contexto)
+	}
+}
+
+impl<'f> FonteConfigurada<'f> {
+	fn coletor(&self) -> &'f dyn Coletor {
+		match *self {
+			FonteConfigurada::Feed(f) => f,
+			FonteConfigurada::Sitemap(s) => s,
+			FonteConfigurada::Github(g) => g,
+			FonteConfigurada::Diretorio(d) => d,
+			FonteConfigurada::Crawler(c) => c,
+		}
+	}
+}
+
+pub fn clean_html_content(html: &str) -> String {
+	let tag_regex = Regex::new(r"<[^>]*>").unwrap();
+	let clean_text = tag_regex.replace_all(html, " ").to_string();
+
+	clean_text
+		.replace(['\n', '\r'], " ")
+		.replace("  ", " ")
+		.replace("  ", " ")
+		.replace("📎", "")
+		.replace("https://", "")
+		.replace("http://", "")
+		.trim()
+		.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn resposta(retry_after: &str) -> Response {
+		Response::from(
+			http::Response::builder()
+				.status(429)
+				.header(RETRY_AFTER, retry_after)
+				.body("")
+				.unwrap(),
+		)
+	}
+
+	#[test]
+	fn retry_after_em_segundos_e_como_data() {
+		assert_eq!(
+			espera_retry_after(&resposta("120")),
+			Some(StdDuration::from_secs(120))
+		);
+		let daqui_a_uma_hora = (Utc::now() + chrono::Duration::hours(1)).to_rfc2822();
+		let espera = espera_retry_after(&resposta(&daqui_a_uma_hora)).unwrap();
+		assert!(espera > StdDuration::from_secs(3500) && espera <= StdDuration::from_secs(3600));
+		assert_eq!(
+			espera_retry_after(&resposta("Wed, 21 Oct 2015 07:28:00 GMT")),
+			Some(StdDuration::ZERO)
+		);
+		assert_eq!(espera_retry_after(&resposta("logo")), None);
+	}
+
+	#[test]
+	fn retry_after_enorme_fica_no_teto() {
+		assert_eq!(
+			espera_retry_after(&resposta("18446744073709551615")),
+			Some(PAUSA_MAXIMA_HOST)
+		);
+		assert_eq!(
+			espera_retry_after(&resposta("Fri, 31 Dec 9999 23:59:59 GMT")),
+			Some(PAUSA_MAXIMA_HOST)
+		);
+		// Não pode estourar o `Instant`, mesmo chamada direto
+		pausar_host("https://pausa.exemplo.com/feed", StdDuration::MAX);
+	}
+}
@@ -0,0 +1,272 @@
+//! Sitemaps XML (e índices de sitemaps), com descoberta pelo `robots.txt`.
+
+use async_recursion::async_recursion;
+use chrono::{Duration, Utc};
+use reqwest::Client;
+use sitemap::{
+	reader::{SiteMapEntity, SiteMapReader},
+	structs::LastMod,
+};
+use std::{error::Error, io::BufReader, time::Duration as StdDuration};
+use tracing::error;
+use url::Url;
+
+use super::{Coleta, Coletor, ContextoColeta, Repeticao};
+use crate::{config::*, pipeline::*};
+
+/// Função auxiliar para download do conteúdo (GZIP-aware, com timeout, novas tentativas e erro HTTP)
+pub async fn fetch_sitemap_content(
+	client: &Client,
+	repeticao: Repeticao,
+	url: &str,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+	// TIMEOUT FIXO REVERTIDO PARA 30s
+	let response = repeticao
+		.enviar(client.get(url).timeout(StdDuration::from_secs(30)), url)
+		.await?;
+
+	if !response.status().is_success() {
+		return Err(format!(
+			"Erro de Status HTTP ao baixar Sitemap ({}): {}",
+			url,
+			response.status()
+		)
+		.into());
+	}
+
+	// O .bytes() lida automaticamente com compressão GZIP (.xml.gz)
+	response
+		.bytes()
+		.await
+		.map(|b| b.to_vec())
+		.map_err(|e| e.into())
+}
+
+/// Uma URL sem caminho (apenas a raiz do site) indica que os sitemaps devem ser
+/// descobertos no robots.txt.
+pub fn is_raiz_do_site(url: &str) -> bool {
+	Url::parse(url)
+		.map(|u| u.path() == "/" && u.query().is_none())
+		.unwrap_or(false)
+}
+
+/// Lê as linhas `Sitemap:` do robots.txt do site.
+pub async fn descobrir_sitemaps_robots(
+	client: &Client,
+	repeticao: Repeticao,
+	raiz: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+	let robots_url = Url::parse(raiz)?.join("/robots.txt")?;
+	let conteudo = fetch_sitemap_content(client, repeticao, robots_url.as_str()).await?;
+
+	let sitemaps = String::from_utf8_lossy(&conteudo)
+		.lines()
+		.filter_map(|linha| {
+			let (chave, valor) = linha.split_once(':')?;
+			chave
+				.trim()
+				.eq_ignore_ascii_case("sitemap")
+				.then(|| valor.trim().to_string())
+		})
+		.filter(|url| validate_url(url).is_ok())
+		.collect();
+
+	Ok(sitemaps)
+}
+
+impl Coletor for SitemapConfig {
+	fn tipo(&self) -> &'static str {
+		"sitemap"
+	}
+
+	fn coletar<'a>(&'a self, contexto: &'a ContextoColeta<'a>) -> Coleta<'a> {
+		coletar_sitemap(
+			contexto.cliente_da_fonte(&self.nome),
+			contexto.repeticao,
+			self,
+			&self.url,
+		)
+	}
+
+	// O progresso dos sitemaps ocupa várias linhas
+	fn resumo_coleta(&self, quantidade: usize) -> String {
+		msg!("fonte.urls", quantidade)
+	}
+}
+
+/// Coleta as URLs de um Sitemap (ou Sitemap Index) de forma recursiva.
+/// Se a URL for a raiz do site, os sitemaps são descobertos no robots.txt.
+#[async_recursion]
+pub async fn coletar_sitemap(
+	client: &Client,
+	repeticao: Repeticao,
+	sitemap_config: &SitemapConfig,
+	url_para_baixar: &str,
+) -> Result<Vec<ItemNoticia>, Box<dyn Error>> {
+	let mut itens = Vec::new();
+
+	if is_raiz_do_site(url_para_baixar) {
+		let descobertos = match descobrir_sitemaps_robots(client, repeticao, url_para_baixar).await
+		{
+			Ok(urls) => urls,
+			Err(e) => {
+				error!(
+					"Falha ao ler o robots.txt de {} ({}): {}",
+					url_para_baixar, sitemap_config.nome, e
+				);
+				return Ok(itens);
+			}
+		};
+
+		informar!(
+			"{}",
+			msg!("sitemap.robots", descobertos.len(), url_para_baixar)
+		);
+
+		for sitemap_url in descobertos {
+			// Evita recursão infinita caso o robots.txt aponte para a própria raiz
+			if is_raiz_do_site(&sitemap_url) {
+				continue;
+			}
+			match coletar_sitemap(client, repeticao, sitemap_config, &sitemap_url).await {
+				Ok(sub_itens) => itens.extend(sub_itens),
+				Err(e) => error!("Falha ao processar {} (robots.txt): {}", sitemap_url, e),
+			}
+		}
+
+		return Ok(itens);
+	}
+
+	informar!("{}", msg!("sitemap.baixando", url_para_baixar));
+
+	// 1. Faz a requisição HTTP (Baixa o XML)
+	let sitemap_data = match fetch_sitemap_content(client, repeticao, url_para_baixar).await {
+		Ok(data) => data,
+		Err(e) => {
+			error!(
+				"Falha ao baixar {} ({}): {}",
+				url_para_baixar, sitemap_config.nome, e
+			);
+			return Ok(itens);
+		}
+	};
+
+	// Data de corte do <lastmod> (entradas sem data são sempre processadas)
+	let corte = sitemap_config
+		.idade_maxima_horas
+		.map(|h| Utc::now() - Duration::hours(h as i64));
+	let expirado = |lastmod: &LastMod| match (corte, lastmod) {
+		(Some(corte), LastMod::DateTime(dt)) => dt.with_timezone(&Utc) < corte,
+		_ => false,
+	};
+	let mut urls_antigas = 0;
+	let filtro_url = FiltroUrlSitemap::new(sitemap_config)?;
+	let mut urls_fora_do_padrao = 0;
+
+	// 2. Analisa o XML
+	let cursor = BufReader::new(sitemap_data.as_slice());
+	let reader = SiteMapReader::new(cursor);
+
+	for entity in reader {
+		match entity {
+			SiteMapEntity::Url(url_entry) => {
+				let link = url_entry
+					.loc
+					.get_url()
+					.map(|url| url.to_string())
+					.unwrap_or_else(|| {
+						error!("Entidade URL sem tag <loc> válida em {}", url_para_baixar);
+						"".to_string()
+					});
+
+				if link.is_empty() {
+					continue;
+				}
+
+				if expirado(&url_entry.lastmod) {
+					urls_antigas += 1;
+					continue;
+				}
+
+				if !filtro_url.aceita(&link) {
+					urls_fora_do_padrao += 1;
+					continue;
+				}
+
+				let last_modified_str = match &url_entry.lastmod {
+					LastMod::DateTime(dt) => dt.to_string(),
+					_ => "[N/A]".to_string(),
+				};
+
+				let title = format!("[Sitemap] {}", link);
+				let description = format!("Última modificação: {}", last_modified_str);
+
+				let publicado = match &url_entry.lastmod {
+					LastMod::DateTime(dt) => Some(dt.with_timezone(&Utc)),
+					_ => None,
+				};
+
+				itens.push(ItemNoticia {
+					fonte: sitemap_config.nome.clone(),
+					chave: link.clone(),
+					link,
+					titulo: title,
+					descricao: description,
+					publicado,
+					guid: None,
+					grupo: None,
+				});
+			}
+			SiteMapEntity::SiteMap(sitemap_url) => {
+				// RECURSÃO: Se for um Sitemap Index
+				let sub_url = sitemap_url
+					.loc
+					.get_url()
+					.map(|url| url.to_string())
+					.unwrap_or_else(|| {
+						error!(
+							"Sub-índice Sitemap sem tag <loc> válida em {}",
+							url_para_baixar
+						);
+						"".to_string()
+					});
+
+				if sub_url.is_empty() {
+					continue;
+				}
+
+				// Um sub-sitemap não modificado desde o corte só contém URLs antigas
+				if expirado(&sitemap_url.lastmod) {
+					continue;
+				}
+
+				// Chamamos a função recursivamente para o novo arquivo Sitemap
+				match coletar_sitemap(client, repeticao, sitemap_config, &sub_url).await {
+					Ok(sub_itens) => itens.extend(sub_itens),
+					Err(e) => error!("Falha ao processar sub-índice {}: {}", sub_url, e),
+				}
+			}
+			// Catch-all para outras entidades (como Image, Video, etc.)
+			_ => {
+				// Ignorado: Entidade do Sitemap não é URL nem Sitemap Index.
+			}
+		}
+	}
+
+	if urls_antigas > 0 {
+		informar!(
+			"{}",
+			msg!(
+				"sitemap.antigas",
+				urls_antigas,
+				sitemap_config.idade_maxima_horas.unwrap_or_default()
+			)
+		);
+	}
+
+	if urls_fora_do_padrao > 0 {
+		informar!("{}", msg!("sitemap.fora_do_padrao", urls_fora_do_padrao));
+	}
+
+	Ok(itens)
+}
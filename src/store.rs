@@ -0,0 +1,952 @@
+//! Banco sled do agente, arquivo de itens relevantes (com cifra opcional) e auditoria.
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::{DateTime, Utc};
+use ring::aead::{Aad, CHACHA20_POLY1305, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use sled::Db;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	error::Error,
+	fs, io,
+	path::Path,
+	process::Command,
+};
+use tracing::warn;
+use url::Url;
+
+use crate::{config::*, pipeline::*};
+
+pub const DB_PATH: &str = "filterflow_data";
+
+/// Pasta do banco sem `--db`: a `filterflow_data` da pasta atual, se existir, ou a de
+/// `$XDG_DATA_HOME/filterflow/` (padrão `~/.local/share/filterflow/`).
+pub fn caminho_banco_padrao() -> String {
+	if Path::new(DB_PATH).exists() {
+		return DB_PATH.to_string();
+	}
+	pasta_xdg("XDG_DATA_HOME", ".local/share")
+		.map(|pasta| pasta.join("filterflow").join(DB_PATH).display().to_string())
+		.unwrap_or_else(|| DB_PATH.to_string())
+}
+pub const IRRELEVANT_CACHE_TREE: &str = "irrelevant_cache";
+// Fontes já vistas (nome -> data da primeira coleta), usado por `primeira_execucao`
+pub const FONTES_CONHECIDAS_TREE: &str = "fontes_conhecidas";
+// Arquivo (fora do sled, que fica bloqueado pelo agente) com os itens numerados do ciclo atual
+pub const REFERENCIAS_FILE: &str = "filterflow_referencias.json";
+// Itens relevantes entregues (chave de deduplicação -> RegistroArquivo em JSON)
+pub const ARQUIVO_TREE: &str = "arquivo_relevantes";
+// Registro somente de acréscimo das ações manuais (id sequencial -> RegistroAuditoria em JSON)
+pub const AUDITORIA_TREE: &str = "auditoria";
+// Contadores acumulados (nome -> u64 big-endian), como as métricas da revisão de irrelevantes
+pub const METRICAS_TREE: &str = "metricas";
+// Itens aguardando o próximo resumo por e-mail (id sequencial -> RegistroArquivo em JSON)
+pub const FILA_EMAIL_TREE: &str = "fila_email";
+// Entregas que falharam, aguardando nova tentativa (id sequencial -> EntregaPendente em JSON)
+pub const FILA_ENTREGAS_TREE: &str = "fila_entregas";
+// Alertas com janela de vigência ainda no calendário (chave -> AlertaCalendario em JSON)
+pub const ALERTAS_TREE: &str = "alertas_calendario";
+// Contadores acumulados por fonte (nome -> EstatisticasFonte em JSON)
+pub const ESTATISTICAS_TREE: &str = "estatisticas_fontes";
+// Fontes pausadas pela API do painel (nome -> data da pausa em RFC 3339)
+pub const FONTES_PAUSADAS_TREE: &str = "fontes_pausadas";
+// Itens coletados ainda sem decisão do filtro ("fonte\0chave" -> RegistroFila em JSON)
+pub const FILA_ITENS_TREE: &str = "fila_itens";
+// Validadores HTTP da última coleta de cada feed (URL -> ValidadoresHttp em JSON)
+pub const VALIDADORES_TREE: &str = "validadores_http";
+
+// --- Referências numeradas do ciclo (comando `filterflow open <n>`) ---
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Referencia {
+	pub numero: u32,
+	pub titulo: String,
+	pub link: String,
+	#[serde(default)]
+	pub fonte: MetadadosFonte,
+	#[serde(default)]
+	pub resumo: Option<String>,
+}
+
+/// Item relevante entregue, guardado para reprocessamentos (ex.: `resummarize`).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegistroArquivo {
+	pub titulo: String,
+	pub link: String,
+	#[serde(default)]
+	pub descricao: String,
+	pub fonte: MetadadosFonte,
+	#[serde(default)]
+	pub publicado: Option<DateTime<Utc>>,
+	pub entregue_em: DateTime<Utc>,
+	#[serde(default)]
+	pub resumo: Option<String>,
+	// Modelo e versão do prompt que geraram o resumo atual
+	#[serde(default)]
+	pub modelo_resumo: Option<String>,
+	#[serde(default)]
+	pub versao_prompt: Option<String>,
+	// Descrição e resumo cifrados (ver [arquivo]); os campos em claro ficam vazios
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub cifrado: Option<String>,
+}
+
+/// Parte do registro arquivado que é cifrada quando `[arquivo].criptografar` está ativo.
+#[derive(Serialize, Deserialize)]
+pub struct ConteudoCifrado {
+	pub descricao: String,
+	pub resumo: Option<String>,
+}
+
+impl RegistroArquivo {
+	/// Cifra a descrição e o resumo, deixando em claro só o necessário para deduplicar e exportar.
+	pub fn selar(&mut self, cifra: &CifraArquivo) -> Result<(), Box<dyn Error>> {
+		let conteudo = ConteudoCifrado {
+			descricao: std::mem::take(&mut self.descricao),
+			resumo: self.resumo.take(),
+		};
+		self.cifrado = Some(cifra.cifrar(&serde_json::to_vec(&conteudo)?)?);
+		Ok(())
+	}
+
+	/// Decifra o registro na leitura. Registros em claro passam sem alteração.
+	pub fn abrir(&mut self, cifra: Option<&CifraArquivo>) -> Result<(), Box<dyn Error>> {
+		let Some(cifrado) = self.cifrado.take() else {
+			return Ok(());
+		};
+		let cifra = cifra
+			.ok_or("Item do arquivo cifrado: ative [arquivo].criptografar e defina a chave.")?;
+		let conteudo: ConteudoCifrado = serde_json::from_slice(&cifra.decifrar(&cifrado)?)?;
+		self.descricao = conteudo.descricao;
+		self.resumo = conteudo.resumo;
+		Ok(())
+	}
+}
+
+pub struct CifraArquivo(LessSafeKey);
+
+impl CifraArquivo {
+	/// Carrega a chave se a cifra estiver ativa.
+	pub fn carregar(config: &ArquivoConfig) -> Result<Option<CifraArquivo>, Box<dyn Error>> {
+		if !config.criptografar {
+			return Ok(None);
+		}
+		let texto = match std::env::var(&config.variavel_chave) {
+			Ok(texto) => texto,
+			Err(_) => chave_do_chaveiro().ok_or(format!(
+				"Chave do arquivo não encontrada: defina ${} ou guarde-a no chaveiro com \
+				 'secret-tool store --label=filterflow service filterflow chave arquivo'.",
+				config.variavel_chave
+			))?,
+		};
+		let texto = texto.trim();
+		let bytes = if texto.len() == 64 && texto.chars().all(|c| c.is_ascii_hexdigit()) {
+			(0..64)
+				.step_by(2)
+				.map(|i| u8::from_str_radix(&texto[i..i + 2], 16))
+				.collect::<Result<Vec<u8>, _>>()?
+		} else {
+			BASE64.decode(texto)?
+		};
+		let chave = UnboundKey::new(&CHACHA20_POLY1305, &bytes)
+			.map_err(|_| "A chave do arquivo deve ter 32 bytes (64 dígitos hexadecimais).")?;
+		Ok(Some(CifraArquivo(LessSafeKey::new(chave))))
+	}
+
+	/// Devolve nonce + texto cifrado + tag, em base64.
+	pub fn cifrar(&self, dados: &[u8]) -> Result<String, Box<dyn Error>> {
+		let mut nonce = [0u8; NONCE_LEN];
+		SystemRandom::new()
+			.fill(&mut nonce)
+			.map_err(|_| "Falha ao gerar o nonce.")?;
+		let mut cifrado = dados.to_vec();
+		self.0
+			.seal_in_place_append_tag(
+				Nonce::assume_unique_for_key(nonce),
+				Aad::empty(),
+				&mut cifrado,
+			)
+			.map_err(|_| "Falha ao cifrar o registro.")?;
+		let mut saida = nonce.to_vec();
+		saida.extend(cifrado);
+		Ok(BASE64.encode(saida))
+	}
+
+	pub fn decifrar(&self, texto: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+		let mut dados = BASE64.decode(texto)?;
+		if dados.len() < NONCE_LEN {
+			return Err("Registro cifrado truncado.".into());
+		}
+		let mut cifrado = dados.split_off(NONCE_LEN);
+		let nonce = Nonce::try_assume_unique_for_key(&dados).map_err(|_| "Nonce inválido.")?;
+		let aberto = self
+			.0
+			.open_in_place(nonce, Aad::empty(), &mut cifrado)
+			.map_err(|_| "Falha ao decifrar o registro (chave errada?).")?;
+		Ok(aberto.to_vec())
+	}
+}
+
+/// Decifra um texto selado com a chave do arquivo.
+fn abrir_texto(cifrado: &str, cifra: Option<&CifraArquivo>) -> Result<String, Box<dyn Error>> {
+	let cifra = cifra.ok_or("Registro cifrado: ative [arquivo].criptografar e defina a chave.")?;
+	Ok(String::from_utf8(cifra.decifrar(cifrado)?)?)
+}
+
+/// Busca a chave do arquivo no chaveiro do sistema (libsecret).
+pub fn chave_do_chaveiro() -> Option<String> {
+	segredo_do_chaveiro(&["chave", "arquivo"])
+}
+
+/// Segredo guardado no chaveiro do sistema (Secret Service, via `secret-tool`) com o atributo
+/// `service filterflow` e os demais atributos dados.
+pub fn segredo_do_chaveiro(atributos: &[&str]) -> Option<String> {
+	let saida = Command::new("secret-tool")
+		.args(["lookup", "service", "filterflow"])
+		.args(atributos)
+		.output()
+		.ok()?;
+	let segredo = String::from_utf8(saida.stdout).ok()?;
+	(saida.status.success() && !segredo.trim().is_empty()).then_some(segredo)
+}
+
+/// Item rejeitado pelo filtro, guardado no cache de irrelevância para a revisão de falsos negativos.
+/// Entradas antigas (ou marcadas à mão) guardam apenas "1" e ficam fora da revisão.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegistroIrrelevante {
+	pub fonte: String,
+	pub titulo: String,
+	pub link: String,
+	#[serde(default)]
+	pub descricao: String,
+	#[serde(default)]
+	pub grupo: Option<String>,
+	#[serde(default)]
+	pub publicado: Option<DateTime<Utc>>,
+	pub rejeitado_em: DateTime<Utc>,
+	// Já confirmado como irrelevante pelo modelo revisor
+	#[serde(default)]
+	pub revisado: bool,
+	// Descrição cifrada (ver [arquivo]); o campo em claro fica vazio
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub cifrado: Option<String>,
+}
+
+impl RegistroIrrelevante {
+	/// Cifra a descrição, como no arquivo de relevantes.
+	pub fn selar(&mut self, cifra: &CifraArquivo) -> Result<(), Box<dyn Error>> {
+		self.cifrado = Some(cifra.cifrar(std::mem::take(&mut self.descricao).as_bytes())?);
+		Ok(())
+	}
+
+	/// Decifra a descrição na leitura. Registros em claro passam sem alteração.
+	pub fn abrir(&mut self, cifra: Option<&CifraArquivo>) -> Result<(), Box<dyn Error>> {
+		if let Some(cifrado) = self.cifrado.take() {
+			self.descricao = abrir_texto(&cifrado, cifra)?;
+		}
+		Ok(())
+	}
+
+	pub fn noticia(&self, chave: &str) -> ItemNoticia {
+		ItemNoticia {
+			fonte: self.fonte.clone(),
+			link: self.link.clone(),
+			titulo: self.titulo.clone(),
+			descricao: self.descricao.clone(),
+			publicado: self.publicado,
+			guid: None,
+			chave: chave.to_string(),
+			grupo: self.grupo.clone(),
+		}
+	}
+}
+
+/// Item coletado à espera do filtro. Sai da fila quando recebe uma decisão (irrelevante,
+/// descartado, já visto ou entregue); uma falha do LLM ou a cota esgotada o mantêm lá, e ele
+/// volta no próximo ciclo da fonte mesmo que não apareça mais nela (ver `db_fila_da_fonte`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegistroFila {
+	pub link: String,
+	pub titulo: String,
+	#[serde(default)]
+	pub descricao: String,
+	#[serde(default)]
+	pub publicado: Option<DateTime<Utc>>,
+	#[serde(default)]
+	pub guid: Option<String>,
+	#[serde(default)]
+	pub grupo: Option<String>,
+	pub enfileirado_em: DateTime<Utc>,
+	// Descrição cifrada (ver [arquivo]); o campo em claro fica vazio
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub cifrado: Option<String>,
+}
+
+impl RegistroFila {
+	pub fn selar(&mut self, cifra: &CifraArquivo) -> Result<(), Box<dyn Error>> {
+		self.cifrado = Some(cifra.cifrar(std::mem::take(&mut self.descricao).as_bytes())?);
+		Ok(())
+	}
+
+	pub fn abrir(&mut self, cifra: Option<&CifraArquivo>) -> Result<(), Box<dyn Error>> {
+		if let Some(cifrado) = self.cifrado.take() {
+			self.descricao = abrir_texto(&cifrado, cifra)?;
+		}
+		Ok(())
+	}
+
+	pub fn noticia(&self, fonte: &str, chave: &str) -> ItemNoticia {
+		ItemNoticia {
+			fonte: fonte.to_string(),
+			link: self.link.clone(),
+			titulo: self.titulo.clone(),
+			descricao: self.descricao.clone(),
+			publicado: self.publicado,
+			guid: self.guid.clone(),
+			chave: chave.to_string(),
+			grupo: self.grupo.clone(),
+		}
+	}
+}
+
+/// `ETag` e `Last-Modified` da última resposta completa de um feed, reenviados como
+/// `If-None-Match` e `If-Modified-Since` na coleta seguinte.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ValidadoresHttp {
+	#[serde(default)]
+	pub etag: Option<String>,
+	#[serde(default)]
+	pub ultima_modificacao: Option<String>,
+}
+
+/// Valor das chaves da árvore principal: a fonte do item e quando ele foi processado. Entradas
+/// antigas (e as marcadas à mão ou recebidas de um par) guardam apenas "processed".
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegistroProcessado {
+	pub fonte: String,
+	pub processado_em: DateTime<Utc>,
+}
+
+/// Ação manual sobre itens ou caches, registrada na auditoria.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AcaoModeracao {
+	MarcarComoLido,
+	Reclassificar,
+	LimparCache,
+	Reprocessar,
+	PausarFonte,
+	RetomarFonte,
+}
+
+/// Interface pela qual a ação foi feita.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrigemAcao {
+	Cli,
+	Api,
+	// A API do painel chamada pelo `filterflow tui`
+	Tui,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegistroAuditoria {
+	pub quando: DateTime<Utc>,
+	pub acao: AcaoModeracao,
+	pub origem: OrigemAcao,
+	// Item, fonte ou seletor afetado
+	pub alvo: String,
+	#[serde(default)]
+	pub detalhe: Option<String>,
+}
+
+/// Identificação da fonte entregue junto com cada item, para que os consumidores possam
+/// agrupar e identificar visualmente os itens sem reinterpretar a URL.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MetadadosFonte {
+	pub nome: String,
+	#[serde(default)]
+	pub grupo: Option<String>,
+	#[serde(default)]
+	pub dominio: Option<String>,
+	#[serde(default)]
+	pub favicon: Option<String>,
+}
+
+impl MetadadosFonte {
+	pub fn de(noticia: &ItemNoticia) -> Self {
+		let dominio = Url::parse(&noticia.link).ok().and_then(|u| {
+			u.host_str()
+				.map(|h| h.trim_start_matches("www.").to_string())
+		});
+		let favicon = Url::parse(&noticia.link)
+			.ok()
+			.and_then(|u| u.join("/favicon.ico").ok())
+			.map(|u| u.to_string());
+		MetadadosFonte {
+			nome: noticia.fonte.clone(),
+			grupo: noticia.grupo.clone(),
+			dominio,
+			favicon,
+		}
+	}
+}
+
+// =================================================================
+// FUNÇÕES AUXILIARES E DB
+// =================================================================
+
+pub fn db_init_trees(db_path: &str) -> Result<sled::Db, sled::Error> {
+	let db = sled::open(db_path)?;
+	let _irrelevant_cache_tree = db.open_tree(IRRELEVANT_CACHE_TREE)?;
+	let _fontes_conhecidas_tree = db.open_tree(FONTES_CONHECIDAS_TREE)?;
+	let _arquivo_tree = db.open_tree(ARQUIVO_TREE)?;
+	let _auditoria_tree = db.open_tree(AUDITORIA_TREE)?;
+	let _metricas_tree = db.open_tree(METRICAS_TREE)?;
+	let _fila_email_tree = db.open_tree(FILA_EMAIL_TREE)?;
+	let _alertas_tree = db.open_tree(ALERTAS_TREE)?;
+	let _fila_entregas_tree = db.open_tree(FILA_ENTREGAS_TREE)?;
+	let _estatisticas_tree = db.open_tree(ESTATISTICAS_TREE)?;
+	let _fontes_pausadas_tree = db.open_tree(FONTES_PAUSADAS_TREE)?;
+	let _fila_itens_tree = db.open_tree(FILA_ITENS_TREE)?;
+	let _validadores_tree = db.open_tree(VALIDADORES_TREE)?;
+	Ok(db)
+}
+
+/// Marca o item como processado na árvore principal (ver `RegistroProcessado`).
+pub fn db_marcar_processado(db: &Db, noticia: &ItemNoticia) -> Result<(), Box<dyn Error>> {
+	let registro = RegistroProcessado {
+		fonte: noticia.fonte.clone(),
+		processado_em: Utc::now(),
+	};
+	db.insert(noticia.chave.as_bytes(), serde_json::to_vec(&registro)?)?;
+	Ok(())
+}
+
+/// Data da primeira coleta da fonte, ou None se ela nunca foi coletada.
+pub fn db_fonte_vista_em(db: &Db, nome: &str) -> Result<Option<DateTime<Utc>>, io::Error> {
+	let tree = db.open_tree(FONTES_CONHECIDAS_TREE)?;
+	Ok(tree.get(nome.as_bytes())?.and_then(|v| {
+		DateTime::parse_from_rfc3339(&String::from_utf8_lossy(&v))
+			.ok()
+			.map(|d| d.with_timezone(&Utc))
+	}))
+}
+
+/// Fontes pausadas, que o agente deixa de coletar até serem retomadas.
+pub fn db_fontes_pausadas(db: &Db) -> Result<BTreeSet<String>, io::Error> {
+	let tree = db.open_tree(FONTES_PAUSADAS_TREE)?;
+	tree.iter()
+		.keys()
+		.map(|k| Ok(String::from_utf8_lossy(&k?).into_owned()))
+		.collect()
+}
+
+/// Pausa (ou retoma) a fonte; devolve se o estado mudou.
+pub fn db_pausar_fonte(db: &Db, nome: &str, pausar: bool) -> Result<bool, io::Error> {
+	let tree = db.open_tree(FONTES_PAUSADAS_TREE)?;
+	let anterior = if pausar {
+		tree.insert(nome.as_bytes(), Utc::now().to_rfc3339().as_bytes())?
+	} else {
+		tree.remove(nome.as_bytes())?
+	};
+	tree.flush()?;
+	Ok(anterior.is_none() == pausar)
+}
+
+/// Corrige a decisão do filtro sobre o item: como irrelevante, ele sai dos processados e do
+/// arquivo e entra no cache de irrelevância; como relevante, sai do cache e volta a ser avaliado.
+pub fn db_reclassificar(db: &Db, chave: &str, relevante: bool) -> Result<(), io::Error> {
+	let irrelevantes = db.open_tree(IRRELEVANT_CACHE_TREE)?;
+	if relevante {
+		irrelevantes.remove(chave.as_bytes())?;
+	} else {
+		db.remove(chave.as_bytes())?;
+		db.open_tree(ARQUIVO_TREE)?.remove(chave.as_bytes())?;
+		irrelevantes.insert(chave.as_bytes(), b"1")?;
+	}
+	db.flush()?;
+	Ok(())
+}
+
+pub fn db_registrar_fonte(db: &Db, nome: &str, quando: DateTime<Utc>) -> Result<(), io::Error> {
+	let tree = db.open_tree(FONTES_CONHECIDAS_TREE)?;
+	tree.insert(nome.as_bytes(), quando.to_rfc3339().as_bytes())?;
+	tree.flush()?;
+	Ok(())
+}
+
+pub fn db_arquivar(
+	db: &Db,
+	chave: &str,
+	registro: &RegistroArquivo,
+	cifra: Option<&CifraArquivo>,
+) -> Result<(), Box<dyn Error>> {
+	let mut registro = registro.clone();
+	if let Some(cifra) = cifra {
+		registro.selar(cifra)?;
+	}
+	let tree = db.open_tree(ARQUIVO_TREE)?;
+	tree.insert(chave.as_bytes(), serde_json::to_vec(&registro)?)?;
+	tree.flush()?;
+	Ok(())
+}
+
+/// Lê um registro do arquivo, decifrando-o se necessário.
+pub fn db_ler_arquivo(
+	valor: &[u8],
+	cifra: Option<&CifraArquivo>,
+) -> Result<RegistroArquivo, Box<dyn Error>> {
+	let mut registro: RegistroArquivo = serde_json::from_slice(valor)?;
+	registro.abrir(cifra)?;
+	Ok(registro)
+}
+
+/// Chave para as árvores em ordem de chegada (auditoria e filas): o id do sled, mas sempre
+/// depois da última chave da árvore, que pode ter vindo de outro banco por `filterflow import`.
+pub fn db_novo_id(db: &Db, tree: &sled::Tree) -> Result<[u8; 8], sled::Error> {
+	let id = db.generate_id()?;
+	let ultimo = tree
+		.last()?
+		.and_then(|(chave, _)| <[u8; 8]>::try_from(chave.as_ref()).ok())
+		.map(u64::from_be_bytes);
+	Ok(match ultimo {
+		Some(ultimo) if ultimo >= id => ultimo + 1,
+		_ => id,
+	}
+	.to_be_bytes())
+}
+
+pub const VERSAO_COPIA_BANCO: u32 = 1;
+
+/// Cópia do banco inteiro (`filterflow export --format json`), independente do formato em
+/// disco do sled. Traz todas as árvores, com chaves e valores em base64 (algumas chaves são
+/// ids binários); os valores vão como estão no banco, então o arquivo cifrado continua cifrado.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CopiaBanco {
+	pub versao: u32,
+	pub exportado_em: DateTime<Utc>,
+	// Nome da árvore -> pares (chave, valor)
+	pub arvores: BTreeMap<String, Vec<(String, String)>>,
+}
+
+pub fn db_exportar(db: &Db) -> Result<CopiaBanco, Box<dyn Error>> {
+	let mut arvores = BTreeMap::new();
+	for nome in db.tree_names() {
+		let tree = db.open_tree(&nome)?;
+		let mut entradas = Vec::with_capacity(tree.len());
+		for entrada in tree.iter() {
+			let (chave, valor) = entrada?;
+			entradas.push((BASE64.encode(chave), BASE64.encode(valor)));
+		}
+		arvores.insert(String::from_utf8_lossy(&nome).into_owned(), entradas);
+	}
+	Ok(CopiaBanco {
+		versao: VERSAO_COPIA_BANCO,
+		exportado_em: Utc::now(),
+		arvores,
+	})
+}
+
+/// Grava no banco uma cópia de `db_exportar` e retorna quantas entradas gravou. Sem
+/// `substituir`, o banco precisa estar vazio; com ele, todas as árvores são esvaziadas antes.
+/// A cópia é decodificada inteira antes da primeira escrita, para que um arquivo corrompido
+/// não deixe o banco pela metade.
+pub fn db_importar(db: &Db, copia: &CopiaBanco, substituir: bool) -> Result<usize, Box<dyn Error>> {
+	if copia.versao > VERSAO_COPIA_BANCO {
+		return Err(format!(
+			"A cópia está na versão {}, mais nova que a suportada ({}).",
+			copia.versao, VERSAO_COPIA_BANCO
+		)
+		.into());
+	}
+	let mut arvores = Vec::with_capacity(copia.arvores.len());
+	for (nome, entradas) in &copia.arvores {
+		let mut lote = sled::Batch::default();
+		for (chave, valor) in entradas {
+			lote.insert(BASE64.decode(chave)?, BASE64.decode(valor)?);
+		}
+		arvores.push((nome, lote));
+	}
+
+	for nome in db.tree_names() {
+		let tree = db.open_tree(&nome)?;
+		if tree.is_empty() {
+			continue;
+		}
+		if !substituir {
+			return Err("O banco de destino já tem dados. Use outro --db ou --replace.".into());
+		}
+		tree.clear()?;
+	}
+	for (nome, lote) in arvores {
+		db.open_tree(nome)?.apply_batch(lote)?;
+	}
+	db.flush()?;
+	Ok(copia.arvores.values().map(Vec::len).sum())
+}
+
+/// Acrescenta uma ação manual à auditoria. As chaves são ids crescentes do sled: os registros
+/// ficam em ordem cronológica e nada neste programa os altera ou remove.
+pub fn db_auditar(
+	db: &Db,
+	acao: AcaoModeracao,
+	origem: OrigemAcao,
+	alvo: &str,
+	detalhe: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+	let registro = RegistroAuditoria {
+		quando: Utc::now(),
+		acao,
+		origem,
+		alvo: alvo.to_string(),
+		detalhe,
+	};
+	let tree = db.open_tree(AUDITORIA_TREE)?;
+	tree.insert(db_novo_id(db, &tree)?, serde_json::to_vec(&registro)?)?;
+	tree.flush()?;
+	Ok(())
+}
+
+/// Identifica o prompt de resumo em uso, para marcar os registros resumidos com ele.
+pub fn versao_prompt_resumo(config: &GeralConfig) -> String {
+	let prompt = format!(
+		"{}\u{0}{}",
+		config.prompt_system_resumo, config.prompt_user_resumo_template
+	);
+	format!("{:016x}", hash_fnv1a(&prompt))
+}
+
+pub fn db_is_irrelevant(db: &Db, link: &str) -> Result<bool, io::Error> {
+	let tree = db.open_tree(IRRELEVANT_CACHE_TREE)?;
+	let exists = tree.contains_key(link.as_bytes())?;
+	Ok(exists)
+}
+
+pub fn db_cache_as_irrelevant(db: &Db, link: &str) -> Result<(), io::Error> {
+	let tree = db.open_tree(IRRELEVANT_CACHE_TREE)?;
+	tree.insert(link.as_bytes(), b"1")?;
+	tree.flush()?;
+	Ok(())
+}
+
+/// Como `db_cache_as_irrelevant`, mas guarda o item para a revisão de falsos negativos.
+pub fn db_cache_noticia_irrelevante(
+	db: &Db,
+	noticia: &ItemNoticia,
+	cifra: Option<&CifraArquivo>,
+) -> Result<(), Box<dyn Error>> {
+	let mut registro = RegistroIrrelevante {
+		fonte: noticia.fonte.clone(),
+		titulo: noticia.titulo.clone(),
+		link: noticia.link.clone(),
+		descricao: noticia.descricao.clone(),
+		grupo: noticia.grupo.clone(),
+		publicado: noticia.publicado,
+		rejeitado_em: Utc::now(),
+		revisado: false,
+		cifrado: None,
+	};
+	if let Some(cifra) = cifra {
+		registro.selar(cifra)?;
+	}
+	let tree = db.open_tree(IRRELEVANT_CACHE_TREE)?;
+	tree.insert(noticia.chave.as_bytes(), serde_json::to_vec(&registro)?)?;
+	tree.flush()?;
+	Ok(())
+}
+
+/// Tira do cache de irrelevância as chaves que a entrega já marcou como processadas (itens
+/// aprovados pela revisão ou pelo reprocessamento) e devolve quantas saíram. As demais ficam
+/// no cache, para uma nova tentativa.
+pub fn db_descachear_processados<S: AsRef<str>>(
+	db: &Db,
+	chaves: &[S],
+) -> Result<usize, sled::Error> {
+	let tree = db.open_tree(IRRELEVANT_CACHE_TREE)?;
+	let mut removidas = 0;
+	for chave in chaves {
+		let chave = chave.as_ref().as_bytes();
+		if db.contains_key(chave)? {
+			tree.remove(chave)?;
+			removidas += 1;
+		}
+	}
+	tree.flush()?;
+	Ok(removidas)
+}
+
+fn chave_fila(fonte: &str, chave: &str) -> Vec<u8> {
+	format!("{}\0{}", fonte, chave).into_bytes()
+}
+
+/// Põe na fila de itens os que ainda não estão nela; os já enfileirados mantêm a data de entrada.
+pub fn db_enfileirar(
+	db: &Db,
+	noticias: &[ItemNoticia],
+	cifra: Option<&CifraArquivo>,
+) -> Result<(), Box<dyn Error>> {
+	let tree = db.open_tree(FILA_ITENS_TREE)?;
+	let agora = Utc::now();
+	for noticia in noticias {
+		let chave = chave_fila(&noticia.fonte, &noticia.chave);
+		if tree.contains_key(&chave)? {
+			continue;
+		}
+		let mut registro = RegistroFila {
+			link: noticia.link.clone(),
+			titulo: noticia.titulo.clone(),
+			descricao: noticia.descricao.clone(),
+			publicado: noticia.publicado,
+			guid: noticia.guid.clone(),
+			grupo: noticia.grupo.clone(),
+			enfileirado_em: agora,
+			cifrado: None,
+		};
+		if let Some(cifra) = cifra {
+			registro.selar(cifra)?;
+		}
+		let _ = tree.compare_and_swap(
+			chave,
+			None as Option<&[u8]>,
+			Some(serde_json::to_vec(&registro)?),
+		)?;
+	}
+	tree.flush()?;
+	Ok(())
+}
+
+/// Itens da fonte que continuam na fila, na ordem de entrada. Um registro que não pode ser
+/// decifrado é pulado com um aviso e fica na fila.
+pub fn db_fila_da_fonte(
+	db: &Db,
+	fonte: &str,
+	cifra: Option<&CifraArquivo>,
+) -> Result<Vec<ItemNoticia>, Box<dyn Error>> {
+	let tree = db.open_tree(FILA_ITENS_TREE)?;
+	let prefixo = chave_fila(fonte, "");
+	let mut itens = Vec::new();
+	for entrada in tree.scan_prefix(&prefixo) {
+		let (chave, valor) = entrada?;
+		let Ok(mut registro) = serde_json::from_slice::<RegistroFila>(&valor) else {
+			continue;
+		};
+		let chave = String::from_utf8_lossy(&chave[prefixo.len()..]).into_owned();
+		if let Err(e) = registro.abrir(cifra) {
+			warn!("Item '{}' da fila de '{}' ignorado: {}", chave, fonte, e);
+			continue;
+		}
+		itens.push((registro.enfileirado_em, registro.noticia(fonte, &chave)));
+	}
+	itens.sort_by_key(|(quando, _)| *quando);
+	Ok(itens.into_iter().map(|(_, noticia)| noticia).collect())
+}
+
+/// Tira o item da fila depois de decidido.
+pub fn db_desenfileirar(db: &Db, noticia: &ItemNoticia) -> Result<(), io::Error> {
+	db.open_tree(FILA_ITENS_TREE)?
+		.remove(chave_fila(&noticia.fonte, &noticia.chave))?;
+	Ok(())
+}
+
+/// Remove da fila os itens de fontes que saíram da configuração. Devolve quantos foram removidos.
+pub fn db_fila_limpar_orfaos(db: &Db, fontes: &BTreeSet<&str>) -> Result<usize, io::Error> {
+	let tree = db.open_tree(FILA_ITENS_TREE)?;
+	let mut removidos = 0;
+	for chave in tree.iter().keys() {
+		let chave = chave?;
+		let fonte = chave.split(|b| *b == 0).next().unwrap_or_default();
+		if !fontes.contains(String::from_utf8_lossy(fonte).as_ref()) {
+			tree.remove(&chave)?;
+			removidos += 1;
+		}
+	}
+	Ok(removidos)
+}
+
+pub fn db_validadores(db: &Db, url: &str) -> Result<Option<ValidadoresHttp>, io::Error> {
+	let tree = db.open_tree(VALIDADORES_TREE)?;
+	Ok(tree
+		.get(url.as_bytes())?
+		.and_then(|v| serde_json::from_slice(&v).ok()))
+}
+
+/// Guarda os validadores da resposta; uma resposta sem nenhum apaga os anteriores.
+pub fn db_gravar_validadores(
+	db: &Db,
+	url: &str,
+	validadores: &ValidadoresHttp,
+) -> Result<(), Box<dyn Error>> {
+	let tree = db.open_tree(VALIDADORES_TREE)?;
+	if *validadores == ValidadoresHttp::default() {
+		tree.remove(url.as_bytes())?;
+	} else {
+		tree.insert(url.as_bytes(), serde_json::to_vec(validadores)?)?;
+	}
+	Ok(())
+}
+
+/// Soma os contadores do ciclo aos acumulados da fonte.
+pub fn db_somar_estatisticas(
+	db: &Db,
+	fonte: &str,
+	ciclo: &EstatisticasFonte,
+) -> Result<(), Box<dyn Error>> {
+	let tree = db.open_tree(ESTATISTICAS_TREE)?;
+	let mut total: EstatisticasFonte = tree
+		.get(fonte.as_bytes())?
+		.and_then(|v| serde_json::from_slice(&v).ok())
+		.unwrap_or_default();
+	total.somar(ciclo);
+	tree.insert(fonte.as_bytes(), serde_json::to_vec(&total)?)?;
+	Ok(())
+}
+
+/// Soma `valor` ao contador `nome` e devolve o total acumulado.
+pub fn db_somar_metrica(db: &Db, nome: &str, valor: u64) -> Result<u64, io::Error> {
+	let tree = db.open_tree(METRICAS_TREE)?;
+	let total = tree
+		.update_and_fetch(nome.as_bytes(), |antigo| {
+			let atual = antigo
+				.and_then(|v| v.try_into().ok())
+				.map(u64::from_be_bytes)
+				.unwrap_or(0);
+			Some((atual + valor).to_be_bytes().to_vec())
+		})?
+		.and_then(|v| v.as_ref().try_into().ok())
+		.map(u64::from_be_bytes)
+		.unwrap_or(0);
+	tree.flush()?;
+	Ok(total)
+}
+
+// Métrica com o instante (segundos desde 1970, UTC) da última varredura de `[revisao]`
+const METRICA_ULTIMA_REVISAO: &str = "revisao_ultima_em";
+
+/// Instante da última varredura de `[revisao]`, guardado no banco para que `once` (em timers
+/// e no cron) respeite `intervalo_horas` entre as execuções.
+pub fn db_ultima_revisao(db: &Db) -> Result<Option<DateTime<Utc>>, sled::Error> {
+	Ok(db
+		.open_tree(METRICAS_TREE)?
+		.get(METRICA_ULTIMA_REVISAO)?
+		.and_then(|v| v.as_ref().try_into().ok())
+		.map(i64::from_be_bytes)
+		.and_then(|segundos| DateTime::from_timestamp(segundos, 0)))
+}
+
+pub fn db_registrar_revisao(db: &Db, quando: DateTime<Utc>) -> Result<(), sled::Error> {
+	let tree = db.open_tree(METRICAS_TREE)?;
+	tree.insert(METRICA_ULTIMA_REVISAO, &quando.timestamp().to_be_bytes())?;
+	tree.flush()?;
+	Ok(())
+}
+
+/// Lê as referências numeradas do último ciclo.
+pub fn referencias_carregar() -> Result<Vec<Referencia>, Box<dyn Error>> {
+	match fs::read_to_string(REFERENCIAS_FILE) {
+		Ok(conteudo) => Ok(serde_json::from_str(&conteudo)?),
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+		Err(e) => Err(e.into()),
+	}
+}
+
+/// Zera a numeração no início de cada ciclo.
+pub fn referencias_limpar() -> Result<(), Box<dyn Error>> {
+	fs::write(REFERENCIAS_FILE, "[]")?;
+	Ok(())
+}
+
+/// Registra um item relevante e retorna o número atribuído a ele no ciclo.
+pub fn referencias_adicionar(noticia: &ItemNoticia) -> Result<u32, Box<dyn Error>> {
+	let mut referencias = referencias_carregar()?;
+	let numero = referencias.len() as u32 + 1;
+	referencias.push(Referencia {
+		numero,
+		titulo: noticia.titulo.clone(),
+		link: noticia.link.clone(),
+		fonte: MetadadosFonte::de(noticia),
+		resumo: None,
+	});
+	fs::write(
+		REFERENCIAS_FILE,
+		serde_json::to_string_pretty(&referencias)?,
+	)?;
+	Ok(numero)
+}
+
+/// Associa o resumo gerado pelo LLM ao item numerado.
+pub fn referencias_definir_resumo(numero: u32, resumo: &str) -> Result<(), Box<dyn Error>> {
+	let mut referencias = referencias_carregar()?;
+	if let Some(referencia) = referencias.iter_mut().find(|r| r.numero == numero) {
+		referencia.resumo = Some(resumo.to_string());
+		fs::write(
+			REFERENCIAS_FILE,
+			serde_json::to_string_pretty(&referencias)?,
+		)?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn banco() -> Db {
+		sled::Config::new().temporary(true).open().unwrap()
+	}
+
+	#[test]
+	fn exportar_e_importar_preservam_as_arvores() {
+		let origem = banco();
+		origem.insert(b"chave", b"processed").unwrap();
+		let tree = origem.open_tree(ARQUIVO_TREE).unwrap();
+		tree.insert([0u8, 1, 2, 255], b"binario".as_slice())
+			.unwrap();
+
+		// Passa pelo JSON, como em `filterflow export` e `filterflow import`
+		let copia: CopiaBanco =
+			serde_json::from_str(&serde_json::to_string(&db_exportar(&origem).unwrap()).unwrap())
+				.unwrap();
+		let destino = banco();
+		assert_eq!(db_importar(&destino, &copia, false).unwrap(), 2);
+		assert_eq!(
+			destino.get(b"chave").unwrap().as_deref(),
+			Some(b"processed".as_slice())
+		);
+		assert_eq!(
+			destino
+				.open_tree(ARQUIVO_TREE)
+				.unwrap()
+				.get([0u8, 1, 2, 255])
+				.unwrap()
+				.as_deref(),
+			Some(b"binario".as_slice())
+		);
+	}
+
+	#[test]
+	fn ultima_revisao_fica_no_banco() {
+		let db = banco();
+		assert_eq!(db_ultima_revisao(&db).unwrap(), None);
+		let quando = DateTime::from_timestamp(1_790_000_000, 0).unwrap();
+		db_registrar_revisao(&db, quando).unwrap();
+		assert_eq!(db_ultima_revisao(&db).unwrap(), Some(quando));
+	}
+
+	#[test]
+	fn importar_so_substitui_banco_com_dados_se_pedido() {
+		let origem = banco();
+		origem.insert(b"nova", b"processed").unwrap();
+		let copia = db_exportar(&origem).unwrap();
+
+		let destino = banco();
+		destino.insert(b"antiga", b"processed").unwrap();
+		assert!(db_importar(&destino, &copia, false).is_err());
+		assert!(destino.contains_key(b"antiga").unwrap());
+
+		db_importar(&destino, &copia, true).unwrap();
+		assert!(!destino.contains_key(b"antiga").unwrap());
+		assert!(destino.contains_key(b"nova").unwrap());
+	}
+}
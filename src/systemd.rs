@@ -0,0 +1,138 @@
+//! Integração com o systemd no modo `--daemon`: avisos do protocolo sd_notify (prontidão,
+//! estado e watchdog) e a unidade gerada por `filterflow systemd-unit`.
+
+use std::{
+	env,
+	error::Error,
+	path::Path,
+	sync::atomic::{AtomicBool, Ordering},
+	time::Duration as StdDuration,
+};
+use tokio::time;
+use tracing::error;
+
+// Se o agente avançou desde o último "WATCHDOG=1" (ver `sinal_de_vida`)
+static PROGRESSO: AtomicBool = AtomicBool::new(true);
+// Se o agente está parado de propósito, esperando o próximo ciclo
+static EM_ESPERA: AtomicBool = AtomicBool::new(false);
+
+/// Envia uma mensagem sd_notify (ex.: "READY=1", "STATUS=...") ao socket de `NOTIFY_SOCKET`.
+/// Sem a variável (processo fora do systemd ou unidade sem `Type=notify`), não faz nada.
+pub fn notificar(estado: &str) {
+	let Some(socket) = env::var_os("NOTIFY_SOCKET") else {
+		return;
+	};
+	if let Err(e) = enviar_notificacao(&socket.to_string_lossy(), estado) {
+		error!("Falha ao notificar o systemd: {}", e);
+	}
+}
+
+#[cfg(target_os = "linux")]
+fn enviar_notificacao(socket: &str, estado: &str) -> Result<(), std::io::Error> {
+	use std::os::linux::net::SocketAddrExt;
+	use std::os::unix::net::{SocketAddr, UnixDatagram};
+
+	let datagrama = UnixDatagram::unbound()?;
+	// Nomes começados por '@' ficam no espaço abstrato do Linux
+	let endereco = match socket.strip_prefix('@') {
+		Some(nome) => SocketAddr::from_abstract_name(nome.as_bytes())?,
+		None => SocketAddr::from_pathname(socket)?,
+	};
+	datagrama.send_to_addr(estado.as_bytes(), &endereco)?;
+	Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn enviar_notificacao(_socket: &str, _estado: &str) -> Result<(), std::io::Error> {
+	Ok(())
+}
+
+/// Intervalo dos avisos "WATCHDOG=1": metade do `WatchdogSec` da unidade, ou None se o
+/// watchdog não está ativo para este processo.
+pub fn intervalo_watchdog() -> Option<StdDuration> {
+	let micros: u64 = env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+	if let Ok(pid) = env::var("WATCHDOG_PID")
+		&& pid.parse() != Ok(std::process::id())
+	{
+		return None;
+	}
+	Some(StdDuration::from_micros(micros / 2))
+}
+
+/// Marca um avanço do agente: uma fonte coletada, uma resposta do LLM, um ciclo concluído.
+pub fn sinal_de_vida() {
+	PROGRESSO.store(true, Ordering::Relaxed);
+}
+
+/// Marca o início (`true`) e o fim (`false`) da espera entre os ciclos.
+pub fn em_espera(esperando: bool) {
+	EM_ESPERA.store(esperando, Ordering::Relaxed);
+	PROGRESSO.store(true, Ordering::Relaxed);
+}
+
+/// Envia "WATCHDOG=1" a cada `intervalo`, mas só se o agente avançou desde o aviso anterior ou
+/// está esperando o próximo ciclo. Um ciclo travado (não só o processo) deixa o `WatchdogSec`
+/// vencer, e o systemd reinicia o agente.
+pub async fn vigiar(intervalo: StdDuration) {
+	let mut relogio = time::interval(intervalo);
+	loop {
+		relogio.tick().await;
+		if PROGRESSO.swap(false, Ordering::Relaxed) || EM_ESPERA.load(Ordering::Relaxed) {
+			notificar("WATCHDOG=1");
+		}
+	}
+}
+
+/// Texto da unidade `filterflow.service` que roda este executável no modo `--daemon`, com os
+/// caminhos absolutos da configuração e do banco. A pasta de trabalho é a atual, onde ficam os
+/// caminhos relativos da configuração (saídas, referências numeradas). Com `usuario`, a unidade
+/// é de usuário (`~/.config/systemd/user`); sem ele, de sistema, rodando como o usuário atual.
+pub fn unidade(
+	caminho_config: &Path,
+	caminho_banco: &Path,
+	usuario: bool,
+) -> Result<String, Box<dyn Error>> {
+	let executavel = env::current_exe()?;
+	let pasta = env::current_dir()?;
+	let absoluto = |caminho: &Path| {
+		if caminho.is_absolute() {
+			caminho.to_path_buf()
+		} else {
+			pasta.join(caminho)
+		}
+	};
+	// Os argumentos vão entre aspas, para caminhos com espaços
+	let aspas = |caminho: &Path| format!("\"{}\"", caminho.display());
+
+	let mut servico = format!(
+		"Type=notify\nExecStart={} --config {} --db {} --daemon\nExecReload=/bin/kill -HUP $MAINPID\n\
+		 WorkingDirectory={}\n",
+		aspas(&executavel),
+		aspas(&absoluto(caminho_config)),
+		aspas(&absoluto(caminho_banco)),
+		pasta.display(),
+	);
+	if !usuario && let Ok(nome) = env::var("USER") {
+		servico.push_str(&format!("User={}\n", nome));
+	}
+	servico.push_str(
+		"Restart=on-failure\nRestartSec=30\nWatchdogSec=600\n\
+		 # A chave do arquivo cifrado ([arquivo]), se usada por variável de ambiente:\n\
+		 # Environment=FILTERFLOW_CHAVE_ARQUIVO=...\n",
+	);
+
+	// O gerenciador de usuário não tem network-online.target
+	let (rede, alvo) = if usuario {
+		("", "default.target")
+	} else {
+		(
+			"Wants=network-online.target\nAfter=network-online.target\n",
+			"multi-user.target",
+		)
+	};
+	Ok(format!(
+		"[Unit]\nDescription=FilterFlow: agente de notícias com LLM local\n{}\n\
+		 [Service]\n{}\n[Install]\nWantedBy={}\n",
+		rede, servico, alvo
+	))
+}
@@ -0,0 +1,595 @@
+//! `filterflow tui`: interface interativa no terminal para o agente em execução, cliente da
+//! API de controle do painel (`[painel].token`). Mostra os itens relevantes à medida que chegam,
+//! a situação de cada fonte e a latência do LLM; pelas teclas, abre o item no navegador, marca-o
+//! como classificado errado ou pede um ciclo.
+//!
+//! A tela é desenhada com sequências ANSI na tela alternativa do terminal, que fica sem eco e
+//! sem modo de linha (`stty`) enquanto a interface roda.
+
+use chrono::{DateTime, Local};
+use reqwest::Client;
+use std::{
+	collections::{HashSet, VecDeque},
+	error::Error,
+	fmt::Write as _,
+	io::{self, IsTerminal, Read, Write},
+	process::{Command, Stdio},
+	time::Duration as StdDuration,
+};
+use tokio::{sync::mpsc, time};
+
+use crate::{
+	BOLD, BOLD_GREEN, BOLD_RED, BOLD_YELLOW, RESET, painel::CABECALHO_CLIENTE,
+	pipeline::EstatisticasFonte,
+};
+
+// Médias de latência guardadas para o gráfico, uma por ciclo
+const HISTORICO_LATENCIA: usize = 40;
+
+/// Cliente da API de controle do painel.
+pub struct ClienteApi {
+	client: Client,
+	base: String,
+	token: String,
+}
+
+impl ClienteApi {
+	pub fn novo(base: &str, token: &str) -> Result<Self, Box<dyn Error>> {
+		Ok(ClienteApi {
+			// O agente costuma estar na mesma máquina: o proxy do sistema não se aplica
+			client: Client::builder()
+				.no_proxy()
+				.timeout(StdDuration::from_secs(10))
+				.build()?,
+			base: base.trim_end_matches('/').to_string(),
+			token: token.to_string(),
+		})
+	}
+
+	async fn pedir(
+		&self,
+		metodo: reqwest::Method,
+		caminho: &str,
+	) -> Result<serde_json::Value, Box<dyn Error>> {
+		let resposta = self
+			.client
+			.request(metodo, format!("{}{}", self.base, caminho))
+			.bearer_auth(&self.token)
+			.header(CABECALHO_CLIENTE, "tui")
+			.send()
+			.await?;
+		let status = resposta.status();
+		let corpo: serde_json::Value = resposta.json().await.unwrap_or_default();
+		// O /healthz responde 503 com o mesmo documento quando o ciclo está atrasado
+		if !status.is_success() && status != reqwest::StatusCode::SERVICE_UNAVAILABLE {
+			return Err(corpo["erro"]
+				.as_str()
+				.map_or_else(|| format!("HTTP {}", status), str::to_string)
+				.into());
+		}
+		Ok(corpo)
+	}
+
+	pub async fn obter(&self, caminho: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+		self.pedir(reqwest::Method::GET, caminho).await
+	}
+
+	pub async fn enviar(&self, caminho: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+		self.pedir(reqwest::Method::POST, caminho).await
+	}
+}
+
+/// Item relevante na lista da interface.
+pub struct ItemTela {
+	pub chave: String,
+	pub titulo: String,
+	pub link: String,
+	pub fonte: String,
+	pub entregue_em: Option<DateTime<Local>>,
+	// Chegou depois que a interface abriu
+	pub novo: bool,
+}
+
+/// Linha da tabela de fontes, com os contadores do último ciclo.
+pub struct FonteTela {
+	pub nome: String,
+	pub pausada: bool,
+	pub falha_coleta: bool,
+	pub ultima_coleta: Option<DateTime<Local>>,
+	pub ciclo: EstatisticasFonte,
+}
+
+/// Estado da interface, atualizado pela API a cada poucos segundos.
+#[derive(Default)]
+pub struct Tela {
+	pub itens: Vec<ItemTela>,
+	vistos: HashSet<String>,
+	// Chegaram depois da primeira leitura; continuam destacados até a interface fechar
+	novos: HashSet<String>,
+	pub fontes: Vec<FonteTela>,
+	pub ultimo_ciclo: Option<String>,
+	pub llm: Option<(bool, String)>,
+	pub latencias: VecDeque<u64>,
+	pub selecionado: usize,
+	pub aviso: String,
+	carregada: bool,
+}
+
+impl Tela {
+	/// Relê itens, fontes e saúde do agente. Uma falha fica no rodapé e a tela mantém os dados
+	/// anteriores.
+	pub async fn atualizar(&mut self, api: &ClienteApi) {
+		match self.carregar(api).await {
+			Ok(()) => {
+				if self.aviso.starts_with("Falha") {
+					self.aviso.clear();
+				}
+			}
+			Err(e) => self.aviso = format!("Falha ao consultar o agente: {}", e),
+		}
+	}
+
+	async fn carregar(&mut self, api: &ClienteApi) -> Result<(), Box<dyn Error>> {
+		let itens = api.obter("/items?limit=100").await?;
+		let fontes = api.obter("/sources").await?;
+		let saude = api.obter("/healthz").await?;
+
+		let chave_selecionada = self.itens.get(self.selecionado).map(|i| i.chave.clone());
+		for chave in itens
+			.as_array()
+			.into_iter()
+			.flatten()
+			.filter_map(|item| item["chave"].as_str())
+		{
+			if self.carregada && !self.vistos.contains(chave) {
+				self.novos.insert(chave.to_string());
+			}
+			self.vistos.insert(chave.to_string());
+		}
+		self.carregada = true;
+		self.itens = itens
+			.as_array()
+			.into_iter()
+			.flatten()
+			.map(|item| {
+				let chave = item["chave"].as_str().unwrap_or_default().to_string();
+				ItemTela {
+					novo: self.novos.contains(&chave),
+					titulo: item["titulo"].as_str().unwrap_or_default().to_string(),
+					link: item["link"].as_str().unwrap_or_default().to_string(),
+					fonte: item["fonte"]["nome"]
+						.as_str()
+						.unwrap_or_default()
+						.to_string(),
+					entregue_em: item["entregue_em"]
+						.as_str()
+						.and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+						.map(|d| d.with_timezone(&Local)),
+					chave,
+				}
+			})
+			.collect();
+		// A seleção acompanha o item, não a posição, quando chegam itens novos no topo
+		if let Some(chave) = chave_selecionada
+			&& let Some(posicao) = self.itens.iter().position(|i| i.chave == chave)
+		{
+			self.selecionado = posicao;
+		}
+		self.selecionado = self.selecionado.min(self.itens.len().saturating_sub(1));
+
+		self.fontes = fontes
+			.as_array()
+			.into_iter()
+			.flatten()
+			.map(|fonte| FonteTela {
+				nome: fonte["nome"].as_str().unwrap_or_default().to_string(),
+				pausada: fonte["pausada"].as_bool().unwrap_or_default(),
+				falha_coleta: fonte["falha_coleta"].as_bool().unwrap_or_default(),
+				ultima_coleta: fonte["ultima_coleta"]
+					.as_str()
+					.and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+					.map(|d| d.with_timezone(&Local)),
+				ciclo: serde_json::from_value(fonte["ultimo_ciclo"].clone()).unwrap_or_default(),
+			})
+			.collect();
+
+		let ciclo = saude["ultimo_ciclo"].as_str().map(str::to_string);
+		if ciclo.is_some() && ciclo != self.ultimo_ciclo {
+			self.latencias.push_back(self.latencia_media_ms());
+			if self.latencias.len() > HISTORICO_LATENCIA {
+				self.latencias.pop_front();
+			}
+		}
+		self.ultimo_ciclo = ciclo;
+		self.llm = Some((
+			saude["llm"]["ok"].as_bool().unwrap_or_default(),
+			saude["llm"]["detalhe"]
+				.as_str()
+				.unwrap_or_default()
+				.to_string(),
+		));
+		Ok(())
+	}
+
+	/// Latência média das chamadas ao LLM no último ciclo, somando todas as fontes.
+	pub fn latencia_media_ms(&self) -> u64 {
+		let (total, chamadas) = self.fontes.iter().fold((0, 0), |(t, c), f| {
+			(t + f.ciclo.latencia_llm_ms, c + f.ciclo.chamadas_llm)
+		});
+		total.checked_div(chamadas).unwrap_or(0)
+	}
+
+	/// Trata uma tecla; devolve false para sair.
+	pub async fn tecla(&mut self, tecla: Tecla, api: &ClienteApi) -> bool {
+		match tecla {
+			Tecla::Sair => return false,
+			Tecla::Acima => self.selecionado = self.selecionado.saturating_sub(1),
+			Tecla::Abaixo => {
+				self.selecionado = (self.selecionado + 1).min(self.itens.len().saturating_sub(1));
+			}
+			Tecla::Abrir => {
+				if let Some(item) = self.itens.get(self.selecionado) {
+					self.aviso = match crate::cli::abrir_no_navegador(&item.link) {
+						Ok(()) => format!("Aberto no navegador: {}", item.link),
+						Err(e) => format!("Falha ao abrir o navegador: {}", e),
+					};
+				}
+			}
+			Tecla::MarcarErrado => {
+				if let Some(item) = self.itens.get(self.selecionado) {
+					let caminho = format!(
+						"/items/reclassify?{}",
+						url::form_urlencoded::Serializer::new(String::new())
+							.append_pair("key", &item.chave)
+							.append_pair("class", "irrelevante")
+							.finish()
+					);
+					match api.enviar(&caminho).await {
+						Ok(_) => {
+							self.aviso = format!("Marcado como irrelevante: {}", item.titulo);
+							self.itens.remove(self.selecionado);
+							self.selecionado =
+								self.selecionado.min(self.itens.len().saturating_sub(1));
+						}
+						Err(e) => self.aviso = format!("Falha ao reclassificar: {}", e),
+					}
+				}
+			}
+			Tecla::Ciclo => {
+				self.aviso = match api.enviar("/cycle/run").await {
+					Ok(_) => "Novo ciclo pedido ao agente.".to_string(),
+					Err(e) => format!("Falha ao pedir o ciclo: {}", e),
+				};
+			}
+			Tecla::Outra => {}
+		}
+		true
+	}
+
+	/// Quadro completo para um terminal de `linhas` x `colunas`: cabeçalho, itens, fontes,
+	/// latência do LLM e rodapé com as teclas.
+	pub fn desenhar(&self, base: &str, linhas: usize, colunas: usize) -> String {
+		let mut quadro = String::from("\x1b[H");
+		let linha = |quadro: &mut String, texto: &str| {
+			quadro.push_str(texto);
+			quadro.push_str(&format!("{}\x1b[K\r\n", RESET));
+		};
+
+		let llm = match &self.llm {
+			Some((true, _)) => format!("{}ok{}", BOLD_GREEN, RESET),
+			Some((false, detalhe)) => format!("{}{}{}", BOLD_RED, detalhe, RESET),
+			None => "?".to_string(),
+		};
+		let ciclo = self
+			.ultimo_ciclo
+			.as_deref()
+			.and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+			.map_or("-".to_string(), |d| {
+				d.with_timezone(&Local).format("%H:%M:%S").to_string()
+			});
+		linha(
+			&mut quadro,
+			&format!(
+				"{}FilterFlow{} {} · último ciclo {} · LLM {}",
+				BOLD,
+				RESET,
+				cortar(base, colunas / 3),
+				ciclo,
+				llm
+			),
+		);
+
+		// A tabela de fontes ocupa até um terço da tela; os itens ficam com o resto
+		let linhas_fontes = (self.fontes.len() + 2).min(linhas / 3).max(2);
+		let linhas_itens = linhas.saturating_sub(1 + 1 + linhas_fontes + 3 + 1).max(1);
+
+		linha(
+			&mut quadro,
+			&format!("{}Itens relevantes ({}){}", BOLD, self.itens.len(), RESET),
+		);
+		let inicio = self
+			.selecionado
+			.saturating_sub(linhas_itens.saturating_sub(1));
+		for posicao in inicio..inicio + linhas_itens {
+			let Some(item) = self.itens.get(posicao) else {
+				linha(&mut quadro, "");
+				continue;
+			};
+			let hora = item
+				.entregue_em
+				.map_or("--:--".to_string(), |d| d.format("%d/%m %H:%M").to_string());
+			let texto = cortar(
+				&format!(
+					"{} {} {} [{}] {}",
+					if posicao == self.selecionado {
+						">"
+					} else {
+						" "
+					},
+					if item.novo { "●" } else { " " },
+					hora,
+					item.fonte,
+					item.titulo
+				),
+				colunas,
+			);
+			if posicao == self.selecionado {
+				linha(&mut quadro, &format!("\x1b[7m{}", texto));
+			} else if item.novo {
+				linha(&mut quadro, &format!("{}{}", BOLD_GREEN, texto));
+			} else {
+				linha(&mut quadro, &texto);
+			}
+		}
+
+		let largura = self
+			.fontes
+			.iter()
+			.map(|f| f.nome.chars().count())
+			.max()
+			.unwrap_or(0)
+			.clamp(5, 30);
+		linha(
+			&mut quadro,
+			&cortar(
+				&format!(
+					"{}{:<largura$} {:<9} {:>6} {:>9} {:>10} {:>6} {:>12}{}",
+					BOLD,
+					"Fonte",
+					"situação",
+					"coleta",
+					"coletados",
+					"relevantes",
+					"erros",
+					"latência LLM",
+					RESET
+				),
+				colunas + BOLD.len() + RESET.len(),
+			),
+		);
+		for fonte in self.fontes.iter().take(linhas_fontes - 1) {
+			let (situacao, cor) = if fonte.pausada {
+				("pausada", BOLD_YELLOW)
+			} else if fonte.falha_coleta {
+				("falha", BOLD_RED)
+			} else {
+				("ok", "")
+			};
+			let texto = format!(
+				"{:<largura$} {:<9} {:>6} {:>9} {:>10} {:>6} {:>9} ms",
+				cortar(&fonte.nome, largura),
+				situacao,
+				fonte
+					.ultima_coleta
+					.map_or("-".to_string(), |d| d.format("%H:%M").to_string()),
+				fonte.ciclo.coletados,
+				fonte.ciclo.relevantes,
+				fonte.ciclo.erros,
+				fonte.ciclo.latencia_media_ms()
+			);
+			linha(&mut quadro, &format!("{}{}", cor, cortar(&texto, colunas)));
+		}
+		for _ in self.fontes.len().min(linhas_fontes - 1)..linhas_fontes - 1 {
+			linha(&mut quadro, "");
+		}
+
+		linha(
+			&mut quadro,
+			&format!(
+				"{}Latência do LLM{}: {} ms em média no último ciclo",
+				BOLD,
+				RESET,
+				self.latencia_media_ms()
+			),
+		);
+		linha(
+			&mut quadro,
+			&cortar(&grafico(self.latencias.iter().copied()), colunas),
+		);
+		linha(&mut quadro, "");
+		quadro.push_str(&cortar(
+			&format!(
+				"↑/↓ navegar · o abrir · x classificado errado · r novo ciclo · q sair   {}",
+				self.aviso
+			),
+			colunas,
+		));
+		quadro.push_str("\x1b[K\x1b[J");
+		quadro
+	}
+}
+
+/// Tecla lida do terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tecla {
+	Acima,
+	Abaixo,
+	Abrir,
+	MarcarErrado,
+	Ciclo,
+	Sair,
+	Outra,
+}
+
+/// Traduz uma leitura do terminal (uma tecla ou uma sequência de escape) em teclas.
+pub fn interpretar_teclas(bytes: &[u8]) -> Vec<Tecla> {
+	match bytes {
+		b"\x1b[A" | b"\x1bOA" => return vec![Tecla::Acima],
+		b"\x1b[B" | b"\x1bOB" => return vec![Tecla::Abaixo],
+		_ => {}
+	}
+	bytes
+		.iter()
+		.map(|b| match b {
+			b'k' => Tecla::Acima,
+			b'j' => Tecla::Abaixo,
+			b'o' | b'\r' | b'\n' => Tecla::Abrir,
+			b'x' => Tecla::MarcarErrado,
+			b'r' => Tecla::Ciclo,
+			// Ctrl-C chega como byte com o `-isig`
+			b'q' | 3 => Tecla::Sair,
+			_ => Tecla::Outra,
+		})
+		.collect()
+}
+
+/// Gráfico de barras em uma linha (▁ a █) das latências, proporcional à maior.
+pub fn grafico(valores: impl Iterator<Item = u64>) -> String {
+	const BARRAS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+	let valores: Vec<u64> = valores.collect();
+	let maximo = valores.iter().copied().max().unwrap_or(0);
+	let mut texto: String = valores
+		.iter()
+		.map(|v| BARRAS[(*v * 7).checked_div(maximo).unwrap_or(0) as usize])
+		.collect();
+	if !valores.is_empty() {
+		let _ = write!(texto, "  (máx. {} ms)", maximo);
+	}
+	texto
+}
+
+/// Corta o texto em `largura` caracteres (não conta as sequências de cor).
+fn cortar(texto: &str, largura: usize) -> String {
+	let mut saida = String::with_capacity(texto.len());
+	let mut visiveis = 0;
+	let mut caracteres = texto.chars();
+	while let Some(c) = caracteres.next() {
+		if c == '\x1b' {
+			saida.push(c);
+			for c in caracteres.by_ref() {
+				saida.push(c);
+				if c.is_ascii_alphabetic() {
+					break;
+				}
+			}
+			continue;
+		}
+		if visiveis == largura {
+			break;
+		}
+		saida.push(c);
+		visiveis += 1;
+	}
+	saida
+}
+
+/// Modo do terminal durante a interface; o anterior volta no `Drop`, inclusive em erro.
+struct TerminalCru {
+	anterior: String,
+}
+
+impl TerminalCru {
+	fn ativar() -> Result<Self, Box<dyn Error>> {
+		let anterior = stty(&["-g"])?;
+		stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+		// Tela alternativa e cursor oculto
+		print!("\x1b[?1049h\x1b[?25l");
+		io::stdout().flush()?;
+		Ok(TerminalCru {
+			anterior: anterior.trim().to_string(),
+		})
+	}
+}
+
+impl Drop for TerminalCru {
+	fn drop(&mut self) {
+		print!("\x1b[?25h\x1b[?1049l");
+		let _ = io::stdout().flush();
+		let _ = stty(&[self.anterior.as_str()]);
+	}
+}
+
+fn stty(argumentos: &[&str]) -> Result<String, Box<dyn Error>> {
+	let saida = Command::new("stty")
+		.args(argumentos)
+		.stdin(Stdio::inherit())
+		.output()?;
+	if !saida.status.success() {
+		return Err(format!(
+			"stty falhou: {}",
+			String::from_utf8_lossy(&saida.stderr).trim()
+		)
+		.into());
+	}
+	Ok(String::from_utf8_lossy(&saida.stdout).into_owned())
+}
+
+/// Linhas e colunas do terminal (padrão 24 x 80 se o `stty size` falhar).
+fn tamanho_terminal() -> (usize, usize) {
+	stty(&["size"])
+		.ok()
+		.and_then(|s| {
+			let (linhas, colunas) = s.trim().split_once(' ')?;
+			Some((linhas.parse().ok()?, colunas.parse().ok()?))
+		})
+		.filter(|&(linhas, colunas)| linhas > 0 && colunas > 0)
+		.unwrap_or((24, 80))
+}
+
+/// Roda a interface até `q` (ou Ctrl-C), relendo a API a cada 3 segundos.
+pub async fn rodar_tela(api: ClienteApi) -> Result<(), Box<dyn Error>> {
+	if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+		return Err("filterflow tui precisa de um terminal interativo.".into());
+	}
+	let _terminal = TerminalCru::ativar()?;
+
+	// A leitura do stdin bloqueia: fica numa thread própria
+	let (envio, mut teclas) = mpsc::unbounded_channel();
+	std::thread::spawn(move || {
+		let mut entrada = io::stdin().lock();
+		let mut buffer = [0u8; 16];
+		while let Ok(lidos) = entrada.read(&mut buffer) {
+			if lidos == 0 {
+				break;
+			}
+			for tecla in interpretar_teclas(&buffer[..lidos]) {
+				if envio.send(tecla).is_err() {
+					return;
+				}
+			}
+		}
+	});
+
+	let mut tela = Tela::default();
+	let mut tamanho = tamanho_terminal();
+	let mut relogio = time::interval(StdDuration::from_secs(3));
+	loop {
+		tokio::select! {
+			_ = relogio.tick() => {
+				tela.atualizar(&api).await;
+				tamanho = tamanho_terminal();
+			}
+			tecla = teclas.recv() => match tecla {
+				Some(tecla) => {
+					if !tela.tecla(tecla, &api).await {
+						break;
+					}
+				}
+				None => break,
+			},
+		}
+		print!("{}", tela.desenhar(&api.base, tamanho.0, tamanho.1));
+		io::stdout().flush()?;
+	}
+	Ok(())
+}